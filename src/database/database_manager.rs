@@ -2,10 +2,9 @@ use std::collections::{HashMap};
 use std::fs::File;
 use std::io::prelude::*;
 use serde::{Deserialize, Serialize};
-use serde::de::DeserializeOwned;
-use bincode::{deserialize, serialize};
 use crate::error::{Result, NollaDBError};
 use crate::database::Database;
+use crate::database::migration::{self, Migrate};
 
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
 pub struct DatabaseManager {
@@ -35,21 +34,21 @@ impl DatabaseManager {
   }
 
   // 从磁盘读取到内存
-  pub fn read<T: DeserializeOwned>(filename: String, new_data: &impl Serialize) -> Result<T> {
+  pub fn read<T: Migrate>(filename: String, new_data: &impl Serialize) -> Result<T> {
     // 先看 filename 在不在，不在就创建这个 file
-    if let Err(_) = File::open(filename.clone()) {
+    if File::open(filename.clone()).is_err() {
       println!("{} creating...", filename);
       DatabaseManager::write_data(
         &filename.to_string(),
         new_data,
-      );
+      )?;
       println!("creating {} done", filename);
     }
 
     match
       DatabaseManager::read_data(&filename.to_string()) {
         Ok(data) => Ok(data),
-        Err(error) => return Err(error),
+        Err(error) => Err(error),
     }
   }
 
@@ -58,23 +57,32 @@ impl DatabaseManager {
     DatabaseManager::write_data(
       &filename.to_string(),
       data,
-    );
-    Ok(())
+    )
   }
 
-  fn write_data(filename: &str, data: &impl Serialize) {
-    let filename = format!("{}", filename);
-    let bytes: Vec<u8> = serialize(&data).unwrap();
-    let mut file = File::create(filename).unwrap();
-    file.write_all(&bytes).unwrap();
+  fn write_data(filename: &str, data: &impl Serialize) -> Result<()> {
+    let filename = filename.to_string();
+    let bytes: Vec<u8> = migration::encode(data)?;
+    let mut file = File::create(filename)
+      .map_err(|error| NollaDBError::Internal(error.to_string()))?;
+    file
+      .write_all(&bytes)
+      .map_err(|error| NollaDBError::Internal(error.to_string()))
   }
 
-  fn read_data<T: DeserializeOwned>(filename: &str) -> Result<T> {
-      let filename = format!("{}", filename);
-      let mut file = File::open(filename).unwrap();
+  // 旧版本的文件读出来之后会转换成当前的格式，退出时保存就变成当前版本的文件了
+  fn read_data<T: Migrate>(filename: &str) -> Result<T> {
+      let filename = filename.to_string();
+      let mut file = File::open(filename.clone())
+        .map_err(|error| NollaDBError::Internal(error.to_string()))?;
       let mut buffer = Vec::<u8>::new();
-      file.read_to_end(&mut buffer).unwrap();
-      let decoded: T = deserialize(&buffer[..]).unwrap();
+      file
+        .read_to_end(&mut buffer)
+        .map_err(|error| NollaDBError::Internal(error.to_string()))?;
+      let (decoded, version): (T, u32) = migration::decode(&buffer[..])?;
+      if version != migration::FILE_VERSION {
+        println!("{} migrated from file version {} to {}", filename, version, migration::FILE_VERSION);
+      }
       Ok(decoded)
   }
 }
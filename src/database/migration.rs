@@ -0,0 +1,365 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use bincode::{deserialize, serialize};
+
+use crate::error::{Result, NollaDBError};
+use crate::database::Database;
+use crate::database::database_manager::DatabaseManager;
+use crate::table::Table;
+use crate::table::row::Row;
+use crate::table::column::Column;
+use crate::table::column::data_type::DataType;
+use crate::table::column::index::{Index, IndexTree};
+
+// 文件开头的标记，后面跟着 u32 的版本号，然后才是 bincode 编码的数据
+// 旧的文件没有这个标记，一开头就是 database name 或者 HashMap 的长度 (u64)
+// 这 8 个字节当作长度的话远远超过了文件的大小，所以不会和旧的文件混淆
+const FILE_MAGIC: &[u8; 8] = b"NOLLADB\0";
+
+// 版本 0：最初发布的格式，没有文件头，INTEGER 用 i32 存，REAL 用 f32 存，没有 NULL
+// 版本 1：当前的格式，INTEGER 用 i64 存，REAL 用 f64 存，DEFAULT 和 CHECK 的表达式存成 SQL 文本
+pub const FILE_VERSION: u32 = 1;
+
+// 能从旧版本的文件中读取出来的数据
+pub trait Migrate: DeserializeOwned {
+  // 版本 0 的文件中对应的数据结构
+  type LegacyData: DeserializeOwned;
+
+  fn from_legacy_data(legacy_data: Self::LegacyData) -> Self;
+}
+
+// 加上文件头之后编码成写入文件的字节
+pub fn encode(data: &impl Serialize) -> Result<Vec<u8>> {
+  let mut bytes = FILE_MAGIC.to_vec();
+  bytes.extend_from_slice(&FILE_VERSION.to_le_bytes());
+  bytes.extend(
+    serialize(data).map_err(|error| NollaDBError::Internal(error.to_string()))?
+  );
+  Ok(bytes)
+}
+
+// 解码文件中读出来的字节，返回解码之后的数据以及文件的版本号
+// 旧版本的文件会被转换成当前的数据结构，下次保存的时候就是当前的版本了
+pub fn decode<T: Migrate>(bytes: &[u8]) -> Result<(T, u32)> {
+  let (version, payload) = match bytes.strip_prefix(&FILE_MAGIC[..]) {
+    Some(rest) if rest.len() >= 4 => {
+      let (version, payload) = rest.split_at(4);
+      (u32::from_le_bytes([version[0], version[1], version[2], version[3]]), payload)
+    },
+    Some(_) => return Err(NollaDBError::Internal("Invalid file header".to_string())),
+    None => (0, bytes),
+  };
+
+  let data = match version {
+    0 => T::from_legacy_data(
+      deserialize(payload).map_err(|_| NollaDBError::Internal(
+        "File has no version header and is not in the version 0 format".to_string()
+      ))?
+    ),
+    FILE_VERSION => deserialize(payload).map_err(|error| NollaDBError::Internal(error.to_string()))?,
+    _ => return Err(NollaDBError::Internal(
+      format!("File version {} is newer than the supported version {}", version, FILE_VERSION)
+    )),
+  };
+
+  Ok((data, version))
+}
+
+impl Migrate for Database {
+  type LegacyData = LegacyDatabase;
+
+  fn from_legacy_data(legacy_data: LegacyDatabase) -> Self {
+    legacy_data.into()
+  }
+}
+
+impl Migrate for DatabaseManager {
+  type LegacyData = LegacyDatabaseManager;
+
+  fn from_legacy_data(legacy_data: LegacyDatabaseManager) -> Self {
+    DatabaseManager {
+      database: legacy_data
+        .database
+        .into_iter()
+        .map(|(database_name, database)| (database_name, database.into()))
+        .collect(),
+    }
+  }
+}
+
+// 下面是版本 0 的数据结构，字段以及 variant 的顺序都要和当时保持一致，bincode 是按照顺序解码的
+// DataType 当时只有前 6 个 variant，后来的类型都加在最后面，所以可以直接用 DataType 解码
+// f32 转换成 f64 的时候先转成最短的十进制表示，这样写入时的 1.1 读出来还是 1.1，而不是 1.100000023841858
+fn get_real_from_legacy(r: f32) -> f64 {
+  r.to_string().parse::<f64>().unwrap_or(r as f64)
+}
+
+// 只有 PRIMARY KEY 的列才有索引，Integer 和 Text 以外的 PRIMARY KEY 不会建索引
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub enum LegacyIndex {
+  Integer(BTreeMap<i32, i64>),
+  Text(BTreeMap<String, i64>),
+  None,
+}
+
+impl From<LegacyIndex> for Index {
+  fn from(legacy_index: LegacyIndex) -> Self {
+    match legacy_index {
+      LegacyIndex::Integer(tree) => Index::Integer(IndexTree::Unique(
+        tree.into_iter().map(|(key, row_id)| (i64::from(key), row_id)).collect()
+      )),
+      LegacyIndex::Text(tree) => Index::Text(IndexTree::Unique(tree)),
+      LegacyIndex::None => Index::None,
+    }
+  }
+}
+
+// 当时没有 NULL，INSERT 时没有写到的列以及写的是 NULL 的列都存成了文本 "Null"，其他类型的列不会缺少值
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub enum LegacyRow {
+  Integer(BTreeMap<i64, i32>),
+  Bool(BTreeMap<i64, bool>),
+  Text(BTreeMap<i64, String>),
+  Real(BTreeMap<i64, f32>),
+  None,
+}
+
+impl From<LegacyRow> for Row {
+  fn from(legacy_row: LegacyRow) -> Self {
+    match legacy_row {
+      LegacyRow::Integer(tree) => Row::Integer(
+        tree.into_iter().map(|(row_id, value)| (row_id, Some(i64::from(value)))).collect()
+      ),
+      LegacyRow::Bool(tree) => Row::Bool(
+        tree.into_iter().map(|(row_id, value)| (row_id, Some(value))).collect()
+      ),
+      LegacyRow::Text(tree) => Row::Text(
+        tree.into_iter().map(|(row_id, value)| (row_id, Some(value).filter(|value| value != "Null"))).collect()
+      ),
+      LegacyRow::Real(tree) => Row::Real(
+        tree.into_iter().map(|(row_id, value)| (row_id, Some(get_real_from_legacy(value)))).collect()
+      ),
+      LegacyRow::None => Row::None,
+    }
+  }
+}
+
+// 当时所有 Integer 和 Text 的列都会往索引里面写，但是只有 is_indexed 的列（PRIMARY KEY）真正用到了索引
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct LegacyColumn {
+  pub column_name: String,
+  pub column_datatype: DataType,
+  pub is_primary_key: bool,
+  pub is_unique_constraint: bool,
+  pub is_not_null_constraint: bool,
+  pub is_indexed: bool,
+  pub index: LegacyIndex,
+}
+
+impl From<LegacyColumn> for Column {
+  fn from(legacy_column: LegacyColumn) -> Self {
+    Column {
+      column_name: legacy_column.column_name,
+      column_datatype: legacy_column.column_datatype,
+      is_primary_key: legacy_column.is_primary_key,
+      is_unique_constraint: legacy_column.is_unique_constraint,
+      is_not_null_constraint: legacy_column.is_not_null_constraint,
+      is_indexed: legacy_column.is_indexed,
+      index: if legacy_column.is_indexed { legacy_column.index.into() } else { Index::None },
+      default_expr: None,
+      check_exprs: vec![],
+      decimal_precision: None,
+    }
+  }
+}
+
+// 没有 PRIMARY KEY 的时候 primary_key 是 "-1"
+// Rc<RefCell<T>> 和 T 的编码是一样的，所以 table_rows 直接用 HashMap 来解码
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct LegacyTable {
+  pub primary_key: String,
+  pub table_name: String,
+  pub indexes: HashMap<String, String>,
+  pub most_recent_row_id: i64,
+  pub table_rows: HashMap<String, LegacyRow>,
+  pub table_columns: Vec<LegacyColumn>,
+}
+
+impl From<LegacyTable> for Table {
+  fn from(legacy_table: LegacyTable) -> Self {
+    Table {
+      primary_key: if legacy_table.primary_key == "-1" { vec![] } else { vec![legacy_table.primary_key] },
+      table_keys: vec![],
+      foreign_keys: vec![],
+      check_exprs: vec![],
+      table_name: legacy_table.table_name,
+      indexes: legacy_table.indexes,
+      most_recent_row_id: legacy_table.most_recent_row_id,
+      is_autoincrement: false,
+      table_rows: Rc::new(RefCell::new(
+        legacy_table
+          .table_rows
+          .into_iter()
+          .map(|(column_name, row)| (column_name, row.into()))
+          .collect()
+      )),
+      table_columns: legacy_table.table_columns.into_iter().map(Column::from).collect(),
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct LegacyDatabase {
+  pub database_name: String,
+  pub tables: HashMap<String, LegacyTable>,
+}
+
+impl From<LegacyDatabase> for Database {
+  fn from(legacy_database: LegacyDatabase) -> Self {
+    Database {
+      database_name: legacy_database.database_name,
+      tables: legacy_database
+        .tables
+        .into_iter()
+        .map(|(table_name, table)| (table_name, table.into()))
+        .collect(),
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Debug)]
+pub struct LegacyDatabaseManager {
+  pub database: HashMap<String, LegacyDatabase>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::sql_query::handle_sql_query;
+  use crate::table::row::value::Value;
+
+  // 用版本 0 的程序写入的文件：
+  // CREATE TABLE scores (id INTEGER PRIMARY KEY, name TEXT UNIQUE, score REAL, passed BOOLEAN);
+  // INSERT INTO scores (name, score, passed) VALUES ('a', 1.1, true);
+  // INSERT INTO scores (name, score, passed) VALUES ('b', 2.5, false);
+  // INSERT INTO scores (id, name, score, passed) VALUES (10, 'c', 0.1, true);
+  // .save baseline.db
+  const BASELINE_DATABASE_FILE: &[u8] = include_bytes!("../../tests/fixtures/baseline.db");
+  const BASELINE_DATABASE_MANAGER_FILE: &[u8] = include_bytes!("../../tests/fixtures/baseline.dmf");
+
+  // 和文件中的内容相同，当时 UNIQUE 只对 PRIMARY KEY 生效，所以 name 列没有 UNIQUE 约束
+  fn create_database() -> Database {
+    let mut database = Database::new("baseline.db".to_string());
+    for sql_query in [
+      "CREATE TABLE scores (id INTEGER PRIMARY KEY, name TEXT, score REAL, passed BOOLEAN);",
+      "INSERT INTO scores (name, score, passed) VALUES ('a', 1.1, true);",
+      "INSERT INTO scores (name, score, passed) VALUES ('b', 2.5, false);",
+      "INSERT INTO scores (id, name, score, passed) VALUES (10, 'c', 0.1, true);",
+    ] {
+      handle_sql_query(sql_query, &mut database).unwrap();
+    }
+    database
+  }
+
+  #[test]
+  fn test_decode_legacy_database() {
+    let (database, version) = decode::<Database>(BASELINE_DATABASE_FILE).unwrap();
+
+    assert_eq!(version, 0);
+    assert_eq!(database, create_database());
+
+    let table = database.get_table("scores".to_string()).unwrap();
+    assert_eq!(table.get_value("score", &1), Ok(Value::Real(1.1)));
+    assert_eq!(table.get_value("score", &10), Ok(Value::Real(0.1)));
+  }
+
+  #[test]
+  fn test_decode_legacy_database_manager() {
+    let (database_manager, version) = decode::<DatabaseManager>(BASELINE_DATABASE_MANAGER_FILE).unwrap();
+
+    assert_eq!(version, 0);
+    assert_eq!(database_manager.get_database("baseline.db".to_string()), Ok(&create_database()));
+  }
+
+  #[test]
+  fn test_decode_legacy_null_text() {
+    let legacy_database = LegacyDatabase {
+      database_name: "testdb".to_string(),
+      tables: HashMap::from([(
+        "test".to_string(),
+        LegacyTable {
+          primary_key: "-1".to_string(),
+          table_name: "test".to_string(),
+          indexes: HashMap::new(),
+          most_recent_row_id: 2,
+          table_rows: HashMap::from([(
+            "name".to_string(),
+            LegacyRow::Text(BTreeMap::from([(1, "a".to_string()), (2, "Null".to_string())])),
+          )]),
+          table_columns: vec![LegacyColumn {
+            column_name: "name".to_string(),
+            column_datatype: DataType::Text,
+            is_primary_key: false,
+            is_unique_constraint: false,
+            is_not_null_constraint: false,
+            is_indexed: false,
+            index: LegacyIndex::Text(BTreeMap::from([("a".to_string(), 1), ("Null".to_string(), 2)])),
+          }],
+        },
+      )]),
+    };
+    let (database, _) = decode::<Database>(&serialize(&legacy_database).unwrap()).unwrap();
+
+    let mut expected = Database::new("testdb".to_string());
+    handle_sql_query("CREATE TABLE test (name TEXT);", &mut expected).unwrap();
+    handle_sql_query("INSERT INTO test (name) VALUES ('a'), (NULL);", &mut expected).unwrap();
+    assert_eq!(database, expected);
+  }
+
+  #[test]
+  fn test_encode_and_decode_database() {
+    let mut database = create_database();
+    // 超出 i32 范围的值在当前版本中可以保存下来
+    handle_sql_query("INSERT INTO scores (id, score) VALUES (3000000000, 0.1);", &mut database).unwrap();
+
+    let bytes = encode(&database).unwrap();
+    assert!(bytes.starts_with(FILE_MAGIC));
+    assert_eq!(decode::<Database>(&bytes), Ok((database, FILE_VERSION)));
+  }
+
+  #[test]
+  fn test_encode_and_decode_expression() {
+    let mut database = Database::new("testdb".to_string());
+    handle_sql_query(
+      "CREATE TABLE test (id INTEGER DEFAULT (1 + 1) CHECK (id > 0), j JSON CHECK (j ->> 'a' IS NOT NULL), CHECK (id < 100));",
+      &mut database,
+    ).unwrap();
+    handle_sql_query("CREATE TABLE prices (price DECIMAL(5, 2));", &mut database).unwrap();
+
+    assert_eq!(decode::<Database>(&encode(&database).unwrap()), Ok((database, FILE_VERSION)));
+  }
+
+  #[rstest]
+  #[case(b"NOLLADB\0\x02\0\0\0".to_vec(), "File version 2 is newer than the supported version 1")]
+  #[case(b"NOLLADB\0\x01".to_vec(), "Invalid file header")]
+  // 不是版本 0 格式的没有文件头的数据
+  #[case(
+    b"\x06\0\0\0\0\0\0\0testdb\x01\0\0\0\0\0\0\0\x04\0\0\0\0\0\0\0test\x02\0\0\0\0\0\0\0id".to_vec(),
+    "File has no version header and is not in the version 0 format",
+  )]
+  fn test_decode_error(
+    #[case] bytes: Vec<u8>,
+    #[case] expected: &str,
+  ) {
+    assert_eq!(
+      decode::<Database>(&bytes),
+      Err(NollaDBError::Internal(expected.to_string())),
+    );
+  }
+}
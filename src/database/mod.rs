@@ -1,10 +1,12 @@
 pub mod database_manager;
+pub mod migration;
 
 use std::collections::{HashMap};
 
 use serde::{Deserialize, Serialize};
 
 use crate::table::Table;
+use crate::table::foreign_key::ForeignKey;
 use crate::error::{Result, NollaDBError};
 
 use database_manager::DatabaseManager;
@@ -85,17 +87,17 @@ impl Database {
     println!("saving {}...", database_name.clone());
     match Database::save(database_name.clone(), database) {
       Ok(_) => {
-        println!("saving {} done", database_name.to_string());
+        println!("saving {} done", database_name);
         // save 完成之后同样要 save database_manager 文件
         match DatabaseManager::save(
           database_manager_file.clone(),
           database_manager,
         ) {
           Ok(()) => Ok(()),
-          Err(error) => return Err(error),
+          Err(error) => Err(error),
         }
       }
-      Err(error) => return Err(error),
+      Err(error) => Err(error),
     }
   }
 
@@ -107,7 +109,7 @@ impl Database {
     // 目前先默认在当前目录
     match database_manager.get_database(database_name) {
       Ok(database) => Ok(database),
-      Err(error) => return Err(error)
+      Err(error) => Err(error)
     }
   }
 
@@ -119,7 +121,7 @@ impl Database {
     // 目前先默认在当前目录
     match database_manager.get_database_mut(database_name) {
       Ok(database) => Ok(database),
-      Err(error) => return Err(error)
+      Err(error) => Err(error)
     }
   }
 
@@ -129,14 +131,14 @@ impl Database {
       new_data,
     ) {
       Ok(data) => Ok(data),
-      Err(error) => return Err(error),
+      Err(error) => Err(error),
     }
   }
 
   pub fn save(database_name: String, data: &Database) -> Result<()> {
     match DatabaseManager::save(database_name, data) {
       Ok(()) => Ok(()),
-      Err(error) => return Err(error),
+      Err(error) => Err(error),
     }
   }
 
@@ -146,11 +148,11 @@ impl Database {
     database_manager: &DatabaseManager,
     database_name: String
   ) -> Result<Vec<String>> {
-    let database = Database::open(&database_manager, database_name).unwrap();
+    let database = Database::open(database_manager, database_name)?;
     Ok(
       database.tables
-        .iter()
-        .map(|(key, _)| key.to_string())
+        .keys()
+        .map(|key| key.to_string())
         .collect()
     )
   }
@@ -159,18 +161,121 @@ impl Database {
     self.tables.contains_key(&table_name)
   }
 
-  #[allow(dead_code)]
   pub fn get_table(&self, table_name: String) -> Result<&Table> {
     match self.tables.get(&table_name) {
       Some(table) => Ok(table),
-      _ => Err(NollaDBError::General(String::from("Table not found"))),
+      _ => Err(NollaDBError::TableNotFound(table_name)),
     }
   }
 
   pub fn get_table_mut(&mut self, table_name: String) -> Result<&mut Table> {
     match self.tables.get_mut(&table_name) {
       Some(table) => Ok(table),
-      _ => Err(NollaDBError::General(String::from("Table not found"))),
+      _ => Err(NollaDBError::TableNotFound(table_name)),
+    }
+  }
+
+  // 索引名在整个数据库中是唯一的，找到索引所在的表
+  pub fn get_index_table_name(&self, index_name: &str) -> Option<String> {
+    self.tables
+      .values()
+      .find(|table| table.indexes.contains_key(index_name))
+      .map(|table| table.table_name.to_string())
+  }
+
+  // 重命名表，表名同时也是 tables 中的 key
+  pub fn rename_table(&mut self, table_name: String, new_table_name: String) -> Result<()> {
+    if self.has_table(new_table_name.to_string()) {
+      return Err(NollaDBError::Internal(
+        format!("Can not rename table, because table '{}' already exists", new_table_name)
+      ));
+    }
+
+    let mut table = self.drop_table(table_name.to_string())?;
+    table.table_name = new_table_name.to_string();
+    self.tables.insert(new_table_name.to_string(), table);
+
+    // 引用了这张表的外键也要改成新的表名
+    for foreign_key in self.tables.values_mut().flat_map(|table| table.foreign_keys.iter_mut()) {
+      if foreign_key.foreign_table_name == table_name {
+        foreign_key.foreign_table_name = new_table_name.to_string();
+      }
+    }
+
+    Ok(())
+  }
+
+  // 重命名列，引用了这一列的外键也要改成新的列名
+  pub fn rename_column(&mut self, table_name: &str, old_column_name: &str, new_column_name: &str) -> Result<()> {
+    self.get_table_mut(table_name.to_string())?.rename_column(old_column_name, new_column_name)?;
+
+    for foreign_key in self.tables.values_mut().flat_map(|table| table.foreign_keys.iter_mut()) {
+      if foreign_key.foreign_table_name != table_name { continue; }
+      for referred_column_name in foreign_key.referred_column_names.iter_mut() {
+        if referred_column_name == old_column_name {
+          *referred_column_name = new_column_name.to_string();
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  // 找到所有引用了这张表的外键，返回子表的表名以及外键，子表可以是这张表自己
+  pub fn get_referencing_foreign_keys(&self, table_name: &str) -> Vec<(String, ForeignKey)> {
+    let mut referencing_foreign_keys: Vec<(String, ForeignKey)> = vec![];
+    for table in self.tables.values() {
+      for foreign_key in &table.foreign_keys {
+        if foreign_key.foreign_table_name == table_name {
+          referencing_foreign_keys.push((table.table_name.to_string(), foreign_key.clone()));
+        }
+      }
+    }
+
+    referencing_foreign_keys
+  }
+
+  // 建表时检查外键引用的表和列，没有写被引用的列的话换成父表的 PRIMARY KEY
+  // 外键可以引用正在创建的这张表自己
+  pub fn check_foreign_key_definitions(&self, table: &mut Table) -> Result<()> {
+    for i in 0..table.foreign_keys.len() {
+      let foreign_key = &table.foreign_keys[i];
+      let foreign_table = if foreign_key.foreign_table_name == table.table_name {
+        &*table
+      } else {
+        self.get_table(foreign_key.foreign_table_name.to_string())?
+      };
+
+      let referred_column_names = if foreign_key.referred_column_names.is_empty() {
+        foreign_table.primary_key.clone()
+      } else {
+        foreign_key.referred_column_names.clone()
+      };
+      // 被引用的列必须正好是父表的 PRIMARY KEY 或者 UNIQUE 约束，这样才能通过索引找到被引用的行
+      if referred_column_names.len() != foreign_key.column_names.len()
+        || !foreign_table.is_unique_key(&referred_column_names) {
+        return Err(NollaDBError::Internal(
+          format!(
+            "Foreign key mismatch: {}.({}) referencing {}.({}), which is not a PRIMARY KEY or UNIQUE key",
+            table.table_name,
+            foreign_key.column_names.join(", "),
+            foreign_table.table_name,
+            referred_column_names.join(", ")
+          )
+        ));
+      }
+
+      table.foreign_keys[i].referred_column_names = referred_column_names;
+    }
+
+    Ok(())
+  }
+
+  // 从数据库中删除表，返回被删除的表
+  pub fn drop_table(&mut self, table_name: String) -> Result<Table> {
+    match self.tables.remove(&table_name) {
+      Some(table) => Ok(table),
+      _ => Err(NollaDBError::TableNotFound(table_name)),
     }
   }
 }
@@ -240,7 +345,7 @@ mod tests {
     let mut database_mut = create_new_database(database_name, query).unwrap();
 
     let table = database.get_table(table_name.to_string()).unwrap();
-    let mut table_mut = database_mut.get_table_mut(table_name.to_string()).unwrap();
+    let table_mut = database_mut.get_table_mut(table_name.to_string()).unwrap();
 
     table_mut.most_recent_row_id += 1;
 
@@ -252,7 +357,7 @@ mod tests {
   fn create_new_database(database_name: &str, query: &str) -> Result<Database, ()> {
     let mut database = Database::new(database_name.to_string());
     let dialect = SQLiteDialect {};
-    let mut ast = Parser::parse_sql(&dialect, &query).unwrap();
+    let mut ast = Parser::parse_sql(&dialect, query).unwrap();
     let create_query = CreateQuery::new(&ast.pop().unwrap()).unwrap();
 
     database.tables.insert(
@@ -1,3 +1,4 @@
+use std::fmt;
 use std::result;
 use thiserror::Error;
 use sqlparser::parser::ParserError;
@@ -15,6 +16,34 @@ pub enum NollaDBError {
   SQLParseError(#[from] ParserError),
   #[error("To be Implemented error: {0}")]
   ToBeImplemented(String),
+  #[error("Type mismatch error: {0}")]
+  TypeMismatch(String),
+  #[error("{kind} constraint violation: column {column}")]
+  ConstraintViolation { kind: ConstraintKind, column: String },
+  #[error("NOT NULL constraint violation: column {0} can not be NULL")]
+  NotNullViolation(String),
+  #[error("CHECK constraint violation: {0}")]
+  CheckViolation(String),
+  #[error("Table not found error: table '{0}' does not exist")]
+  TableNotFound(String),
+}
+
+// 违反的是哪一种约束
+#[derive(Debug, PartialEq, Clone)]
+pub enum ConstraintKind {
+  PrimaryKey,
+  Unique,
+  ForeignKey,
+}
+
+impl fmt::Display for ConstraintKind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ConstraintKind::PrimaryKey => f.write_str("PRIMARY KEY"),
+      ConstraintKind::Unique => f.write_str("UNIQUE"),
+      ConstraintKind::ForeignKey => f.write_str("FOREIGN KEY"),
+    }
+  }
 }
 
 pub type Result<T> = result::Result<T, NollaDBError>;
@@ -34,7 +63,7 @@ mod tests {
   #[case("test error")]
   fn test_nolladb_error(#[case] input: &str) {
     let expected = NollaDBError::General(input.to_string());
-    let result = nolladb_error(&input);
+    let result = nolladb_error(input);
 
     assert_eq!(result, expected);
   }
@@ -75,6 +104,42 @@ mod tests {
       assert_eq!(result, expected);
   }
 
+  #[rstest]
+  #[case(
+    NollaDBError::TypeMismatch("can not store Text value 'abc' into Integer column".to_string()),
+    "Type mismatch error: can not store Text value 'abc' into Integer column",
+  )]
+  #[case(
+    NollaDBError::ConstraintViolation { kind: ConstraintKind::Unique, column: "test.email".to_string() },
+    "UNIQUE constraint violation: column test.email",
+  )]
+  #[case(
+    NollaDBError::ConstraintViolation { kind: ConstraintKind::PrimaryKey, column: "test.id".to_string() },
+    "PRIMARY KEY constraint violation: column test.id",
+  )]
+  #[case(
+    NollaDBError::ConstraintViolation { kind: ConstraintKind::ForeignKey, column: "orders.user_id".to_string() },
+    "FOREIGN KEY constraint violation: column orders.user_id",
+  )]
+  #[case(
+    NollaDBError::NotNullViolation("test.name".to_string()),
+    "NOT NULL constraint violation: column test.name can not be NULL",
+  )]
+  #[case(
+    NollaDBError::CheckViolation("price > 0".to_string()),
+    "CHECK constraint violation: price > 0",
+  )]
+  #[case(
+    NollaDBError::TableNotFound("test".to_string()),
+    "Table not found error: table 'test' does not exist",
+  )]
+  fn test_nolladb_typed_error(
+    #[case] error: NollaDBError,
+    #[case] expected: &str,
+  ) {
+    assert_eq!(format!("{}", error), expected);
+  }
+
   #[rstest]
   #[case("To be implemented")]
   fn test_nolladb_to_be_implemented_error(#[case] input: &str) {
@@ -7,13 +7,15 @@ use clap::{
 
 pub fn intro_message() {
   println!(
-    "{} - {}\n{}\n{}\n{}{}{}",
+    concat!(
+      "{} - {}\n{}\n{}\n",
+      "Using '.exit' or '.quit' to quit.\n",
+      "Using '.help' for usage hints.\n",
+      "Using '.open FILENAME' to reopen on a persistent database."
+    ),
     crate_name!(),
     crate_version!(),
     crate_authors!(),
     crate_description!(),
-    "Using '.exit' or '.quit' to quit.\n",
-    "Using '.help' for usage hints.\n",
-    "Using '.open FILENAME' to reopen on a persistent database."
   );
 }
@@ -9,6 +9,8 @@ mod sql_query;
 mod read_eval_print_loop;
 mod table;
 mod database;
+#[cfg(test)]
+mod test_helpers;
 
 use std::{env, process};
 
@@ -40,7 +42,7 @@ fn main() -> rustyline::Result<()> {
   // cargo run
   let args_right_number = 2;
   if args.len() != args_right_number {
-    println!("Usage: {} DATABASE_NAME.db", "cargo run");
+    println!("Usage: cargo run DATABASE_NAME.db");
     process::exit(1)
   }
   let database_name = &args[args_right_number - 1];
@@ -85,7 +87,7 @@ fn main() -> rustyline::Result<()> {
   intro_message();
 
   loop {
-    let print = format!("nolladb>");
+    let print = String::from("nolladb>");
     repl
      .helper_mut()
      .expect("No helper found")
@@ -94,7 +96,7 @@ fn main() -> rustyline::Result<()> {
     let readline = repl.readline(&print);
     match readline {
       Ok(command) => {
-        if command.split_whitespace().collect::<Vec<&str>>().len() == 0 { continue; }
+        if command.split_whitespace().collect::<Vec<&str>>().is_empty() { continue; }
 
         repl.add_history_entry(command.as_str());
         let command_type = get_command_type(&command.trim().to_owned());
@@ -22,7 +22,7 @@ pub enum MetaCommand {
 impl MetaCommand {
   pub fn new(command: String) -> MetaCommand {
     let args: Vec<&str> = command.split_whitespace().collect();
-    if args.len() == 0 {
+    if args.is_empty() {
       return MetaCommand::Unknown;
     }
     // to_owned 将 &str 转变成 String
@@ -67,7 +67,7 @@ fn handle_exit_or_quit_meta_command(
 }
 
 fn get_str_after_meta_command(
-  ref args: String,
+  args: String,
   error_message: &str,
 ) -> Result<String> {
   let mut args_vec = args.split_whitespace().collect::<Vec<&str>>();
@@ -89,22 +89,23 @@ pub fn handle_meta_command(
     MetaCommand::Quit => handle_exit_or_quit_meta_command(repl_helper),
     MetaCommand::Help => {
       println!(
-        "{}{}{}{}{}{}{}{}{}",
-        "Special commands:\n",
-        ".help            - Display help message\n",
-        "---------------------------------------\n",
-        ".ast  <QUERY>    - Show the abstract syntax tree for QUERY\n",
-        ".exit            - Quits this application\n",
-        ".open <FILENAME> - Close existing database and reopen FILENAME\n",
-        ".read <FILENAME> - Read input from FILENAME\n",
-        ".save <FILENAME> - Write in-memory database into FILENAME\n",
-        ".tables          - List names of tables\n",
+        concat!(
+          "Special commands:\n",
+          ".help            - Display help message\n",
+          "---------------------------------------\n",
+          ".ast  <QUERY>    - Show the abstract syntax tree for QUERY\n",
+          ".exit            - Quits this application\n",
+          ".open <FILENAME> - Close existing database and reopen FILENAME\n",
+          ".read <FILENAME> - Read input from FILENAME\n",
+          ".save <FILENAME> - Write in-memory database into FILENAME\n",
+          ".tables          - List names of tables\n",
+        )
       );
       Ok(command)
     },
     MetaCommand::Tables => {
       let table_names = database.get_all_tables(
-        &database_manager,
+        database_manager,
         database.database_name.clone()
       ).unwrap();
 
@@ -119,7 +120,7 @@ pub fn handle_meta_command(
         ".open <FILENAME>: FILENAME should not be empty",
       ) {
         Ok(args) => Ok(MetaCommand::Open(args)),
-        Err(error) => return Err(error),
+        Err(error) => Err(error),
       }
     },
     MetaCommand::Read(args) => {
@@ -128,7 +129,7 @@ pub fn handle_meta_command(
         ".read <FILENAME>: FILENAME should not be empty",
       ) {
         Ok(args) => Ok(MetaCommand::Read(args)),
-        Err(error) => return Err(error),
+        Err(error) => Err(error),
       }
     },
     MetaCommand::Save(args) => {
@@ -137,7 +138,7 @@ pub fn handle_meta_command(
         ".save <FILENAME>: FILENAME should not be empty",
       ) {
         Ok(args) => Ok(MetaCommand::Save(args)),
-        Err(error) => return Err(error),
+        Err(error) => Err(error),
       }
     },
     MetaCommand::Ast(ref args) => {
@@ -155,9 +156,9 @@ pub fn handle_meta_command(
       }
       Ok(command)
     },
-    MetaCommand::Unknown => Err(NollaDBError::UnknownCommand(format!(
-      "Unknown command or invalid arguments. Enter '.help'"
-    ))),
+    MetaCommand::Unknown => Err(NollaDBError::UnknownCommand(
+      "Unknown command or invalid arguments. Enter '.help'".to_string()
+    )),
   }
 }
 
@@ -230,14 +231,11 @@ mod test {
     let mut repl = init_repl().unwrap();
     let mut database = Database::new("test".to_string());
     let mut database_manager = DatabaseManager::new();
-    match handle_meta_command(
+    handle_meta_command(
       input,
       &mut repl,
       &mut database,
       &mut database_manager
-    ) {
-      Ok(result) => Ok(result),
-      Err(error) => return Err(error),
-    }
+    )
   }
 }
@@ -0,0 +1,413 @@
+use prettytable::Table as PrintTable;
+
+use sqlparser::ast::{
+  Statement,
+  Expr,
+};
+
+use crate::error::{Result, NollaDBError};
+use crate::database::Database;
+use crate::table::Table;
+use crate::sql_query::query::select::{SelectQuery, SelectTable, JoinKind};
+use crate::sql_query::query::update::UpdateQuery;
+use crate::sql_query::query::delete::DeleteQuery;
+use crate::sql_query::expression::{TableRow, RowContext, check_column_references};
+
+use super::{
+  expand_table_functions,
+  get_select_table_rows,
+  get_projection_exprs,
+  get_order_by_keys,
+  get_aggregate_exprs,
+  get_is_aggregate_query,
+  get_order_by_index_column,
+  OrderByKey,
+};
+use super::planner::{
+  get_scan_plan,
+  get_table_description,
+  get_index_description,
+};
+use super::join::explain_join_table;
+
+// 查询计划中的一个节点
+// 和 SQLite 的 EXPLAIN QUERY PLAN 一样用 parent 来表示树的结构，根节点的 parent 为 0
+#[derive(Debug, PartialEq)]
+pub struct QueryPlanNode {
+  pub id: usize,
+  pub parent: usize,
+  pub detail: String,
+}
+
+// EXPLAIN 得到的查询计划，节点按照先序遍历的顺序排列
+#[derive(Debug, PartialEq, Default)]
+pub struct QueryPlan {
+  pub nodes: Vec<QueryPlanNode>,
+}
+
+impl QueryPlan {
+  // 添加一个节点，返回这个节点的 id
+  fn push_node(&mut self, parent: usize, detail: String) -> usize {
+    let id = self.nodes.len() + 1;
+    self.nodes.push(QueryPlanNode { id, parent, detail });
+    id
+  }
+
+  pub fn print_query_plan(&self) -> Result<usize> {
+    let mut print_table = PrintTable::new();
+    print_table.add_row(row!["id", "parent", "detail"]);
+
+    // 父节点总是在子节点前面，detail 按照节点在树中的深度缩进
+    let mut depths: Vec<usize> = vec![0];
+    for QueryPlanNode { id, parent, detail } in &self.nodes {
+      let depth = depths.get(*parent).map_or(0, |depth| depth + 1);
+      depths.push(depth);
+      print_table.add_row(row![
+        id,
+        parent,
+        format!("{}{}", "  ".repeat(depth.saturating_sub(1)), detail),
+      ]);
+    }
+
+    print_table
+      .print_tty(false)
+      .map_err(|error| NollaDBError::Internal(error.to_string()))
+  }
+}
+
+pub fn explain_statement(statement: &Statement, database: &Database) -> Result<QueryPlan> {
+  match statement {
+    Statement::Query(_) => explain_select_query(&SelectQuery::new(statement)?, database),
+    Statement::Update { .. } => explain_update_query(&UpdateQuery::new(statement)?, database),
+    Statement::Delete { .. } => explain_delete_query(&DeleteQuery::new(statement)?, database),
+    _ => Err(NollaDBError::ToBeImplemented(
+      "EXPLAIN other SQL statement will be implemented soon".to_string()
+    )),
+  }
+}
+
+// 和 execute_select_query 做同样的选择，只是不真正执行
+// 从根节点往下依次是 LIMIT、ORDER BY、GROUP BY、WHERE 以及表的扫描和 JOIN
+pub fn explain_select_query(
+  select_query: &SelectQuery,
+  database: &Database,
+) -> Result<QueryPlan> {
+  let SelectQuery {
+    tables,
+    projection,
+    selection,
+    group_by,
+    having,
+    order_by,
+    limit,
+    offset,
+  } = select_query;
+
+  let table_functions = expand_table_functions(tables, database)?;
+  let table_rows = get_select_table_rows(tables, database, &table_functions)?;
+  let projection_exprs = get_projection_exprs(projection, &table_rows)?;
+  let order_by_keys = get_order_by_keys(order_by, &projection_exprs)?;
+
+  // 不会真正执行查询，所以要先检查引用的列是否存在，和执行的时候报同样的错误
+  let row_context = RowContext {
+    table_rows: table_rows.clone(),
+    ..RowContext::default()
+  };
+  let order_by_exprs = order_by_keys.iter().filter_map(|(order_by_key, _)| match order_by_key {
+    OrderByKey::Expr(expr) => Some(expr),
+    OrderByKey::Projection(_) => None,
+  });
+  for expr in projection_exprs.iter().map(|(_, expr)| expr).chain(selection).chain(order_by_exprs) {
+    check_column_references(expr, &row_context)?;
+  }
+
+  let aggregate_exprs = get_aggregate_exprs(&projection_exprs, having, &order_by_keys);
+  let is_aggregate_query = get_is_aggregate_query(group_by, having, &aggregate_exprs)?;
+  let order_by_index_column = get_order_by_index_column(
+    &table_rows,
+    is_aggregate_query,
+    &order_by_keys,
+    &projection_exprs,
+  );
+
+  let mut query_plan = QueryPlan::default();
+  let mut parent = 0;
+  if limit.is_some() || offset.is_some() {
+    let mut details: Vec<String> = vec![];
+    if let Some(expr) = limit {
+      details.push(format!("LIMIT {}", expr));
+    }
+    if let Some(expr) = offset {
+      details.push(format!("OFFSET {}", expr));
+    }
+    parent = query_plan.push_node(parent, details.join(" "));
+  }
+  // 按照索引的顺序扫描的话就不用再排序了
+  if !order_by.is_empty() && order_by_index_column.is_none() {
+    parent = query_plan.push_node(parent, format!("SORT BY {}", join_to_string(order_by)));
+  }
+  if is_aggregate_query {
+    let mut detail = "AGGREGATE".to_string();
+    if !group_by.is_empty() {
+      detail.push_str(&format!(" GROUP BY {}", join_to_string(group_by)));
+    }
+    if let Some(expr) = having {
+      detail.push_str(&format!(" HAVING {}", expr));
+    }
+    parent = query_plan.push_node(parent, detail);
+  }
+  if let Some(expr) = selection {
+    parent = query_plan.push_node(parent, format!("FILTER {}", expr));
+  }
+
+  match order_by_index_column {
+    Some((column_name, is_asc)) => {
+      let table_row = &table_rows[0];
+      query_plan.push_node(parent, format!(
+        "SCAN {} USING {} ORDER BY {} {}",
+        get_table_description(table_row),
+        get_index_description(table_row.table, &column_name),
+        column_name,
+        if is_asc { "ASC" } else { "DESC" }
+      ));
+    },
+    None => push_join_nodes(&mut query_plan, parent, tables, &table_rows, table_rows.len().saturating_sub(1), selection)?,
+  }
+
+  Ok(query_plan)
+}
+
+pub fn explain_update_query(
+  update_query: &UpdateQuery,
+  database: &Database,
+) -> Result<QueryPlan> {
+  let UpdateQuery {
+    table_name,
+    selection,
+    ..
+  } = update_query;
+
+  let table = database.get_table(table_name.to_string())?;
+  let mut query_plan = QueryPlan::default();
+  let parent = query_plan.push_node(0, format!("UPDATE {}", table_name));
+  push_matched_row_ids_nodes(&mut query_plan, parent, table, selection);
+
+  Ok(query_plan)
+}
+
+pub fn explain_delete_query(
+  delete_query: &DeleteQuery,
+  database: &Database,
+) -> Result<QueryPlan> {
+  let DeleteQuery {
+    table_name,
+    selection,
+  } = delete_query;
+
+  let table = database.get_table(table_name.to_string())?;
+  let mut query_plan = QueryPlan::default();
+  let parent = query_plan.push_node(0, format!("DELETE FROM {}", table_name));
+  push_matched_row_ids_nodes(&mut query_plan, parent, table, selection);
+
+  Ok(query_plan)
+}
+
+// 和 get_matched_row_ids 一样，先按照扫描方式拿到 row id，再用 WHERE 条件过滤
+fn push_matched_row_ids_nodes(
+  query_plan: &mut QueryPlan,
+  mut parent: usize,
+  table: &Table,
+  selection: &Option<Expr>,
+) {
+  let table_rows = vec![TableRow {
+    table_name: table.table_name.to_string(),
+    table,
+    row_id: None,
+  }];
+  if let Some(expr) = selection {
+    parent = query_plan.push_node(parent, format!("FILTER {}", expr));
+  }
+  query_plan.push_node(parent, get_scan_plan(&table_rows, selection).get_description(&table_rows[0]));
+}
+
+// 前面 i 张表 JOIN 好之后再和第 i 张表 JOIN，所以 JOIN 节点的第一个子节点是左边的子树，第二个是右表
+fn push_join_nodes(
+  query_plan: &mut QueryPlan,
+  parent: usize,
+  tables: &[SelectTable],
+  table_rows: &[TableRow],
+  i: usize,
+  selection: &Option<Expr>,
+) -> Result<()> {
+  if i == 0 {
+    // 和 SQLite 一样，没有 FROM 的话只扫描一个常量行
+    let detail = match table_rows.first() {
+      Some(table_row) => get_scan_plan(table_rows, selection).get_description(table_row),
+      None => "SCAN CONSTANT ROW".to_string(),
+    };
+    query_plan.push_node(parent, detail);
+    return Ok(());
+  }
+
+  let (join_kind, constraint) = match &tables[i].join {
+    Some(join) => join,
+    None => return Err(NollaDBError::Internal(
+      format!("Missing JOIN for table '{}'", tables[i].get_reference_name())
+    )),
+  };
+  let (join_algorithm, right_table_detail) = explain_join_table(
+    &table_rows[..i],
+    &table_rows[i],
+    constraint,
+    tables[i].function_args.is_some(),
+  )?;
+  let mut detail = format!(
+    "{} JOIN USING {}",
+    match join_kind {
+      JoinKind::Inner => "INNER",
+      JoinKind::LeftOuter => "LEFT",
+      JoinKind::Cross => "CROSS",
+    },
+    join_algorithm
+  );
+  if let Some(expr) = constraint {
+    detail.push_str(&format!(" ON {}", expr));
+  }
+
+  let id = query_plan.push_node(parent, detail);
+  push_join_nodes(query_plan, id, tables, table_rows, i - 1, selection)?;
+  query_plan.push_node(id, right_table_detail);
+
+  Ok(())
+}
+
+fn join_to_string<T: ToString>(items: &[T]) -> String {
+  items
+    .iter()
+    .map(|item| item.to_string())
+    .collect::<Vec<String>>()
+    .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::test_helpers::create_test_database;
+  use crate::sql_query::{handle_sql_query, get_sql_ast};
+
+  #[rstest]
+  #[case("SELECT * FROM users;", vec![(1, 0, "SCAN users")])]
+  #[case("SELECT * FROM users WHERE id = 1;", vec![
+    (1, 0, "FILTER id = 1"),
+    (2, 1, "SEARCH users USING PRIMARY KEY (id=?)"),
+  ])]
+  #[case("SELECT * FROM orders WHERE user_id BETWEEN 1 AND 2 AND amount > 1;", vec![
+    (1, 0, "FILTER user_id BETWEEN 1 AND 2 AND amount > 1"),
+    (2, 1, "SEARCH orders USING INDEX idx_user_id (user_id>=? AND user_id<=?)"),
+  ])]
+  #[case("SELECT * FROM e WHERE sid = 1 AND course = 'math';", vec![
+    (1, 0, "FILTER sid = 1 AND course = 'math'"),
+    (2, 1, "SEARCH e USING PRIMARY KEY (sid=? AND course=?)"),
+  ])]
+  #[case("SELECT * FROM e WHERE seat = 2 AND course = 'math' AND sid > 0;", vec![
+    (1, 0, "FILTER seat = 2 AND course = 'math' AND sid > 0"),
+    (2, 1, "SEARCH e USING UNIQUE INDEX (course=? AND seat=?)"),
+  ])]
+  #[case("SELECT * FROM e WHERE sid = 1;", vec![
+    (1, 0, "FILTER sid = 1"),
+    (2, 1, "SCAN e"),
+  ])]
+  #[case("SELECT * FROM users ORDER BY id DESC LIMIT 1;", vec![
+    (1, 0, "LIMIT 1"),
+    (2, 1, "SCAN users USING PRIMARY KEY ORDER BY id DESC"),
+  ])]
+  #[case("SELECT name, COUNT(*) FROM users GROUP BY name HAVING COUNT(*) > 1 ORDER BY name LIMIT 2 OFFSET 1;", vec![
+    (1, 0, "LIMIT 2 OFFSET 1"),
+    (2, 1, "SORT BY name"),
+    (3, 2, "AGGREGATE GROUP BY name HAVING COUNT(*) > 1"),
+    (4, 3, "SCAN users"),
+  ])]
+  #[case("SELECT * FROM users u JOIN orders o ON o.user_id = u.id LEFT JOIN users v ON v.name = u.name CROSS JOIN orders;", vec![
+    (1, 0, "CROSS JOIN USING NESTED LOOP"),
+    (2, 1, "LEFT JOIN USING HASH JOIN ON v.name = u.name"),
+    (3, 2, "INNER JOIN USING INDEX NESTED LOOP ON o.user_id = u.id"),
+    (4, 3, "SCAN users AS u"),
+    (5, 3, "SEARCH orders AS o USING INDEX idx_user_id (user_id=?)"),
+    (6, 2, "BUILD HASH TABLE ON users AS v (name)"),
+    (7, 1, "SCAN orders"),
+  ])]
+  #[case("UPDATE orders SET amount = 1 WHERE 1 < id;", vec![
+    (1, 0, "UPDATE orders"),
+    (2, 1, "FILTER 1 < id"),
+    (3, 2, "SEARCH orders USING PRIMARY KEY (id>?)"),
+  ])]
+  #[case("DELETE FROM orders;", vec![
+    (1, 0, "DELETE FROM orders"),
+    (2, 1, "SCAN orders"),
+  ])]
+  #[case("SELECT 1 + 1;", vec![(1, 0, "SCAN CONSTANT ROW")])]
+  #[case("SELECT * FROM users u, json_each('[1, 2]') AS j;", vec![
+    (1, 0, "CROSS JOIN USING TABLE FUNCTION"),
+    (2, 1, "SCAN users AS u"),
+    (3, 1, "SCAN json_each AS j VIRTUAL TABLE"),
+  ])]
+  fn test_explain_statement(
+    #[case] query: &str,
+    #[case] expected: Vec<(usize, usize, &str)>,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);",
+      "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER, amount INTEGER);",
+      "CREATE INDEX idx_user_id ON orders (user_id);",
+      "CREATE TABLE e (sid INTEGER, course TEXT, seat INTEGER, PRIMARY KEY (sid, course), UNIQUE (course, seat));",
+    ]);
+    let query_plan = explain_statement(&get_sql_ast(query).unwrap(), &database).unwrap();
+
+    assert_eq!(
+      query_plan,
+      QueryPlan {
+        nodes: expected
+          .into_iter()
+          .map(|(id, parent, detail)| QueryPlanNode { id, parent, detail: detail.to_string() })
+          .collect(),
+      }
+    );
+    // 1 条 header + 每个节点一条数据，外加每条之间的分割线
+    assert_eq!(query_plan.print_query_plan(), Ok(query_plan.nodes.len() * 2 + 3));
+  }
+
+  #[rstest]
+  #[case("SELECT * FROM test;")]
+  #[case("SELECT * FROM users HAVING id > 1;")]
+  #[case("INSERT INTO users (name) VALUES ('a');")]
+  fn test_explain_statement_error(#[case] query: &str) {
+    let database = create_test_database(&[
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);",
+      "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER, amount INTEGER);",
+      "CREATE INDEX idx_user_id ON orders (user_id);",
+      "CREATE TABLE e (sid INTEGER, course TEXT, seat INTEGER, PRIMARY KEY (sid, course), UNIQUE (course, seat));",
+    ]);
+    assert!(explain_statement(&get_sql_ast(query).unwrap(), &database).is_err());
+  }
+
+  #[rstest]
+  #[case("SELECT * FROM users WHERE zz = 'a';", "Unknown column 'zz'")]
+  #[case("SELECT zz FROM users;", "Unknown column 'zz'")]
+  #[case("SELECT name FROM users ORDER BY qq;", "Unknown column 'qq'")]
+  #[case("SELECT length(users.zz) FROM users;", "Unknown column 'users.zz'")]
+  #[case("SELECT * FROM users JOIN orders ON users.id = orders.user_id WHERE id = 1;", "Ambiguous column name 'id'")]
+  fn test_explain_unknown_column(#[case] query: &str, #[case] expected: &str) {
+    let mut database = create_test_database(&[
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);",
+      "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER, amount INTEGER);",
+      "INSERT INTO users (name) VALUES ('a');",
+      "INSERT INTO orders (user_id, amount) VALUES (1, 10);",
+    ]);
+    let expected = Err(NollaDBError::Internal(expected.to_string()));
+
+    assert_eq!(explain_statement(&get_sql_ast(query).unwrap(), &database).map(|_| ()), expected);
+    assert_eq!(handle_sql_query(query, &mut database).map(|_| ()), expected);
+  }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashSet;
+
+use crate::error::{Result, NollaDBError, ConstraintKind};
+use crate::database::Database;
+use crate::table::row::value::Value;
+use crate::table::foreign_key::{ForeignKey, ForeignKeyAction};
+
+// 违反外键约束时返回的错误，列出子表中外键的每一列
+fn get_foreign_key_violation_error(table_name: &str, foreign_key: &ForeignKey) -> NollaDBError {
+  NollaDBError::ConstraintViolation {
+    kind: ConstraintKind::ForeignKey,
+    column: foreign_key.get_qualified_column_names(table_name),
+  }
+}
+
+// 子表中的一行在外键上的值必须在父表中存在，外键中有一列是 NULL 的话不检查
+// get_value 返回这一行在某一列上的值，已经转换成了子表中这一列的类型
+pub fn check_foreign_key_values(
+  database: &Database,
+  table_name: &str,
+  get_value: impl Fn(&str) -> Result<Value>,
+) -> Result<()> {
+  let table = database.get_table(table_name.to_string())?;
+
+  for foreign_key in &table.foreign_keys {
+    let values = foreign_key
+      .column_names
+      .iter()
+      .map(|column_name| get_value(column_name))
+      .collect::<Result<Vec<Value>>>()?;
+    if values.iter().any(|value| value.is_null()) { continue; }
+
+    // 转换成父表中被引用的列的类型，转换不了的话父表中肯定没有这个值
+    let foreign_table = database.get_table(foreign_key.foreign_table_name.to_string())?;
+    let is_existed = match foreign_key
+      .referred_column_names
+      .iter()
+      .zip(values.iter())
+      .map(|(column_name, value)| foreign_table.cast_value(column_name, value))
+      .collect::<Result<Vec<Value>>>() {
+      Ok(referred_values) => {
+        foreign_table.get_row_id_by_key(&foreign_key.referred_column_names, &referred_values).is_some()
+          // 引用这张表自己的话，这一行可以引用它自己
+          || (foreign_key.foreign_table_name == table_name && foreign_key
+            .referred_column_names
+            .iter()
+            .map(|column_name| get_value(column_name))
+            .collect::<Result<Vec<Value>>>()? == referred_values)
+      },
+      Err(_) => false,
+    };
+
+    if !is_existed {
+      return Err(get_foreign_key_violation_error(table_name, foreign_key));
+    }
+  }
+
+  Ok(())
+}
+
+// 外键引起的对某一张表中某一行的修改
+#[derive(Debug, PartialEq)]
+pub enum ForeignKeyOperation {
+  Delete { table_name: String, row_id: i64 },
+  Update { table_name: String, row_id: i64, updated_values: Vec<(String, Value)> },
+}
+
+// 没有事务，所以先找出所有要一起删除或者更新的行，检查全部通过之后再修改表
+struct ForeignKeyOperationPlanner<'a> {
+  database: &'a Database,
+  operations: Vec<ForeignKeyOperation>,
+  // 已经确定要删除的行，(表名, row id)
+  deleted_rows: HashSet<(String, i64)>,
+  // 已经确定要做的更新，避免外键互相引用时无限递归
+  updated_rows: HashSet<String>,
+}
+
+impl<'a> ForeignKeyOperationPlanner<'a> {
+  fn new(database: &'a Database) -> Self {
+    ForeignKeyOperationPlanner {
+      database,
+      operations: vec![],
+      deleted_rows: HashSet::new(),
+      updated_rows: HashSet::new(),
+    }
+  }
+
+  // 子表中引用了父表 values 的所有行，不包括已经确定要删除的行
+  fn get_referencing_row_ids(
+    &self,
+    table_name: &str,
+    foreign_key: &ForeignKey,
+    values: &[Value],
+  ) -> Result<Vec<i64>> {
+    let table = self.database.get_table(table_name.to_string())?;
+    // 转换成子表中外键的列的类型，转换不了的话子表中肯定没有这个值
+    let values = match foreign_key
+      .column_names
+      .iter()
+      .zip(values.iter())
+      .map(|(column_name, value)| table.cast_value(column_name, value))
+      .collect::<Result<Vec<Value>>>() {
+      Ok(values) => values,
+      Err(_) => return Ok(vec![]),
+    };
+
+    Ok(
+      table
+        .get_row_ids_by_values(&foreign_key.column_names, &values)?
+        .into_iter()
+        .filter(|row_id| !self.deleted_rows.contains(&(table_name.to_string(), *row_id)))
+        .collect()
+    )
+  }
+
+  fn plan_delete(&mut self, table_name: &str, row_id: i64) -> Result<()> {
+    if !self.deleted_rows.insert((table_name.to_string(), row_id)) {
+      return Ok(());
+    }
+    self.operations.push(ForeignKeyOperation::Delete { table_name: table_name.to_string(), row_id });
+
+    self.plan_delete_cascade(table_name, row_id)
+  }
+
+  // 按照 ON DELETE 处理引用了这一行的子表
+  fn plan_delete_cascade(&mut self, table_name: &str, row_id: i64) -> Result<()> {
+    let table = self.database.get_table(table_name.to_string())?;
+    for (child_table_name, foreign_key) in self.database.get_referencing_foreign_keys(table_name) {
+      let values = foreign_key
+        .referred_column_names
+        .iter()
+        .map(|column_name| table.get_value(column_name, &row_id))
+        .collect::<Result<Vec<Value>>>()?;
+      let child_row_ids = self.get_referencing_row_ids(&child_table_name, &foreign_key, &values)?;
+      if child_row_ids.is_empty() { continue; }
+
+      match foreign_key.on_delete {
+        ForeignKeyAction::Restrict => {
+          return Err(get_foreign_key_violation_error(&child_table_name, &foreign_key));
+        },
+        ForeignKeyAction::Cascade => {
+          for child_row_id in child_row_ids {
+            self.plan_delete(&child_table_name, child_row_id)?;
+          }
+        },
+        ForeignKeyAction::SetNull => {
+          let updated_values = foreign_key
+            .column_names
+            .iter()
+            .map(|column_name| (column_name.to_string(), Value::Null))
+            .collect::<Vec<(String, Value)>>();
+          for child_row_id in child_row_ids {
+            self.plan_update(&child_table_name, child_row_id, updated_values.clone())?;
+          }
+        },
+      }
+    }
+
+    Ok(())
+  }
+
+  // 子表中的行因为父表的修改而更新，更新之后它自己也可能被别的表引用
+  fn plan_update(&mut self, table_name: &str, row_id: i64, updated_values: Vec<(String, Value)>) -> Result<()> {
+    if self.deleted_rows.contains(&(table_name.to_string(), row_id))
+      || !self.updated_rows.insert(format!("{}:{}:{:?}", table_name, row_id, updated_values)) {
+      return Ok(());
+    }
+
+    let table = self.database.get_table(table_name.to_string())?;
+    let updated_row = vec![(row_id, updated_values)];
+    table.check_update_constraint(&updated_row)?;
+    let (row_id, updated_values) = updated_row.into_iter().next().unwrap();
+
+    self.plan_update_cascade(table_name, row_id, &updated_values)?;
+    self.operations.push(ForeignKeyOperation::Update {
+      table_name: table_name.to_string(),
+      row_id,
+      updated_values,
+    });
+
+    Ok(())
+  }
+
+  // 被引用的列的值改变的话，按照 ON UPDATE 处理引用了这一行的子表
+  fn plan_update_cascade(&mut self, table_name: &str, row_id: i64, updated_values: &[(String, Value)]) -> Result<()> {
+    let table = self.database.get_table(table_name.to_string())?;
+    for (child_table_name, foreign_key) in self.database.get_referencing_foreign_keys(table_name) {
+      let old_values = foreign_key
+        .referred_column_names
+        .iter()
+        .map(|column_name| table.get_value(column_name, &row_id))
+        .collect::<Result<Vec<Value>>>()?;
+      let new_values = foreign_key
+        .referred_column_names
+        .iter()
+        .zip(old_values.iter())
+        .map(|(column_name, old_value)| match updated_values.iter().find(|(name, _)| name == column_name) {
+          Some((_, value)) => value.clone(),
+          None => old_value.clone(),
+        })
+        .collect::<Vec<Value>>();
+      if old_values == new_values { continue; }
+
+      let child_row_ids = self.get_referencing_row_ids(&child_table_name, &foreign_key, &old_values)?;
+      if child_row_ids.is_empty() { continue; }
+
+      let child_updated_values = match foreign_key.on_update {
+        ForeignKeyAction::Restrict => {
+          return Err(get_foreign_key_violation_error(&child_table_name, &foreign_key));
+        },
+        ForeignKeyAction::Cascade => {
+          let child_table = self.database.get_table(child_table_name.to_string())?;
+          foreign_key
+            .column_names
+            .iter()
+            .zip(new_values.iter())
+            .map(|(column_name, value)| Ok((column_name.to_string(), child_table.cast_value(column_name, value)?)))
+            .collect::<Result<Vec<(String, Value)>>>()?
+        },
+        ForeignKeyAction::SetNull => foreign_key
+          .column_names
+          .iter()
+          .map(|column_name| (column_name.to_string(), Value::Null))
+          .collect::<Vec<(String, Value)>>(),
+      };
+      for child_row_id in child_row_ids {
+        self.plan_update(&child_table_name, child_row_id, child_updated_values.clone())?;
+      }
+    }
+
+    Ok(())
+  }
+}
+
+// DELETE 之前，按照 ON DELETE 找出子表中要一起删除或者设为 NULL 的行
+// 有 RESTRICT 的外键还被引用的话直接报错，这个时候还没有修改任何表
+// 返回的操作中包括删除 row_ids 本身
+pub fn get_delete_operations(
+  database: &Database,
+  table_name: &str,
+  row_ids: &[i64],
+) -> Result<Vec<ForeignKeyOperation>> {
+  let mut planner = ForeignKeyOperationPlanner::new(database);
+  // 先把要删除的行都标记上，这些行之间互相引用的话不算违反约束
+  for row_id in row_ids {
+    if planner.deleted_rows.insert((table_name.to_string(), *row_id)) {
+      planner.operations.push(ForeignKeyOperation::Delete { table_name: table_name.to_string(), row_id: *row_id });
+    }
+  }
+  for row_id in row_ids {
+    planner.plan_delete_cascade(table_name, *row_id)?;
+  }
+
+  Ok(planner.operations)
+}
+
+// UPDATE 之前，按照 ON UPDATE 找出子表中要一起更新或者设为 NULL 的行
+// 返回的操作中不包括 updated_rows 本身
+pub fn get_update_operations(
+  database: &Database,
+  table_name: &str,
+  updated_rows: &[(i64, Vec<(String, Value)>)],
+) -> Result<Vec<ForeignKeyOperation>> {
+  let mut planner = ForeignKeyOperationPlanner::new(database);
+  for (row_id, updated_values) in updated_rows {
+    planner.plan_update_cascade(table_name, *row_id, updated_values)?;
+  }
+
+  Ok(planner.operations)
+}
+
+pub fn apply_foreign_key_operations(database: &mut Database, operations: &[ForeignKeyOperation]) -> Result<()> {
+  for operation in operations {
+    match operation {
+      ForeignKeyOperation::Delete { table_name, row_id } => {
+        database.get_table_mut(table_name.to_string())?.delete_row(*row_id);
+      },
+      ForeignKeyOperation::Update { table_name, row_id, updated_values } => {
+        database.get_table_mut(table_name.to_string())?.update_row(*row_id, updated_values)?;
+      },
+    }
+  }
+
+  Ok(())
+}
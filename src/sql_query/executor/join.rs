@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use sqlparser::ast::{
+  Expr,
+  BinaryOperator,
+};
+
+use crate::error::Result;
+use crate::table::column::Column;
+use crate::table::column::data_type::DataType;
+use crate::table::row::value::Value;
+use crate::table::row::date_time::Timestamp;
+use crate::table::row::decimal::Decimal;
+use crate::sql_query::query::select::JoinKind;
+use crate::sql_query::expression::{
+  RowContext,
+  TableRow,
+  evaluate_expression,
+  evaluate_predicate,
+};
+
+use super::planner::{get_table_description, get_index_description};
+use super::table_function::TableFunction;
+
+// 拿右表中可能和左边这一行匹配上的 row id 的方式
+enum JoinStrategy {
+  // 右表的 JOIN key 上有索引，直接拿左边的值去索引里面找
+  IndexNestedLoop { left_expr: Expr, column_name: String },
+  // 先对右表的 JOIN key 建一个哈希表，再拿左边的值去哈希表里面找
+  HashJoin { left_expr: Expr, column_name: String, hash_table: HashMap<String, Vec<i64>> },
+  // 没有可以利用的等值条件，只能和右表的每一行都试一下
+  NestedLoop { row_ids: Vec<i64> },
+  // 右边是表值函数，只需要试一下左边这一行的参数值展开出来的行
+  TableFunction,
+}
+
+// JOIN 条件中的列引用属于哪一边
+enum ColumnSide {
+  Left,
+  // 右表的列名
+  Right(String),
+}
+
+// 把右边的表和左边已经 JOIN 好的行连接起来
+// right_table_row 的 row_id 为 None，只用来表示右边是哪张表
+// 右边是表值函数的话 table_function 为展开之后的结果
+pub fn join_table<'a>(
+  left_row_contexts: Vec<RowContext<'a>>,
+  right_table_row: &TableRow<'a>,
+  join_kind: &JoinKind,
+  constraint: &Option<Expr>,
+  table_function: Option<&TableFunction>,
+) -> Result<Vec<RowContext<'a>>> {
+  let right_table = right_table_row.table;
+  let left_table_rows = match left_row_contexts.first() {
+    Some(row_context) => &row_context.table_rows[..],
+    None => &[],
+  };
+  let join_strategy = match constraint
+    .as_ref()
+    .filter(|_| table_function.is_none())
+    .and_then(|expr| get_join_key(expr, left_table_rows, right_table_row)) {
+      Some((left_expr, column_name)) => {
+        let table_column = right_table.get_column(column_name.to_string())?;
+        if is_index_nested_loop(table_column) {
+          JoinStrategy::IndexNestedLoop { left_expr, column_name }
+        } else {
+          let row_ids = right_table.get_row_ids();
+          let values = right_table.get_column_values(&column_name, Some(&row_ids))?;
+          let mut hash_table: HashMap<String, Vec<i64>> = HashMap::new();
+          for (row_id, value) in row_ids.into_iter().zip(values.iter()) {
+            if let Some(hash_key) = get_hash_key(value) {
+              hash_table.entry(hash_key).or_default().push(row_id);
+            }
+          }
+          JoinStrategy::HashJoin { left_expr, column_name, hash_table }
+        }
+      },
+      None if table_function.is_some() => JoinStrategy::TableFunction,
+      None => JoinStrategy::NestedLoop { row_ids: right_table.get_row_ids() },
+  };
+
+  let mut row_contexts: Vec<RowContext> = vec![];
+  for left_row_context in left_row_contexts {
+    let row_ids = match &join_strategy {
+      JoinStrategy::IndexNestedLoop { left_expr, column_name } => {
+        let value = evaluate_expression(left_expr, &left_row_context)?;
+        // 类型转换不了的值不可能和这一列上的值相等
+        match right_table.cast_value(column_name, &value) {
+          Ok(value) => right_table
+            .get_column(column_name.to_string())?
+            .index
+            .get_row_ids(&value),
+          Err(_) => vec![],
+        }
+      },
+      JoinStrategy::HashJoin { left_expr, column_name, hash_table } => {
+        let value = evaluate_expression(left_expr, &left_row_context)?;
+        // Text 和 Date 之类的值比较时会先解析成对应的类型，所以这里也先转换成右表这一列的类型
+        // JSON 的标量是按照 SQL 值比较的，所以 Text 只有解析成 JSON 的数组或者对象时才需要转换
+        let value = match (&value, right_table.cast_value(column_name, &value)) {
+          (
+            Value::Text(_),
+            Ok(cast_value @ (Value::Date(_) | Value::Time(_) | Value::Timestamp(_) | Value::Decimal(_))),
+          ) => cast_value,
+          (Value::Text(_), Ok(Value::Json(json))) if json.is_container() => Value::Json(json),
+          _ => value,
+        };
+        get_hash_key(&value)
+          .and_then(|hash_key| hash_table.get(&hash_key))
+          .cloned()
+          .unwrap_or_default()
+      },
+      JoinStrategy::NestedLoop { row_ids } => row_ids.clone(),
+      JoinStrategy::TableFunction => match table_function {
+        Some(table_function) => table_function.get_row_ids(&left_row_context)?,
+        None => vec![],
+      },
+    };
+
+    // 索引和哈希表只是帮忙缩小范围，最后还是要用完整的 ON 条件判断一下
+    let mut is_matched = false;
+    for row_id in row_ids {
+      let mut row_context = left_row_context.clone();
+      row_context.table_rows.push(TableRow {
+        row_id: Some(row_id),
+        ..right_table_row.clone()
+      });
+      if let Some(expr) = constraint {
+        if !evaluate_predicate(expr, &row_context)? { continue; }
+      }
+      is_matched = true;
+      row_contexts.push(row_context);
+    }
+
+    // LEFT JOIN 没有匹配上的话，右表的列都是 NULL
+    if !is_matched && *join_kind == JoinKind::LeftOuter {
+      let mut row_context = left_row_context;
+      row_context.table_rows.push(right_table_row.clone());
+      row_contexts.push(row_context);
+    }
+  }
+
+  Ok(row_contexts)
+}
+
+// EXPLAIN 时描述怎样 JOIN 右表，和 join_table 中选择的方式保持一致
+// 返回 JOIN 的算法以及右表是怎样被访问的
+pub fn explain_join_table(
+  left_table_rows: &[TableRow],
+  right_table_row: &TableRow,
+  constraint: &Option<Expr>,
+  is_table_function: bool,
+) -> Result<(String, String)> {
+  let table_description = get_table_description(right_table_row);
+  if is_table_function {
+    return Ok(("TABLE FUNCTION".to_string(), format!("SCAN {} VIRTUAL TABLE", table_description)));
+  }
+  match constraint
+    .as_ref()
+    .and_then(|expr| get_join_key(expr, left_table_rows, right_table_row)) {
+      Some((_, column_name)) => {
+        if is_index_nested_loop(right_table_row.table.get_column(column_name.to_string())?) {
+          Ok((
+            "INDEX NESTED LOOP".to_string(),
+            format!(
+              "SEARCH {} USING {} ({}=?)",
+              table_description,
+              get_index_description(right_table_row.table, &column_name),
+              column_name
+            ),
+          ))
+        } else {
+          Ok((
+            "HASH JOIN".to_string(),
+            format!("BUILD HASH TABLE ON {} ({})", table_description, column_name),
+          ))
+        }
+      },
+      None => Ok(("NESTED LOOP".to_string(), format!("SCAN {}", table_description))),
+  }
+}
+
+// 右表的 JOIN key 上有索引的话可以直接拿左边的值去索引里面找
+// JSON 的标量是按照 SQL 值比较的（比如 1 和 1.0 相等），和索引中的 key 不一致，所以 JSON 列不用索引
+fn is_index_nested_loop(table_column: &Column) -> bool {
+  table_column.is_indexed && table_column.column_datatype != DataType::Json
+}
+
+// 从 ON 条件中找出一个 左边的列 = 右表的列 这样的等值条件
+// 返回左边的表达式以及右表的列名
+fn get_join_key(
+  expr: &Expr,
+  left_table_rows: &[TableRow],
+  right_table_row: &TableRow,
+) -> Option<(Expr, String)> {
+  match expr {
+    Expr::Nested(expr) => get_join_key(expr, left_table_rows, right_table_row),
+    Expr::BinaryOp { left, op: BinaryOperator::And, right } =>
+      get_join_key(left, left_table_rows, right_table_row)
+        .or_else(|| get_join_key(right, left_table_rows, right_table_row)),
+    Expr::BinaryOp { left, op: BinaryOperator::Eq, right } => {
+      match (
+        get_column_side(left, left_table_rows, right_table_row),
+        get_column_side(right, left_table_rows, right_table_row),
+      ) {
+        (Some(ColumnSide::Right(column_name)), Some(ColumnSide::Left)) =>
+          Some((*right.clone(), column_name)),
+        (Some(ColumnSide::Left), Some(ColumnSide::Right(column_name))) =>
+          Some((*left.clone(), column_name)),
+        _ => None,
+      }
+    },
+    _ => None,
+  }
+}
+
+// 判断一个列引用是右表的列还是左边某张表的列
+// 不是列引用或者有歧义的话返回 None
+fn get_column_side(
+  expr: &Expr,
+  left_table_rows: &[TableRow],
+  right_table_row: &TableRow,
+) -> Option<ColumnSide> {
+  match expr {
+    Expr::Identifier(ident) => {
+      let column_name = ident.to_string();
+      let is_left_column = left_table_rows
+        .iter()
+        .any(|table_row| table_row.table.has_column(column_name.to_string()));
+      match (is_left_column, right_table_row.table.has_column(column_name.to_string())) {
+        (false, true) => Some(ColumnSide::Right(column_name)),
+        (true, false) => Some(ColumnSide::Left),
+        _ => None,
+      }
+    },
+    Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+      [prefix, ident] if prefix.to_string() == right_table_row.table_name
+        && right_table_row.table.has_column(ident.to_string()) =>
+          Some(ColumnSide::Right(ident.to_string())),
+      [prefix, _] if left_table_rows
+        .iter()
+        .any(|table_row| table_row.table_name == prefix.to_string()) => Some(ColumnSide::Left),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+// 能够相等的值才会有相同的 key，NULL 和任何值都不相等
+// 数字统一用 Decimal 的写法，这样 1、1.0 以及 DECIMAL 的 1 是同一个 key；DATE 当作当天 00:00:00 的 TIMESTAMP
+fn get_hash_key(value: &Value) -> Option<String> {
+  match value {
+    Value::Null => None,
+    Value::Integer(i) => Some(format!("number:{}", Decimal::from(*i))),
+    Value::Real(r) => match Decimal::from_real(*r) {
+      Some(d) => Some(format!("number:{}", d)),
+      None => Some(format!("number:{}", r)),
+    },
+    Value::Decimal(d) => Some(format!("number:{}", d)),
+    Value::Text(s) => Some(format!("text:{}", s)),
+    Value::Bool(b) => Some(format!("bool:{}", b)),
+    Value::Blob(_) => Some(format!("blob:{}", value)),
+    Value::Date(d) => Some(format!("timestamp:{}", Timestamp::from(*d))),
+    Value::Timestamp(ts) => Some(format!("timestamp:{}", ts)),
+    Value::Time(t) => Some(format!("time:{}", t)),
+    // JSON 的标量和对应的 SQL 值相等
+    Value::Json(json) => match json.get_sql_value() {
+      Value::Json(_) => Some(format!("json:{}", json)),
+      value => get_hash_key(&value),
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::test_helpers::{parse_expression, create_test_database};
+
+  #[rstest]
+  #[case("o.user_id = u.id", Some(("o.user_id", "id")))]
+  #[case("u.id = o.user_id AND o.amount > 1", Some(("o.user_id", "id")))]
+  #[case("user_id = name", Some(("user_id", "name")))]
+  #[case("o.amount > u.id", None)]
+  #[case("id = u.id", None)]
+  fn test_get_join_key(
+    #[case] input: &str,
+    #[case] expected: Option<(&str, &str)>,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);",
+      "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER, amount INTEGER);",
+      "INSERT INTO orders (user_id, amount) VALUES (1, 10);",
+    ]);
+    let orders = database.get_table("orders".to_string()).unwrap();
+    let users = database.get_table("users".to_string()).unwrap();
+    let left_table_rows = vec![TableRow { table_name: "o".to_string(), table: orders, row_id: Some(1) }];
+    let right_table_row = TableRow { table_name: "u".to_string(), table: users, row_id: None };
+
+    let expr = parse_expression(input);
+    let join_key = get_join_key(&expr, &left_table_rows, &right_table_row)
+      .map(|(left_expr, column_name)| (left_expr.to_string(), column_name));
+
+    assert_eq!(
+      join_key,
+      expected.map(|(left_expr, column_name)| (left_expr.to_string(), column_name.to_string()))
+    );
+  }
+}
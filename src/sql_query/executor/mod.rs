@@ -0,0 +1,1408 @@
+pub mod result_set;
+pub mod join;
+pub mod planner;
+pub mod explain;
+pub mod foreign_key;
+pub mod table_function;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use sqlparser::ast::{
+  SelectItem,
+  Expr,
+  Ident,
+  OrderByExpr,
+  Value as SQLValue,
+};
+
+use crate::error::{Result, NollaDBError};
+use crate::database::Database;
+use crate::table::Table;
+use crate::table::row::value::Value;
+use crate::sql_query::query::select::{SelectQuery, SelectTable};
+use crate::sql_query::query::update::UpdateQuery;
+use crate::sql_query::query::delete::DeleteQuery;
+use crate::sql_query::expression::{
+  RowContext,
+  TableRow,
+  evaluate_expression,
+  evaluate_predicate,
+};
+use crate::sql_query::expression::json::get_expr_display;
+use crate::sql_query::expression::aggregate::{
+  collect_aggregate_functions,
+  contains_aggregate_function,
+  evaluate_aggregate_function,
+};
+
+use result_set::ResultSet;
+use planner::get_scan_plan;
+use table_function::TableFunction;
+use foreign_key::{
+  check_foreign_key_values,
+  get_update_operations,
+  get_delete_operations,
+  apply_foreign_key_operations,
+};
+
+// 拿到表中满足 WHERE 条件的所有 row id
+// UPDATE 以及 DELETE 都需要用到，能用索引的话只检查索引找出来的行
+pub fn get_matched_row_ids(table: &Table, selection: &Option<Expr>) -> Result<Vec<i64>> {
+  let table_rows = vec![TableRow {
+    table_name: table.table_name.to_string(),
+    table,
+    row_id: None,
+  }];
+  let mut row_ids: Vec<i64> = vec![];
+  for row_id in get_scan_plan(&table_rows, selection).get_row_ids(table)? {
+    let is_matched = match selection {
+      Some(expr) => evaluate_predicate(expr, &RowContext::new(table, row_id))?,
+      None => true,
+    };
+    if is_matched {
+      row_ids.push(row_id);
+    }
+  }
+
+  Ok(row_ids)
+}
+
+// 展开 FROM 中的表值函数，普通的表对应 None
+// 表值函数的参数可以引用前面的表，所以先把前面的表 JOIN 好，再对每一行的参数值展开
+fn expand_table_functions(
+  tables: &[SelectTable],
+  database: &Database,
+) -> Result<Vec<Option<TableFunction>>> {
+  let mut table_functions: Vec<Option<TableFunction>> = vec![];
+  for (i, select_table) in tables.iter().enumerate() {
+    let table_function = match &select_table.function_args {
+      Some(args) => {
+        let table_rows = get_select_table_rows(&tables[..i], database, &table_functions)?;
+        let row_contexts = get_matched_row_contexts(&tables[..i], &table_rows, &None, &table_functions)?;
+        Some(TableFunction::new(select_table, args, &row_contexts)?)
+      },
+      None => None,
+    };
+    table_functions.push(table_function);
+  }
+
+  Ok(table_functions)
+}
+
+// 拿到 FROM 中的每一张表，row_id 都为 None
+// 表值函数用展开之后的临时表
+fn get_select_table_rows<'a>(
+  tables: &[SelectTable],
+  database: &'a Database,
+  table_functions: &'a [Option<TableFunction>],
+) -> Result<Vec<TableRow<'a>>> {
+  let mut table_rows: Vec<TableRow> = vec![];
+  for (i, select_table) in tables.iter().enumerate() {
+    let table_name = select_table.get_reference_name();
+    if table_rows.iter().any(|table_row| table_row.table_name == table_name) {
+      return Err(NollaDBError::Internal(
+        format!("Not unique table or alias: '{}'", table_name)
+      ));
+    }
+    let table = match table_functions.get(i) {
+      Some(Some(table_function)) => &table_function.table,
+      _ => database.get_table(select_table.table_name.to_string())?,
+    };
+    table_rows.push(TableRow {
+      table_name,
+      table,
+      row_id: None,
+    });
+  }
+
+  Ok(table_rows)
+}
+
+// 把一张表的所有列展开成 projection
+// 只有一张表的时候不需要带上表名前缀
+fn push_table_columns(
+  projection_exprs: &mut Vec<(String, Expr)>,
+  table_row: &TableRow,
+  is_qualified: bool,
+) {
+  for table_column in &table_row.table.table_columns {
+    let column_name = table_column.column_name.to_string();
+    let expr = if is_qualified {
+      Expr::CompoundIdentifier(vec![
+        Ident::new(table_row.table_name.to_string()),
+        Ident::new(column_name.to_string()),
+      ])
+    } else {
+      Expr::Identifier(Ident::new(column_name.to_string()))
+    };
+    projection_exprs.push((column_name, expr));
+  }
+}
+
+// 把 projection 展开成 (输出的列名, 对应的表达式)
+fn get_projection_exprs(
+  projection: &[SelectItem],
+  table_rows: &[TableRow],
+) -> Result<Vec<(String, Expr)>> {
+  let mut projection_exprs: Vec<(String, Expr)> = vec![];
+  for select_item in projection {
+    match select_item {
+      SelectItem::Wildcard => {
+        if table_rows.is_empty() {
+          return Err(NollaDBError::Internal("No tables specified".to_string()));
+        }
+        for table_row in table_rows {
+          push_table_columns(&mut projection_exprs, table_row, table_rows.len() > 1);
+        }
+      },
+      SelectItem::QualifiedWildcard(prefix) => {
+        match table_rows
+          .iter()
+          .find(|table_row| table_row.table_name == prefix.to_string()) {
+            Some(table_row) => push_table_columns(&mut projection_exprs, table_row, true),
+            None => return Err(NollaDBError::Internal(
+              format!("Unknown table '{}'", prefix)
+            )),
+        }
+      },
+      SelectItem::UnnamedExpr(expr) => {
+        // 如果是列名的话，输出时就不带上表名前缀
+        let output_column_name = match expr {
+          Expr::CompoundIdentifier(idents) => idents
+            .last()
+            .map_or(expr.to_string(), |ident| ident.to_string()),
+          _ => get_expr_display(expr),
+        };
+        projection_exprs.push((output_column_name, expr.clone()));
+      },
+      SelectItem::ExprWithAlias { expr, alias } => {
+        projection_exprs.push((alias.to_string(), expr.clone()));
+      },
+    }
+  }
+
+  Ok(projection_exprs)
+}
+
+// 对 FROM 中的表做 JOIN，返回满足 WHERE 条件的所有行
+// 第一张表能用索引的话只从索引找出来的行开始 JOIN
+// 没有 FROM 的话只有一行，这一行没有任何列
+fn get_matched_row_contexts<'a>(
+  tables: &[SelectTable],
+  table_rows: &[TableRow<'a>],
+  selection: &Option<Expr>,
+  table_functions: &[Option<TableFunction>],
+) -> Result<Vec<RowContext<'a>>> {
+  let mut row_contexts = match table_rows.first() {
+    Some(table_row) => get_scan_plan(table_rows, selection)
+      .get_row_ids(table_row.table)?
+      .into_iter()
+      .map(|row_id| RowContext {
+        table_rows: vec![TableRow { row_id: Some(row_id), ..table_row.clone() }],
+        ..RowContext::default()
+      })
+      .collect::<Vec<RowContext>>(),
+    None => vec![RowContext::default()],
+  };
+
+  for (i, (select_table, table_row)) in tables.iter().zip(table_rows.iter()).enumerate().skip(1) {
+    if let Some((join_kind, constraint)) = &select_table.join {
+      let table_function = table_functions.get(i).and_then(Option::as_ref);
+      row_contexts = join::join_table(row_contexts, table_row, join_kind, constraint, table_function)?;
+    }
+  }
+
+  match selection {
+    Some(expr) => {
+      let mut matched_row_contexts: Vec<RowContext> = vec![];
+      for row_context in row_contexts {
+        if evaluate_predicate(expr, &row_context)? {
+          matched_row_contexts.push(row_context);
+        }
+      }
+      Ok(matched_row_contexts)
+    },
+    None => Ok(row_contexts),
+  }
+}
+
+// ORDER BY 中的排序 key
+enum OrderByKey {
+  // 引用了 projection 中的某一列，比如别名或者 ORDER BY 2 这种位置
+  Projection(usize),
+  Expr(Expr),
+}
+
+fn get_order_by_keys<'a>(
+  order_by: &'a [OrderByExpr],
+  projection_exprs: &[(String, Expr)],
+) -> Result<Vec<(OrderByKey, &'a OrderByExpr)>> {
+  let mut order_by_keys: Vec<(OrderByKey, &OrderByExpr)> = vec![];
+  for order_by_expr in order_by {
+    let order_by_key = match &order_by_expr.expr {
+      Expr::Value(SQLValue::Number(n, _)) => match n.parse::<usize>() {
+        Ok(position) if position >= 1 && position <= projection_exprs.len() =>
+          OrderByKey::Projection(position - 1),
+        _ => return Err(NollaDBError::Internal(
+          format!("ORDER BY term out of range: {}", n)
+        )),
+      },
+      Expr::Identifier(ident) => match projection_exprs
+        .iter()
+        .position(|(output_column_name, _)| *output_column_name == ident.to_string()) {
+          Some(position) => OrderByKey::Projection(position),
+          None => OrderByKey::Expr(order_by_expr.expr.clone()),
+      },
+      expr => OrderByKey::Expr(expr.clone()),
+    };
+    order_by_keys.push((order_by_key, order_by_expr));
+  }
+
+  Ok(order_by_keys)
+}
+
+// 如果第一个排序 key 就是带有唯一索引的列，那么可以直接按照索引的顺序来扫描，不用再排序
+// 返回这一列的列名以及是否是升序
+// 聚合查询以及多表 JOIN 的时候不能这样做
+fn get_order_by_index_column(
+  table_rows: &[TableRow],
+  is_aggregate_query: bool,
+  order_by_keys: &[(OrderByKey, &OrderByExpr)],
+  projection_exprs: &[(String, Expr)],
+) -> Option<(String, bool)> {
+  if is_aggregate_query || table_rows.len() > 1 {
+    return None;
+  }
+  let table_row = table_rows.first()?;
+  let (order_by_key, order_by_expr) = order_by_keys.first()?;
+  let expr = match order_by_key {
+    OrderByKey::Projection(position) => &projection_exprs[*position].1,
+    OrderByKey::Expr(expr) => expr,
+  };
+  let column_name = match expr {
+    Expr::Identifier(ident) => ident.to_string(),
+    Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+      [prefix, ident] if prefix.to_string() == table_row.table_name => ident.to_string(),
+      _ => return None,
+    },
+    _ => return None,
+  };
+
+  let table_column = table_row.table.get_column(column_name.to_string()).ok()?;
+  // NULL 不会写入索引，所以这一列还必须是 NOT NULL 的
+  if !table_column.is_indexed
+    || !table_column.is_unique_constraint
+    || !table_column.is_not_null_constraint {
+    return None;
+  }
+
+  Some((column_name, order_by_expr.asc.unwrap_or(true)))
+}
+
+fn compare_order_by_values(
+  a: &[Value],
+  b: &[Value],
+  order_by_keys: &[(OrderByKey, &OrderByExpr)],
+) -> Ordering {
+  for (i, (_, order_by_expr)) in order_by_keys.iter().enumerate() {
+    let is_asc = order_by_expr.asc.unwrap_or(true);
+    // 默认 NULL 是最小的，所以升序时 NULL 在前，降序时 NULL 在后
+    let is_nulls_first = order_by_expr.nulls_first.unwrap_or(is_asc);
+    let ordering = match (a[i].is_null(), b[i].is_null()) {
+      (true, true) => Ordering::Equal,
+      (true, false) => if is_nulls_first { Ordering::Less } else { Ordering::Greater },
+      (false, true) => if is_nulls_first { Ordering::Greater } else { Ordering::Less },
+      (false, false) => {
+        let ordering = a[i].compare_for_order_by(&b[i]);
+        if is_asc { ordering } else { ordering.reverse() }
+      },
+    };
+    if ordering != Ordering::Equal {
+      return ordering;
+    }
+  }
+
+  Ordering::Equal
+}
+
+// LIMIT 和 OFFSET 的值必须是整数，负数表示没有限制
+fn get_limit_or_offset_value(expr: &Option<Expr>) -> Result<Option<usize>> {
+  let expr = match expr {
+    Some(expr) => expr,
+    None => return Ok(None),
+  };
+
+  match evaluate_expression(expr, &RowContext::default())? {
+    Value::Integer(i) if i >= 0 => Ok(Some(i as usize)),
+    Value::Integer(_) => Ok(None),
+    value => Err(NollaDBError::TypeMismatch(
+      format!("LIMIT and OFFSET expect an integer, but found {} value '{}'", value.get_type_name(), value)
+    )),
+  }
+}
+
+// 聚合函数可能出现在 projection、HAVING 以及 ORDER BY 里面
+fn get_aggregate_exprs(
+  projection_exprs: &[(String, Expr)],
+  having: &Option<Expr>,
+  order_by_keys: &[(OrderByKey, &OrderByExpr)],
+) -> Vec<Expr> {
+  let mut aggregate_exprs: Vec<Expr> = vec![];
+  for (_, expr) in projection_exprs {
+    collect_aggregate_functions(expr, &mut aggregate_exprs);
+  }
+  if let Some(expr) = having {
+    collect_aggregate_functions(expr, &mut aggregate_exprs);
+  }
+  for (order_by_key, _) in order_by_keys {
+    if let OrderByKey::Expr(expr) = order_by_key {
+      collect_aggregate_functions(expr, &mut aggregate_exprs);
+    }
+  }
+
+  aggregate_exprs
+}
+
+// 有 GROUP BY 或者聚合函数的话就是聚合查询，只有聚合查询才能有 HAVING
+fn get_is_aggregate_query(
+  group_by: &[Expr],
+  having: &Option<Expr>,
+  aggregate_exprs: &[Expr],
+) -> Result<bool> {
+  let is_aggregate_query = !group_by.is_empty() || !aggregate_exprs.is_empty();
+  if having.is_some() && !is_aggregate_query {
+    return Err(NollaDBError::Internal(
+      "A GROUP BY clause is required before HAVING".to_string()
+    ));
+  }
+
+  Ok(is_aggregate_query)
+}
+
+// GROUP BY 中可以用 projection 的别名或者位置来引用 projection 中的某一列
+fn get_group_by_expr<'a>(
+  expr: &'a Expr,
+  table_rows: &[TableRow],
+  projection_exprs: &'a [(String, Expr)],
+) -> Result<&'a Expr> {
+  let group_by_expr = match expr {
+    Expr::Value(SQLValue::Number(n, _)) => match n.parse::<usize>() {
+      Ok(position) if position >= 1 && position <= projection_exprs.len() =>
+        &projection_exprs[position - 1].1,
+      _ => return Err(NollaDBError::Internal(
+        format!("GROUP BY term out of range: {}", n)
+      )),
+    },
+    Expr::Identifier(ident) if !table_rows
+      .iter()
+      .any(|table_row| table_row.table.has_column(ident.to_string())) => projection_exprs
+      .iter()
+      .find(|(output_column_name, _)| *output_column_name == ident.to_string())
+      .map_or(expr, |(_, projection_expr)| projection_expr),
+    _ => expr,
+  };
+
+  if contains_aggregate_function(group_by_expr) {
+    return Err(NollaDBError::Internal(
+      format!("Aggregate functions are not allowed in GROUP BY: {}", group_by_expr)
+    ));
+  }
+
+  Ok(group_by_expr)
+}
+
+// 按照 GROUP BY 对满足 WHERE 条件的行进行分组，计算每个分组上的聚合函数，再用 HAVING 过滤分组
+// 每个分组对应一个 RowContext，分组里面直接引用的列取的是分组中第一行的值
+fn get_group_row_contexts<'a>(
+  row_contexts: Vec<RowContext<'a>>,
+  table_rows: &[TableRow<'a>],
+  group_by: &[Expr],
+  having: &Option<Expr>,
+  projection_exprs: &[(String, Expr)],
+  aggregate_exprs: &[Expr],
+  is_all_rows: bool,
+) -> Result<Vec<RowContext<'a>>> {
+  let group_by_exprs = group_by
+    .iter()
+    .map(|expr| get_group_by_expr(expr, table_rows, projection_exprs))
+    .collect::<Result<Vec<&Expr>>>()?;
+
+  // (分组的 key, 分组中的行)
+  let mut groups: Vec<(Vec<Value>, Vec<RowContext>)> = vec![];
+  if group_by_exprs.is_empty() {
+    // 没有 GROUP BY 的时候所有的行都在同一个分组里面，即使一行都没有也要输出一行
+    groups.push((vec![], row_contexts));
+  } else {
+    // Value 里面有 f64，没法直接作为 HashMap 的 key，这里用 Debug 的输出来区分不同的分组
+    let mut group_positions: HashMap<String, usize> = HashMap::new();
+    for row_context in row_contexts {
+      let group_key = group_by_exprs
+        .iter()
+        .map(|expr| evaluate_expression(expr, &row_context))
+        .collect::<Result<Vec<Value>>>()?;
+      let group_key_string = format!("{:?}", group_key);
+      match group_positions.get(&group_key_string) {
+        Some(position) => groups[*position].1.push(row_context),
+        None => {
+          group_positions.insert(group_key_string, groups.len());
+          groups.push((group_key, vec![row_context]));
+        },
+      }
+    }
+
+    // 分组按照 key 从小到大输出
+    groups.sort_by(|(a, _), (b, _)| a
+      .iter()
+      .zip(b.iter())
+      .map(|(a, b)| a.compare_for_order_by(b))
+      .find(|ordering| *ordering != Ordering::Equal)
+      .unwrap_or(Ordering::Equal)
+    );
+  }
+
+  let mut group_row_contexts: Vec<RowContext> = vec![];
+  for (_, row_contexts) in groups {
+    let mut aggregate_values: HashMap<Expr, Value> = HashMap::new();
+    for expr in aggregate_exprs {
+      if let Expr::Function(function) = expr {
+        let value = evaluate_aggregate_function(function, &row_contexts, is_all_rows)?;
+        aggregate_values.insert(expr.clone(), value);
+      }
+    }
+
+    // 空的分组里面没有任何行，引用到的列都是 NULL
+    let mut group_row_context = match row_contexts.into_iter().next() {
+      Some(row_context) => row_context,
+      None => RowContext {
+        table_rows: table_rows.to_vec(),
+        ..RowContext::default()
+      },
+    };
+    group_row_context.aggregate_values = aggregate_values;
+    if let Some(expr) = having {
+      if !evaluate_predicate(expr, &group_row_context)? { continue; }
+    }
+    group_row_contexts.push(group_row_context);
+  }
+
+  Ok(group_row_contexts)
+}
+
+fn evaluate_projection_exprs(
+  projection_exprs: &[(String, Expr)],
+  row_context: &RowContext,
+) -> Result<Vec<Value>> {
+  projection_exprs
+    .iter()
+    .map(|(_, expr)| evaluate_expression(expr, row_context))
+    .collect::<Result<Vec<Value>>>()
+}
+
+pub fn execute_select_query(
+  select_query: &SelectQuery,
+  database: &Database,
+) -> Result<ResultSet> {
+  let SelectQuery {
+    tables,
+    projection,
+    selection,
+    group_by,
+    having,
+    order_by,
+    limit,
+    offset,
+  } = select_query;
+
+  let table_functions = expand_table_functions(tables, database)?;
+  let table_rows = get_select_table_rows(tables, database, &table_functions)?;
+
+  // 1. 解析 projection，拿到输出的列名以及对应的表达式
+  let projection_exprs = get_projection_exprs(projection, &table_rows)?;
+  let order_by_keys = get_order_by_keys(order_by, &projection_exprs)?;
+  let limit = get_limit_or_offset_value(limit)?;
+  let offset = get_limit_or_offset_value(offset)?.unwrap_or(0);
+
+  let aggregate_exprs = get_aggregate_exprs(&projection_exprs, having, &order_by_keys);
+  let is_aggregate_query = get_is_aggregate_query(group_by, having, &aggregate_exprs)?;
+
+  let mut result_set = ResultSet::new(
+    projection_exprs
+      .iter()
+      .map(|(output_column_name, _)| output_column_name.to_string())
+      .collect()
+  );
+
+  // 2. 过滤出满足 WHERE 条件的行，并按照 ORDER BY 排好序，然后对 projection 求值
+  let order_by_index_column = get_order_by_index_column(
+    &table_rows,
+    is_aggregate_query,
+    &order_by_keys,
+    &projection_exprs,
+  );
+  match order_by_index_column {
+    Some((column_name, is_asc)) => {
+      // 按照索引的顺序扫描，拿到的行已经是排好序的了
+      // 有 LIMIT 的话拿够 offset + limit 行就可以停下来
+      let table_row = &table_rows[0];
+      let mut row_ids = table_row.table.get_column(column_name)?.index.get_ordered_row_ids();
+      if !is_asc {
+        row_ids.reverse();
+      }
+      for row_id in row_ids {
+        if let Some(limit) = limit {
+          if result_set.rows.len() >= offset + limit { break; }
+        }
+
+        let row_context = RowContext {
+          table_rows: vec![TableRow { row_id: Some(row_id), ..table_row.clone() }],
+          ..RowContext::default()
+        };
+        if let Some(expr) = selection {
+          if !evaluate_predicate(expr, &row_context)? { continue; }
+        }
+        result_set.rows.push(evaluate_projection_exprs(&projection_exprs, &row_context)?);
+      }
+    },
+    None => {
+      // 聚合查询的话每个分组输出一行，否则每个满足条件的行输出一行
+      let mut row_contexts = get_matched_row_contexts(tables, &table_rows, selection, &table_functions)?;
+      if is_aggregate_query {
+        // 单表并且没有 WHERE 也没有 GROUP BY 的时候，聚合函数可以直接扫描整列
+        let is_all_rows = table_rows.len() == 1 && selection.is_none() && group_by.is_empty();
+        row_contexts = get_group_row_contexts(
+          row_contexts,
+          &table_rows,
+          group_by,
+          having,
+          &projection_exprs,
+          &aggregate_exprs,
+          is_all_rows,
+        )?;
+      }
+
+      // (projection 求值之后的一行, 这一行的排序 key)
+      let mut rows: Vec<(Vec<Value>, Vec<Value>)> = vec![];
+      for row_context in row_contexts {
+        let row = evaluate_projection_exprs(&projection_exprs, &row_context)?;
+        let order_by_values = order_by_keys
+          .iter()
+          .map(|(order_by_key, _)| match order_by_key {
+            OrderByKey::Projection(position) => Ok(row[*position].clone()),
+            OrderByKey::Expr(expr) => evaluate_expression(expr, &row_context),
+          })
+          .collect::<Result<Vec<Value>>>()?;
+        rows.push((row, order_by_values));
+      }
+
+      if !order_by_keys.is_empty() {
+        rows.sort_by(|(_, a), (_, b)| compare_order_by_values(a, b, &order_by_keys));
+      }
+      result_set.rows = rows.into_iter().map(|(row, _)| row).collect();
+    },
+  }
+
+  // 3. 处理 OFFSET 和 LIMIT
+  result_set.rows = result_set.rows
+    .into_iter()
+    .skip(offset)
+    .take(limit.unwrap_or(usize::MAX))
+    .collect();
+
+  Ok(result_set)
+}
+
+// 返回被更新的行数
+pub fn execute_update_query(
+  update_query: &UpdateQuery,
+  database: &mut Database,
+) -> Result<usize> {
+  let UpdateQuery {
+    table_name,
+    assignments,
+    selection,
+  } = update_query;
+
+  let table = database.get_table(table_name.to_string())?;
+
+  // 检查要更新的 column name 是否在表中存在
+  if let Some((column_name, _)) = assignments
+    .iter()
+    .find(|(column_name, _)| !table.has_column(column_name.to_string())) {
+    return Err(NollaDBError::Internal(
+      format!("Can not update, because column '{}' does not exist", column_name)
+    ));
+  }
+
+  // 1. 先对每一行求出要更新的值，这个时候还不修改表
+  let mut updated_rows: Vec<(i64, Vec<(String, Value)>)> = vec![];
+  for row_id in get_matched_row_ids(table, selection)? {
+    let row_context = RowContext::new(table, row_id);
+    let mut updated_values: Vec<(String, Value)> = vec![];
+    for (column_name, expr) in assignments {
+      let value = evaluate_expression(expr, &row_context)?;
+      updated_values.push((
+        column_name.to_string(),
+        table.cast_value(column_name, &value)?,
+      ));
+    }
+    updated_rows.push((row_id, updated_values));
+  }
+
+  // 2. 检查约束，外键上的值改变的话新的值要在父表中存在
+  table.check_update_constraint(&updated_rows)?;
+  for (row_id, updated_values) in &updated_rows {
+    let is_foreign_key_updated = table
+      .foreign_keys
+      .iter()
+      .flat_map(|foreign_key| foreign_key.column_names.iter())
+      .any(|column_name| updated_values.iter().any(|(name, _)| name == column_name));
+    if !is_foreign_key_updated { continue; }
+
+    check_foreign_key_values(database, table_name, |column_name| {
+      match updated_values.iter().find(|(name, _)| name == column_name) {
+        Some((_, value)) => Ok(value.clone()),
+        None => table.get_value(column_name, row_id),
+      }
+    })?;
+  }
+
+  // 3. 被引用的值改变的话按照 ON UPDATE 找出子表中要一起修改的行，全部通过之后再真正更新
+  let operations = get_update_operations(database, table_name, &updated_rows)?;
+  let table = database.get_table_mut(table_name.to_string())?;
+  for (row_id, updated_values) in &updated_rows {
+    table.update_row(*row_id, updated_values)?;
+  }
+  apply_foreign_key_operations(database, &operations)?;
+
+  Ok(updated_rows.len())
+}
+
+// 返回被删除的行数
+pub fn execute_delete_query(
+  delete_query: &DeleteQuery,
+  database: &mut Database,
+) -> Result<usize> {
+  let DeleteQuery {
+    table_name,
+    selection,
+  } = delete_query;
+
+  let table = database.get_table(table_name.to_string())?;
+
+  // 先找出所有要删除的 row id，以及按照 ON DELETE 要一起删除或者修改的子表中的行
+  // 全部通过之后再一行一行删除
+  let row_ids = get_matched_row_ids(table, selection)?;
+  let operations = get_delete_operations(database, table_name, &row_ids)?;
+  apply_foreign_key_operations(database, &operations)?;
+
+  Ok(row_ids.len())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::test_helpers::create_test_database;
+  use crate::sql_query::{handle_sql_query, get_sql_ast};
+
+  #[rstest]
+  #[case(
+    "SELECT * FROM test;",
+    vec!["id", "name"],
+    vec![
+      vec![Value::Integer(1), Value::Text("xxx".to_string())],
+      vec![Value::Integer(2), Value::Text("yyy".to_string())],
+    ],
+  )]
+  #[case(
+    "SELECT name AS n, test.id FROM test;",
+    vec!["n", "id"],
+    vec![
+      vec![Value::Text("xxx".to_string()), Value::Integer(1)],
+      vec![Value::Text("yyy".to_string()), Value::Integer(2)],
+    ],
+  )]
+  #[case(
+    "SELECT id * 10 AS x FROM test WHERE name <> 'xxx';",
+    vec!["x"],
+    vec![
+      vec![Value::Integer(20)],
+    ],
+  )]
+  #[case(
+    "SELECT * FROM test WHERE id > 5;",
+    vec!["id", "name"],
+    vec![],
+  )]
+  #[case(
+    "SELECT 1 + 1 AS x, 'abc';",
+    vec!["x", "'abc'"],
+    vec![
+      vec![Value::Integer(2), Value::Text("abc".to_string())],
+    ],
+  )]
+  #[case(
+    "SELECT 1 WHERE 1 > 2;",
+    vec!["1"],
+    vec![],
+  )]
+  fn test_execute_select_query(
+    #[case] query: &str,
+    #[case] expected_column_names: Vec<&str>,
+    #[case] expected_rows: Vec<Vec<Value>>,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "INSERT INTO test (name) VALUES ('xxx');",
+      "INSERT INTO test (name) VALUES ('yyy');",
+    ]);
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+
+    assert_eq!(result_set.column_names, expected_column_names);
+    assert_eq!(result_set.rows, expected_rows);
+  }
+
+  #[rstest]
+  #[case("SELECT * FROM test ORDER BY score DESC, name;", vec![3, 1, 4, 2])]
+  #[case("SELECT * FROM test ORDER BY score NULLS LAST, name DESC;", vec![2, 4, 1, 3])]
+  #[case("SELECT * FROM test ORDER BY id DESC LIMIT 2;", vec![4, 3])]
+  #[case("SELECT * FROM test ORDER BY id LIMIT 2 OFFSET 1;", vec![2, 3])]
+  #[case("SELECT id AS x FROM test WHERE id > 1 ORDER BY x DESC LIMIT 1 OFFSET 1;", vec![3])]
+  #[case("SELECT id, name FROM test ORDER BY 2 DESC;", vec![2, 3, 4, 1])]
+  #[case("SELECT * FROM test LIMIT 0;", vec![])]
+  #[case("SELECT * FROM test LIMIT 2 OFFSET 3;", vec![4])]
+  fn test_execute_select_query_order_by_limit_offset(
+    #[case] query: &str,
+    #[case] expected_ids: Vec<i64>,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER);",
+      "INSERT INTO test (name, score) VALUES ('a', 2);",
+      "INSERT INTO test (name, score) VALUES ('d', 1);",
+      "INSERT INTO test (name, score) VALUES ('c', 3);",
+      "INSERT INTO test (name, score) VALUES ('b', 2);",
+    ]);
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+
+    let ids = result_set.rows
+      .iter()
+      .map(|row| match row[0] {
+        Value::Integer(id) => id,
+        _ => panic!("Expected integer id"),
+      })
+      .collect::<Vec<i64>>();
+    assert_eq!(ids, expected_ids);
+  }
+
+  #[rstest]
+  #[case("SELECT COUNT(*), SUM(score), MIN(name), MAX(score) FROM test;", vec![
+    vec![Value::Integer(4), Value::Integer(8), Value::Text("a".to_string()), Value::Integer(3)],
+  ])]
+  #[case("SELECT COUNT(*), AVG(score) FROM test WHERE id > 4;", vec![
+    vec![Value::Integer(0), Value::Null],
+  ])]
+  #[case("SELECT score, COUNT(*) AS c FROM test GROUP BY score;", vec![
+    vec![Value::Integer(1), Value::Integer(1)],
+    vec![Value::Integer(2), Value::Integer(2)],
+    vec![Value::Integer(3), Value::Integer(1)],
+  ])]
+  #[case("SELECT score, COUNT(*) FROM test GROUP BY 1 HAVING COUNT(*) > 1;", vec![
+    vec![Value::Integer(2), Value::Integer(2)],
+  ])]
+  #[case("SELECT score AS s, SUM(id) FROM test WHERE id > 1 GROUP BY s ORDER BY SUM(id) DESC;", vec![
+    vec![Value::Integer(2), Value::Integer(4)],
+    vec![Value::Integer(3), Value::Integer(3)],
+    vec![Value::Integer(1), Value::Integer(2)],
+  ])]
+  #[case("SELECT COUNT(*) * 10 + 1 FROM test GROUP BY score ORDER BY 1 DESC LIMIT 1;", vec![
+    vec![Value::Integer(21)],
+  ])]
+  fn test_execute_select_query_aggregate(
+    #[case] query: &str,
+    #[case] expected_rows: Vec<Vec<Value>>,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER);",
+      "INSERT INTO test (name, score) VALUES ('a', 2);",
+      "INSERT INTO test (name, score) VALUES ('d', 1);",
+      "INSERT INTO test (name, score) VALUES ('c', 3);",
+      "INSERT INTO test (name, score) VALUES ('b', 2);",
+    ]);
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+
+    assert_eq!(result_set.rows, expected_rows);
+  }
+
+  #[rstest]
+  #[case("SELECT * FROM test HAVING id > 1;")]
+  #[case("SELECT COUNT(*) FROM test WHERE COUNT(*) > 1;")]
+  #[case("SELECT COUNT(*) FROM test GROUP BY COUNT(*);")]
+  #[case("SELECT SUM(name) FROM test;")]
+  fn test_execute_select_query_aggregate_error(#[case] query: &str) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER);",
+      "INSERT INTO test (name, score) VALUES ('a', 2);",
+      "INSERT INTO test (name, score) VALUES ('d', 1);",
+      "INSERT INTO test (name, score) VALUES ('c', 3);",
+      "INSERT INTO test (name, score) VALUES ('b', 2);",
+    ]);
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert!(execute_select_query(&select_query, &database).is_err());
+  }
+
+  #[rstest]
+  #[case(
+    "SELECT o.id, u.name FROM orders AS o JOIN users AS u ON o.user_id = u.id;",
+    vec![
+      vec![Value::Integer(1), Value::Text("alice".to_string())],
+      vec![Value::Integer(2), Value::Text("alice".to_string())],
+      vec![Value::Integer(3), Value::Text("bob".to_string())],
+    ]
+  )]
+  #[case(
+    "SELECT u.name, o.amount FROM users u LEFT JOIN orders o ON o.user_id = u.id ORDER BY u.id, o.id;",
+    vec![
+      vec![Value::Text("alice".to_string()), Value::Integer(10)],
+      vec![Value::Text("alice".to_string()), Value::Integer(20)],
+      vec![Value::Text("bob".to_string()), Value::Integer(5)],
+      vec![Value::Text("carol".to_string()), Value::Null],
+    ]
+  )]
+  #[case(
+    "SELECT name, amount FROM users LEFT JOIN orders ON users.id = user_id AND amount > 5 ORDER BY 1, 2;",
+    vec![
+      vec![Value::Text("alice".to_string()), Value::Integer(10)],
+      vec![Value::Text("alice".to_string()), Value::Integer(20)],
+      vec![Value::Text("bob".to_string()), Value::Null],
+      vec![Value::Text("carol".to_string()), Value::Null],
+    ]
+  )]
+  #[case(
+    "SELECT COUNT(*) FROM users CROSS JOIN orders;",
+    vec![vec![Value::Integer(12)]]
+  )]
+  #[case(
+    "SELECT u.name, SUM(o.amount) FROM users u JOIN orders o ON u.id = o.user_id GROUP BY u.name ORDER BY 2 DESC;",
+    vec![
+      vec![Value::Text("alice".to_string()), Value::Integer(30)],
+      vec![Value::Text("bob".to_string()), Value::Integer(5)],
+    ]
+  )]
+  #[case(
+    "SELECT * FROM users, orders WHERE users.id = orders.user_id AND orders.amount > 15;",
+    vec![vec![
+      Value::Integer(1), Value::Text("alice".to_string()),
+      Value::Integer(2), Value::Integer(1), Value::Integer(20),
+    ]]
+  )]
+  #[case(
+    "SELECT o.* FROM users u JOIN orders o ON o.amount > u.id * 10;",
+    vec![vec![Value::Integer(2), Value::Integer(1), Value::Integer(20)]]
+  )]
+  fn test_execute_select_query_join(
+    #[case] query: &str,
+    #[case] expected_rows: Vec<Vec<Value>>,
+    #[values(false, true)] is_indexed: bool,
+  ) {
+    let mut database = create_test_database(&[
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);",
+      "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER, amount INTEGER);",
+      "INSERT INTO users (name) VALUES ('alice');",
+      "INSERT INTO users (name) VALUES ('bob');",
+      "INSERT INTO users (name) VALUES ('carol');",
+      "INSERT INTO orders (user_id, amount) VALUES (1, 10);",
+      "INSERT INTO orders (user_id, amount) VALUES (1, 20);",
+      "INSERT INTO orders (user_id, amount) VALUES (2, 5);",
+      "INSERT INTO orders (user_id, amount) VALUES (4, 7);",
+    ]);
+    // orders.user_id 上有非唯一索引的时候，JOIN 会直接在索引里面找匹配的行
+    if is_indexed {
+      handle_sql_query("CREATE INDEX idx_user_id ON orders (user_id);", &mut database).unwrap();
+    }
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+
+    assert_eq!(result_set.rows, expected_rows);
+  }
+
+  #[rstest]
+  #[case("SELECT id FROM users JOIN orders ON users.id = orders.user_id;")]
+  #[case("SELECT users.name FROM users AS u;")]
+  #[case("SELECT * FROM users JOIN users ON users.id = users.id;")]
+  #[case("SELECT x.* FROM users;")]
+  fn test_execute_select_query_join_error(#[case] query: &str) {
+    let database = create_test_database(&[
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);",
+      "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER, amount INTEGER);",
+      "INSERT INTO users (name) VALUES ('alice');",
+      "INSERT INTO users (name) VALUES ('bob');",
+      "INSERT INTO users (name) VALUES ('carol');",
+      "INSERT INTO orders (user_id, amount) VALUES (1, 10);",
+      "INSERT INTO orders (user_id, amount) VALUES (1, 20);",
+      "INSERT INTO orders (user_id, amount) VALUES (2, 5);",
+      "INSERT INTO orders (user_id, amount) VALUES (4, 7);",
+    ]);
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert!(execute_select_query(&select_query, &database).is_err());
+  }
+
+  #[rstest]
+  #[case("SELECT id FROM test WHERE score IS NULL;", vec![vec![Value::Integer(2)]])]
+  #[case("SELECT id FROM test WHERE score > 0 OR score IS NULL ORDER BY score DESC;", vec![
+    vec![Value::Integer(3)],
+    vec![Value::Integer(1)],
+    vec![Value::Integer(2)],
+  ])]
+  #[case("SELECT COUNT(*), COUNT(score), SUM(score) FROM test;", vec![
+    vec![Value::Integer(3), Value::Integer(2), Value::Integer(3)],
+  ])]
+  #[case("SELECT name FROM test WHERE id = 2;", vec![vec![Value::Text("Null".to_string())]])]
+  fn test_execute_select_query_null(
+    #[case] query: &str,
+    #[case] expected_rows: Vec<Vec<Value>>,
+  ) {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER);",
+      "INSERT INTO test (name, score) VALUES ('a', 1);",
+      "INSERT INTO test (name, score) VALUES ('Null', NULL);",
+      "INSERT INTO test (name, score) VALUES (NULL, 2);",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+
+    assert_eq!(result_set.rows, expected_rows);
+  }
+
+  #[test]
+  fn test_execute_select_query_unique_real_and_bool() {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, rate REAL UNIQUE, active BOOLEAN UNIQUE);",
+      "INSERT INTO test (rate, active) VALUES (2.5, true);",
+      "INSERT INTO test (rate, active) VALUES (NULL, NULL);",
+      "INSERT INTO test (rate, active) VALUES (-1, false);",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+    for query in [
+      "INSERT INTO test (rate) VALUES (2.5);",
+      "INSERT INTO test (active) VALUES (false);",
+      "UPDATE test SET rate = -1.0 WHERE id = 1;",
+    ] {
+      assert!(matches!(
+        handle_sql_query(query, &mut database),
+        Err(NollaDBError::ConstraintViolation { .. })
+      ));
+    }
+
+    let table = database.get_table("test".to_string()).unwrap();
+    let table_column = table.get_column("rate".to_string()).unwrap();
+    assert!(table_column.is_indexed && table_column.is_unique_constraint);
+    assert_eq!(table_column.index.get_ordered_row_ids(), vec![3, 1]);
+
+    // rate 上有 NULL，所以不能按照索引的顺序扫描
+    let select_query = SelectQuery::new(&get_sql_ast("SELECT id FROM test ORDER BY rate;").unwrap()).unwrap();
+    assert_eq!(
+      execute_select_query(&select_query, &database).unwrap().rows,
+      vec![vec![Value::Integer(2)], vec![Value::Integer(3)], vec![Value::Integer(1)]]
+    );
+  }
+
+  #[rstest]
+  #[case("SELECT email FROM test;")]
+  #[case("SELECT * FROM test ORDER BY 3;")]
+  #[case("SELECT * FROM test LIMIT 1.5;")]
+  #[case("SELECT other.id FROM test;")]
+  #[case("SELECT * FROM other;")]
+  #[case("SELECT *;")]
+  #[case("SELECT id;")]
+  fn test_execute_select_query_error(#[case] query: &str) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "INSERT INTO test (name) VALUES ('xxx');",
+      "INSERT INTO test (name) VALUES ('yyy');",
+    ]);
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert!(execute_select_query(&select_query, &database).is_err());
+  }
+
+  #[rstest]
+  #[case(
+    "UPDATE test SET name = 'zzz' WHERE id = 2;",
+    1,
+    vec![
+      vec![Value::Integer(1), Value::Text("xxx".to_string())],
+      vec![Value::Integer(2), Value::Text("zzz".to_string())],
+    ],
+  )]
+  #[case(
+    "UPDATE test SET name = name WHERE id > 5;",
+    0,
+    vec![
+      vec![Value::Integer(1), Value::Text("xxx".to_string())],
+      vec![Value::Integer(2), Value::Text("yyy".to_string())],
+    ],
+  )]
+  #[case(
+    "UPDATE test SET id = id + 10 WHERE name = 'yyy';",
+    1,
+    vec![
+      vec![Value::Integer(1), Value::Text("xxx".to_string())],
+      vec![Value::Integer(12), Value::Text("yyy".to_string())],
+    ],
+  )]
+  #[case(
+    "UPDATE test SET name = NULL WHERE id = 1;",
+    1,
+    vec![
+      vec![Value::Integer(1), Value::Null],
+      vec![Value::Integer(2), Value::Text("yyy".to_string())],
+    ],
+  )]
+  fn test_execute_update_query(
+    #[case] query: &str,
+    #[case] expected_affected_rows: usize,
+    #[case] expected_rows: Vec<Vec<Value>>,
+  ) {
+    let mut database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "INSERT INTO test (name) VALUES ('xxx');",
+      "INSERT INTO test (name) VALUES ('yyy');",
+    ]);
+    let update_query = UpdateQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert_eq!(execute_update_query(&update_query, &mut database), Ok(expected_affected_rows));
+
+    let select_query = SelectQuery::new(&get_sql_ast("SELECT * FROM test;").unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+    assert_eq!(result_set.rows, expected_rows);
+  }
+
+  #[rstest]
+  #[case("UPDATE test SET id = 2 WHERE id = 1;")]
+  #[case("UPDATE test SET id = 3;")]
+  #[case("UPDATE test SET id = 'abc';")]
+  #[case("UPDATE test SET email = 'xxx';")]
+  #[case("UPDATE test SET id = NULL WHERE id = 1;")]
+  fn test_execute_update_query_error(#[case] query: &str) {
+    let mut database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "INSERT INTO test (name) VALUES ('xxx');",
+      "INSERT INTO test (name) VALUES ('yyy');",
+    ]);
+    let update_query = UpdateQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert!(execute_update_query(&update_query, &mut database).is_err());
+
+    // 出错时表中的数据不能有任何改动
+    let select_query = SelectQuery::new(&get_sql_ast("SELECT * FROM test;").unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+    assert_eq!(result_set.rows, vec![
+      vec![Value::Integer(1), Value::Text("xxx".to_string())],
+      vec![Value::Integer(2), Value::Text("yyy".to_string())],
+    ]);
+  }
+
+  #[rstest]
+  #[case("SELECT id FROM test WHERE name BETWEEN 'b' AND 'c';", vec![3, 4])]
+  #[case("SELECT id FROM test WHERE id >= 2 AND id < 4 AND score = 3;", vec![3])]
+  #[case("SELECT id FROM test WHERE 'c' < name ORDER BY name;", vec![2])]
+  #[case("SELECT id FROM test WHERE name = 'a' OR id = 4;", vec![1, 4])]
+  #[case("SELECT id FROM test WHERE name > 'b' AND name < 'b';", vec![])]
+  #[case("SELECT t.id FROM test t JOIN test u ON t.score = u.score WHERE t.id = 1 ORDER BY u.id;", vec![1, 1])]
+  fn test_execute_select_query_with_index_scan(
+    #[case] query: &str,
+    #[case] expected_row_ids: Vec<i64>,
+    #[values(false, true)] is_indexed: bool,
+  ) {
+    let mut database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER);",
+      "INSERT INTO test (name, score) VALUES ('a', 2);",
+      "INSERT INTO test (name, score) VALUES ('d', 1);",
+      "INSERT INTO test (name, score) VALUES ('c', 3);",
+      "INSERT INTO test (name, score) VALUES ('b', 2);",
+    ]);
+    // 有没有索引查询的结果都应该是一样的
+    if is_indexed {
+      handle_sql_query("CREATE INDEX idx_name ON test (name);", &mut database).unwrap();
+    }
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+
+    assert_eq!(
+      result_set.rows,
+      expected_row_ids.into_iter().map(|id| vec![Value::Integer(id)]).collect::<Vec<Vec<Value>>>()
+    );
+  }
+
+  #[rstest]
+  #[case("SELECT id FROM events WHERE day >= '2024-01-01' ORDER BY day;", vec![3, 1])]
+  #[case("SELECT id FROM events WHERE day = DATE '2023-12-31';", vec![2])]
+  #[case("SELECT id FROM events WHERE at < '12:00' ORDER BY at;", vec![1])]
+  #[case("SELECT id FROM events WHERE created BETWEEN '2024-02-29' AND '2024-03-01 08:30' ORDER BY id;", vec![1, 3])]
+  #[case("SELECT id FROM events WHERE created > DATE '2024-02-29';", vec![1])]
+  #[case("SELECT id FROM events WHERE price = 0.1;", vec![2])]
+  #[case("SELECT id FROM events WHERE price <= '5.00' ORDER BY price;", vec![2, 4, 3])]
+  #[case("SELECT id FROM events WHERE data = X'0A0B';", vec![3])]
+  #[case("SELECT id FROM events WHERE data > X'0A' ORDER BY data DESC;", vec![2, 3])]
+  fn test_execute_select_query_with_new_column_types(
+    #[case] query: &str,
+    #[case] expected_row_ids: Vec<i64>,
+    #[values(false, true)] is_indexed: bool,
+  ) {
+    let mut database = create_test_database(&[
+      "CREATE TABLE events (id INTEGER PRIMARY KEY, day DATE, at TIME, created TIMESTAMP, price DECIMAL(10, 2), data BLOB);",
+      "INSERT INTO events (day, at, created, price, data) VALUES ('2024-03-01', '08:30', '2024-03-01 08:30:00', 19.99, X'0A');",
+      "INSERT INTO events (day, at, created, price, data) VALUES ('2023-12-31', '23:59:59.5', '2023-12-31T23:59:59.5', '0.10', X'FF');",
+      "INSERT INTO events (day, at, created, price, data) VALUES ('2024-02-29', '12:00:00', '2024-02-29', 5, X'0A0B');",
+      "INSERT INTO events (price) VALUES (0.2);",
+    ]);
+    if is_indexed {
+      for column_name in ["day", "at", "created", "price", "data"] {
+        handle_sql_query(
+          &format!("CREATE INDEX idx_{} ON events ({});", column_name, column_name),
+          &mut database,
+        ).unwrap();
+      }
+    }
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+
+    assert_eq!(
+      result_set.rows,
+      expected_row_ids.into_iter().map(|id| vec![Value::Integer(id)]).collect::<Vec<Vec<Value>>>()
+    );
+  }
+
+  #[rstest]
+  #[case("SELECT day, at, created, data FROM events WHERE id = 2;", vec!["2023-12-31", "23:59:59.5", "2023-12-31 23:59:59.5", "X'FF'"])]
+  #[case("SELECT SUM(price), AVG(price), MAX(day) FROM events;", vec!["25.29", "6.3225", "2024-03-01"])]
+  #[case("SELECT price * 3, price + 0.2, price / 3 FROM events WHERE id = 2;", vec!["0.3", "0.3", "0.0333333"])]
+  fn test_execute_select_query_new_column_type_values(
+    #[case] query: &str,
+    #[case] expected_values: Vec<&str>,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE events (id INTEGER PRIMARY KEY, day DATE, at TIME, created TIMESTAMP, price DECIMAL(10, 2), data BLOB);",
+      "INSERT INTO events (day, at, created, price, data) VALUES ('2024-03-01', '08:30', '2024-03-01 08:30:00', 19.99, X'0A');",
+      "INSERT INTO events (day, at, created, price, data) VALUES ('2023-12-31', '23:59:59.5', '2023-12-31T23:59:59.5', '0.10', X'FF');",
+      "INSERT INTO events (day, at, created, price, data) VALUES ('2024-02-29', '12:00:00', '2024-02-29', 5, X'0A0B');",
+      "INSERT INTO events (price) VALUES (0.2);",
+    ]);
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+
+    assert_eq!(
+      result_set.rows[0].iter().map(|value| value.to_string()).collect::<Vec<String>>(),
+      expected_values,
+    );
+  }
+
+  #[rstest]
+  #[case("SELECT key, value, type, atom, fullkey, path FROM json_each('[1, 2.5, \"x\", [true]]');", vec![
+    vec!["0", "1", "integer", "1", "$[0]", "$"],
+    vec!["1", "2.5", "real", "2.5", "$[1]", "$"],
+    vec!["2", "\"x\"", "text", "\"x\"", "$[2]", "$"],
+    vec!["3", "[true]", "array", "NULL", "$[3]", "$"],
+  ])]
+  #[case("SELECT key, value FROM json_each('{\"b\": 2, \"a\": 1}') WHERE value > 1;", vec![vec!["b", "2"]])]
+  #[case("SELECT key, value FROM json_each('{\"b\": 2, \"a\": 1}');", vec![vec!["b", "2"], vec!["a", "1"]])]
+  #[case("SELECT key, value FROM json_each('{\"a\": {\"b c\": 1}}', '$.a');", vec![vec!["b c", "1"]])]
+  #[case("SELECT key, value, fullkey FROM json_each('42');", vec![vec!["NULL", "42", "$"]])]
+  #[case("SELECT * FROM json_each(NULL);", vec![])]
+  #[case("SELECT * FROM json_each('[1]', '$.missing');", vec![])]
+  #[case(
+    "SELECT logs.id, tag.value FROM logs, json_each(logs.payload, '$.tags') AS tag ORDER BY logs.id, tag.key;",
+    vec![vec!["1", "a"], vec!["1", "b"], vec!["3", "c"]]
+  )]
+  #[case(
+    "SELECT logs.id, COUNT(j.key) FROM logs LEFT JOIN json_each(logs.payload) AS j GROUP BY logs.id ORDER BY logs.id;",
+    vec![vec!["1", "2"], vec!["2", "1"], vec!["3", "2"], vec!["4", "0"]]
+  )]
+  #[case(
+    "SELECT id, payload ->> '$.level' FROM logs WHERE payload ->> 'level' = 'error' ORDER BY id;",
+    vec![vec!["1", "error"], vec!["3", "error"]]
+  )]
+  #[case("SELECT id, payload -> 'tags' -> 0 FROM logs WHERE payload -> 'tags' IS NOT NULL ORDER BY id;", vec![
+    vec!["1", "\"a\""],
+    vec!["3", "\"c\""],
+  ])]
+  #[case("SELECT id FROM logs WHERE json_array_length(payload, '$.tags') = 2;", vec![vec!["1"]])]
+  fn test_execute_select_query_json(
+    #[case] query: &str,
+    #[case] expected_rows: Vec<Vec<&str>>,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE logs (id INTEGER PRIMARY KEY, payload JSON);",
+      "INSERT INTO logs (payload) VALUES ('{\"level\": \"error\", \"tags\": [\"a\", \"b\"]}');",
+      "INSERT INTO logs (payload) VALUES ('{\"level\": \"info\"}');",
+      "INSERT INTO logs (payload) VALUES ('{\"tags\": [\"c\"], \"level\": \"error\"}');",
+      "INSERT INTO logs (payload) VALUES (NULL);",
+    ]);
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+
+    assert_eq!(
+      result_set.rows
+        .iter()
+        .map(|row| row.iter().map(|value| value.to_string()).collect::<Vec<String>>())
+        .collect::<Vec<Vec<String>>>(),
+      expected_rows,
+    );
+  }
+
+  #[rstest]
+  #[case(
+    "SELECT upper(name) || ':' || score, typeof(score) FROM test WHERE length(name) = 1 AND score >= 2 ORDER BY id;",
+    vec![vec!["A:2", "integer"], vec!["C:3", "integer"], vec!["B:2", "integer"]]
+  )]
+  #[case(
+    "SELECT name FROM test WHERE coalesce(nullif(score, 2), 0) = 0 ORDER BY abs(id - 4);",
+    vec![vec!["b"], vec!["a"]]
+  )]
+  #[case("SELECT max(score, id), min(score, id) FROM test WHERE instr('cd', name) > 0 ORDER BY 1;", vec![
+    vec!["2", "1"],
+    vec!["3", "3"],
+  ])]
+  #[case("SELECT replace(name, 'a', 'x'), round(AVG(score), 1) FROM test GROUP BY name HAVING name = 'a';", vec![
+    vec!["x", "2"],
+  ])]
+  fn test_execute_select_query_scalar_function(
+    #[case] query: &str,
+    #[case] expected_rows: Vec<Vec<&str>>,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER);",
+      "INSERT INTO test (name, score) VALUES ('a', 2);",
+      "INSERT INTO test (name, score) VALUES ('d', 1);",
+      "INSERT INTO test (name, score) VALUES ('c', 3);",
+      "INSERT INTO test (name, score) VALUES ('b', 2);",
+    ]);
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+
+    assert_eq!(
+      result_set.rows
+        .iter()
+        .map(|row| row.iter().map(|value| value.to_string()).collect::<Vec<String>>())
+        .collect::<Vec<Vec<String>>>(),
+      expected_rows,
+    );
+  }
+
+  #[test]
+  fn test_execute_select_query_json_column_names() {
+    let database = create_test_database(&[
+      "CREATE TABLE logs (id INTEGER PRIMARY KEY, payload JSON);",
+      "INSERT INTO logs (payload) VALUES ('{\"level\": \"error\", \"tags\": [\"a\", \"b\"]}');",
+      "INSERT INTO logs (payload) VALUES ('{\"level\": \"info\"}');",
+      "INSERT INTO logs (payload) VALUES ('{\"tags\": [\"c\"], \"level\": \"error\"}');",
+      "INSERT INTO logs (payload) VALUES (NULL);",
+    ]);
+    let select_query = SelectQuery::new(
+      &get_sql_ast("SELECT payload ->> '$.level', payload -> 'tags' FROM logs;").unwrap()
+    ).unwrap();
+    let result_set = execute_select_query(&select_query, &database).unwrap();
+
+    assert_eq!(result_set.column_names, vec!["payload ->> '$.level'", "payload -> 'tags'"]);
+  }
+
+  #[rstest]
+  #[case("SELECT * FROM json_each('[1');")]
+  #[case("SELECT * FROM json_each('[1]', '$', 1);")]
+  #[case("SELECT * FROM json_each(logs.payload);")]
+  fn test_execute_select_query_json_error(#[case] query: &str) {
+    let database = create_test_database(&[
+      "CREATE TABLE logs (id INTEGER PRIMARY KEY, payload JSON);",
+      "INSERT INTO logs (payload) VALUES ('{\"level\": \"error\", \"tags\": [\"a\", \"b\"]}');",
+      "INSERT INTO logs (payload) VALUES ('{\"level\": \"info\"}');",
+      "INSERT INTO logs (payload) VALUES ('{\"tags\": [\"c\"], \"level\": \"error\"}');",
+      "INSERT INTO logs (payload) VALUES (NULL);",
+    ]);
+    let select_query = SelectQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert!(execute_select_query(&select_query, &database).is_err());
+  }
+
+  #[test]
+  fn test_execute_update_and_delete_query_with_index_scan() {
+    let mut database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER);",
+      "INSERT INTO test (name, score) VALUES ('a', 2);",
+      "INSERT INTO test (name, score) VALUES ('d', 1);",
+      "INSERT INTO test (name, score) VALUES ('c', 3);",
+      "INSERT INTO test (name, score) VALUES ('b', 2);",
+    ]);
+    handle_sql_query("CREATE INDEX idx_name ON test (name);", &mut database).unwrap();
+
+    let update_query = UpdateQuery::new(
+      &get_sql_ast("UPDATE test SET name = 'e' WHERE name BETWEEN 'b' AND 'c';").unwrap()
+    ).unwrap();
+    assert_eq!(execute_update_query(&update_query, &mut database), Ok(2));
+
+    let delete_query = DeleteQuery::new(&get_sql_ast("DELETE FROM test WHERE name = 'e';").unwrap()).unwrap();
+    assert_eq!(execute_delete_query(&delete_query, &mut database), Ok(2));
+    assert_eq!(database.get_table("test".to_string()).unwrap().get_row_ids(), vec![1, 2]);
+  }
+
+  #[test]
+  fn test_execute_update_query_keeps_index() {
+    let mut database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "INSERT INTO test (name) VALUES ('xxx');",
+      "INSERT INTO test (name) VALUES ('yyy');",
+    ]);
+    let update_query = UpdateQuery::new(
+      &get_sql_ast("UPDATE test SET id = 5 WHERE id = 1;").unwrap()
+    ).unwrap();
+    execute_update_query(&update_query, &mut database).unwrap();
+
+    let table = database.get_table("test".to_string()).unwrap();
+    let index = &table.get_column("id".to_string()).unwrap().index;
+    assert_eq!(index.get_row_id(&Value::Integer(1)), None);
+    assert_eq!(index.get_row_id(&Value::Integer(5)), Some(5));
+  }
+
+  #[rstest]
+  #[case("DELETE FROM test WHERE id = 1;", 1, vec![2])]
+  #[case("DELETE FROM test WHERE name = 'zzz';", 0, vec![1, 2])]
+  #[case("DELETE FROM test;", 2, vec![])]
+  fn test_execute_delete_query(
+    #[case] query: &str,
+    #[case] expected_affected_rows: usize,
+    #[case] expected_row_ids: Vec<i64>,
+  ) {
+    let mut database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "INSERT INTO test (name) VALUES ('xxx');",
+      "INSERT INTO test (name) VALUES ('yyy');",
+    ]);
+    let delete_query = DeleteQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert_eq!(execute_delete_query(&delete_query, &mut database), Ok(expected_affected_rows));
+
+    let table = database.get_table("test".to_string()).unwrap();
+    assert_eq!(table.get_row_ids(), expected_row_ids);
+    assert_eq!(table.get_value("name", &1).unwrap().is_null(), !expected_row_ids.contains(&1));
+
+    // 索引中对应的 row id 也要被删掉
+    let index = &table.get_column("id".to_string()).unwrap().index;
+    for row_id in [1, 2] {
+      assert_eq!(
+        index.get_row_id(&Value::Integer(row_id)).is_some(),
+        expected_row_ids.contains(&row_id),
+      );
+    }
+  }
+}
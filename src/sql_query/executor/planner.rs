@@ -0,0 +1,440 @@
+use std::cmp::Ordering;
+use std::ops::Bound;
+
+use sqlparser::ast::{
+  Expr,
+  BinaryOperator,
+};
+
+use crate::error::Result;
+use crate::table::Table;
+use crate::table::row::value::Value;
+use crate::sql_query::expression::{
+  RowContext,
+  TableRow,
+  evaluate_expression,
+};
+
+// 一张表的扫描方式
+// 索引只是用来缩小范围，拿到的行最后还是要用完整的 WHERE 条件判断一下
+#[derive(Debug, PartialEq)]
+pub enum ScanPlan {
+  // 扫描表中所有的行
+  FullTable,
+  // column = value
+  IndexPoint { column_name: String, value: Value },
+  // 由多列组成的 PRIMARY KEY 或者 UNIQUE 约束上的每一列都是 column = value，values 按照 column_names 的顺序排列
+  TableKeyPoint { column_names: Vec<String>, values: Vec<Value> },
+  // column 在 low 和 high 之间
+  IndexRange { column_name: String, low: Bound<Value>, high: Bound<Value> },
+}
+
+impl ScanPlan {
+  // 按照扫描方式拿到可能满足 WHERE 条件的 row id，按照 row id 的顺序排列
+  pub fn get_row_ids(&self, table: &Table) -> Result<Vec<i64>> {
+    match self {
+      ScanPlan::FullTable => Ok(table.get_row_ids()),
+      ScanPlan::IndexPoint { column_name, value } => Ok(
+        table.get_column(column_name.to_string())?.index.get_row_ids(value)
+      ),
+      ScanPlan::TableKeyPoint { column_names, values } => Ok(
+        table
+          .table_keys
+          .iter()
+          .find(|table_key| table_key.column_names == *column_names)
+          .and_then(|table_key| table_key.get_row_id(values))
+          .map_or(vec![], |row_id| vec![row_id])
+      ),
+      ScanPlan::IndexRange { column_name, low, high } => {
+        let mut row_ids = table
+          .get_column(column_name.to_string())?
+          .index
+          .get_range_row_ids(low.as_ref(), high.as_ref());
+        row_ids.sort_unstable();
+        Ok(row_ids)
+      },
+    }
+  }
+
+  // EXPLAIN 时输出的描述，和 SQLite 一样用 ? 代替具体的值
+  pub fn get_description(&self, table_row: &TableRow) -> String {
+    let table_description = get_table_description(table_row);
+    match self {
+      ScanPlan::FullTable => format!("SCAN {}", table_description),
+      ScanPlan::IndexPoint { column_name, .. } => format!(
+        "SEARCH {} USING {} ({}=?)",
+        table_description,
+        get_index_description(table_row.table, column_name),
+        column_name
+      ),
+      ScanPlan::TableKeyPoint { column_names, .. } => format!(
+        "SEARCH {} USING {} ({})",
+        table_description,
+        get_table_key_description(table_row.table, column_names),
+        column_names
+          .iter()
+          .map(|column_name| format!("{}=?", column_name))
+          .collect::<Vec<String>>()
+          .join(" AND ")
+      ),
+      ScanPlan::IndexRange { column_name, low, high } => {
+        let mut conditions: Vec<String> = vec![];
+        match low {
+          Bound::Included(_) => conditions.push(format!("{}>=?", column_name)),
+          Bound::Excluded(_) => conditions.push(format!("{}>?", column_name)),
+          Bound::Unbounded => (),
+        }
+        match high {
+          Bound::Included(_) => conditions.push(format!("{}<=?", column_name)),
+          Bound::Excluded(_) => conditions.push(format!("{}<?", column_name)),
+          Bound::Unbounded => (),
+        }
+        format!(
+          "SEARCH {} USING {} ({})",
+          table_description,
+          get_index_description(table_row.table, column_name),
+          conditions.join(" AND ")
+        )
+      },
+    }
+  }
+}
+
+// 有别名的话带上别名，比如 orders AS o
+pub fn get_table_description(table_row: &TableRow) -> String {
+  if table_row.table_name == table_row.table.table_name {
+    table_row.table_name.to_string()
+  } else {
+    format!("{} AS {}", table_row.table.table_name, table_row.table_name)
+  }
+}
+
+// 一列上的索引的描述，CREATE INDEX 建的索引带上索引名
+// PRIMARY KEY 和 UNIQUE 约束自动建的索引没有名字
+pub fn get_index_description(table: &Table, column_name: &str) -> String {
+  let index_name = table
+    .indexes
+    .iter()
+    .find(|(_, indexed_column_name)| *indexed_column_name == column_name)
+    .map(|(index_name, _)| index_name);
+  match (index_name, table.get_column(column_name.to_string())) {
+    (Some(index_name), _) => format!("INDEX {}", index_name),
+    (None, Ok(table_column)) if table_column.is_primary_key => "PRIMARY KEY".to_string(),
+    (None, _) => "UNIQUE INDEX".to_string(),
+  }
+}
+
+// 由多列组成的 PRIMARY KEY 或者 UNIQUE 约束自动建的索引的描述
+fn get_table_key_description(table: &Table, column_names: &[String]) -> String {
+  match table.table_keys.iter().find(|table_key| table_key.column_names == column_names) {
+    Some(table_key) if table_key.is_primary => "PRIMARY KEY".to_string(),
+    _ => "UNIQUE INDEX".to_string(),
+  }
+}
+
+// 简单的基于规则的优化：
+// 1. WHERE 中用 AND 连接的条件里面，如果有 索引列 = 常量，那么用索引点查
+//    没有 PRIMARY KEY 或者 UNIQUE 的列，而多列的 PRIMARY KEY 或者 UNIQUE 约束上的每一列都是 列 = 常量的话，用这个约束的索引点查
+// 2. 否则如果有 索引列 >、>=、<、<= 常量或者 索引列 BETWEEN 常量 AND 常量，那么用索引范围查询
+//    同一列上的多个范围条件会合并成一个范围
+// 3. 都没有的话扫描整张表
+// table_rows[0] 是要扫描的表，其他的是 JOIN 进来的表，用来判断没有表名前缀的列是不是有歧义
+pub fn get_scan_plan(table_rows: &[TableRow], selection: &Option<Expr>) -> ScanPlan {
+  let expr = match selection {
+    Some(expr) => expr,
+    None => return ScanPlan::FullTable,
+  };
+  let mut exprs: Vec<&Expr> = vec![];
+  split_conjunction(expr, &mut exprs);
+
+  // 所有 列 = 常量 的条件，这一列上不一定有索引
+  let mut equalities: Vec<(String, Value)> = vec![];
+  // (列名, 下界, 上界)
+  let mut range_scans: Vec<(String, Bound<Value>, Bound<Value>)> = vec![];
+  for expr in exprs {
+    match expr {
+      Expr::BinaryOp { left, op, right } => {
+        let (column_name, op, value) = match (
+          get_column_name(left, table_rows),
+          get_column_name(right, table_rows),
+        ) {
+          (Some(column_name), None) => match get_index_key(right, &column_name, table_rows) {
+            Some(value) => (column_name, op.clone(), value),
+            None => continue,
+          },
+          // 常量在左边的话，把比较运算符反过来
+          (None, Some(column_name)) => match get_index_key(left, &column_name, table_rows) {
+            Some(value) => (column_name, match op {
+              BinaryOperator::Lt => BinaryOperator::Gt,
+              BinaryOperator::LtEq => BinaryOperator::GtEq,
+              BinaryOperator::Gt => BinaryOperator::Lt,
+              BinaryOperator::GtEq => BinaryOperator::LtEq,
+              op => op.clone(),
+            }, value),
+            None => continue,
+          },
+          _ => continue,
+        };
+        if op == BinaryOperator::Eq {
+          if !equalities.iter().any(|(equality_column_name, _)| *equality_column_name == column_name) {
+            equalities.push((column_name.to_string(), value.clone()));
+          }
+          continue;
+        }
+        if !is_indexed_column(&column_name, table_rows) {
+          continue;
+        }
+        let (low, high) = match op {
+          BinaryOperator::Gt => (Bound::Excluded(value), Bound::Unbounded),
+          BinaryOperator::GtEq => (Bound::Included(value), Bound::Unbounded),
+          BinaryOperator::Lt => (Bound::Unbounded, Bound::Excluded(value)),
+          BinaryOperator::LtEq => (Bound::Unbounded, Bound::Included(value)),
+          _ => continue,
+        };
+        push_range_scan(&mut range_scans, column_name, low, high);
+      },
+      Expr::Between { expr, negated: false, low, high } => {
+        let column_name = match get_index_column_name(expr, table_rows) {
+          Some(column_name) => column_name,
+          None => continue,
+        };
+        if let (Some(low), Some(high)) = (
+          get_index_key(low, &column_name, table_rows),
+          get_index_key(high, &column_name, table_rows),
+        ) {
+          push_range_scan(&mut range_scans, column_name, Bound::Included(low), Bound::Included(high));
+        }
+      },
+      _ => (),
+    }
+  }
+
+  // 优先用 PRIMARY KEY 或者 UNIQUE 的列，然后是多列的约束，最后是普通索引
+  let indexed_equalities = equalities
+    .iter()
+    .filter(|(column_name, _)| is_indexed_column(column_name, table_rows))
+    .collect::<Vec<&(String, Value)>>();
+  let unique_equality = indexed_equalities.iter().find(|(column_name, _)| {
+    table_rows[0]
+      .table
+      .get_column(column_name.to_string())
+      .is_ok_and(|table_column| table_column.is_primary_key || table_column.is_unique_constraint)
+  });
+  if let Some((column_name, value)) = unique_equality {
+    return ScanPlan::IndexPoint { column_name: column_name.to_string(), value: value.clone() };
+  }
+  if let Some(table_key_scan) = get_table_key_scan(table_rows, &equalities) {
+    return table_key_scan;
+  }
+  if let Some((column_name, value)) = indexed_equalities.first() {
+    return ScanPlan::IndexPoint { column_name: column_name.to_string(), value: value.clone() };
+  }
+  match range_scans.into_iter().next() {
+    Some((column_name, low, high)) => ScanPlan::IndexRange { column_name, low, high },
+    None => ScanPlan::FullTable,
+  }
+}
+
+// 把用 AND 连接起来的条件拆开
+fn split_conjunction<'a>(expr: &'a Expr, exprs: &mut Vec<&'a Expr>) {
+  match expr {
+    Expr::Nested(expr) => split_conjunction(expr, exprs),
+    Expr::BinaryOp { left, op: BinaryOperator::And, right } => {
+      split_conjunction(left, exprs);
+      split_conjunction(right, exprs);
+    },
+    expr => exprs.push(expr),
+  }
+}
+
+// 第一个每一列都有 列 = 常量 条件的多列 PRIMARY KEY 或者 UNIQUE 约束
+fn get_table_key_scan(table_rows: &[TableRow], equalities: &[(String, Value)]) -> Option<ScanPlan> {
+  table_rows.first()?.table.table_keys.iter().find_map(|table_key| {
+    let values = table_key
+      .column_names
+      .iter()
+      .map(|column_name| equalities
+        .iter()
+        .find(|(equality_column_name, _)| equality_column_name == column_name)
+        .map(|(_, value)| value.clone())
+      )
+      .collect::<Option<Vec<Value>>>()?;
+    Some(ScanPlan::TableKeyPoint { column_names: table_key.column_names.clone(), values })
+  })
+}
+
+fn is_indexed_column(column_name: &str, table_rows: &[TableRow]) -> bool {
+  table_rows
+    .first()
+    .and_then(|table_row| table_row.table.get_column(column_name.to_string()).ok())
+    .is_some_and(|table_column| table_column.is_indexed)
+}
+
+// 如果表达式引用的是要扫描的表上的一列，返回列名
+fn get_column_name(expr: &Expr, table_rows: &[TableRow]) -> Option<String> {
+  let table_row = table_rows.first()?;
+  let column_name = match expr {
+    Expr::Nested(expr) => return get_column_name(expr, table_rows),
+    // 没有表名前缀的话，其他的表上不能有同名的列
+    Expr::Identifier(ident) if !table_rows[1..]
+      .iter()
+      .any(|other_table_row| other_table_row.table.has_column(ident.to_string())) => ident.to_string(),
+    Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+      [prefix, ident] if prefix.to_string() == table_row.table_name => ident.to_string(),
+      _ => return None,
+    },
+    _ => return None,
+  };
+
+  match table_row.table.has_column(column_name.to_string()) {
+    true => Some(column_name),
+    false => None,
+  }
+}
+
+// 如果表达式引用的是要扫描的表上一个有索引的列，返回列名
+fn get_index_column_name(expr: &Expr, table_rows: &[TableRow]) -> Option<String> {
+  get_column_name(expr, table_rows).filter(|column_name| is_indexed_column(column_name, table_rows))
+}
+
+// 对不引用任何列的常量表达式求值，并转换成索引 key 的类型
+// 只有转换前后比较的语义一致才能用索引，比如 Integer 写入 Real 列，或者 Text 写入 Date 列
+fn get_index_key(expr: &Expr, column_name: &str, table_rows: &[TableRow]) -> Option<Value> {
+  let value = evaluate_expression(expr, &RowContext::default()).ok()?;
+  let table = table_rows.first()?.table;
+  let column_value = table.cast_value_to_column_type(column_name, &value).ok()?;
+  match (&column_value, &value) {
+    (Value::Integer(_), Value::Integer(_))
+    | (Value::Text(_), Value::Text(_))
+    | (Value::Bool(_), Value::Bool(_))
+    | (Value::Real(_), Value::Real(_) | Value::Integer(_))
+    | (Value::Blob(_), Value::Blob(_))
+    | (Value::Date(_), Value::Date(_) | Value::Text(_))
+    | (Value::Time(_), Value::Time(_) | Value::Text(_))
+    | (Value::Timestamp(_), Value::Timestamp(_) | Value::Date(_) | Value::Text(_))
+    | (Value::Decimal(_), Value::Decimal(_) | Value::Integer(_) | Value::Real(_) | Value::Text(_)) => Some(column_value),
+    _ => None,
+  }
+}
+
+// 同一列上的范围条件取交集，下界取大的，上界取小的
+fn push_range_scan(
+  range_scans: &mut Vec<(String, Bound<Value>, Bound<Value>)>,
+  column_name: String,
+  low: Bound<Value>,
+  high: Bound<Value>,
+) {
+  match range_scans.iter_mut().find(|(range_column_name, _, _)| *range_column_name == column_name) {
+    Some((_, range_low, range_high)) => {
+      *range_low = get_tighter_bound(range_low, low, Ordering::Greater);
+      *range_high = get_tighter_bound(range_high, high, Ordering::Less);
+    },
+    None => range_scans.push((column_name, low, high)),
+  }
+}
+
+// ordering 为 Greater 的时候取大的边界，为 Less 的时候取小的边界
+// 值相同的时候开区间更紧
+fn get_tighter_bound(a: &Bound<Value>, b: Bound<Value>, ordering: Ordering) -> Bound<Value> {
+  let (a_value, b_value) = match (a, &b) {
+    (Bound::Unbounded, _) => return b,
+    (_, Bound::Unbounded) => return a.clone(),
+    (Bound::Included(a_value) | Bound::Excluded(a_value), Bound::Included(b_value) | Bound::Excluded(b_value)) =>
+      (a_value, b_value),
+  };
+  match b_value.compare_for_order_by(a_value) {
+    Ordering::Equal => match b {
+      Bound::Excluded(_) => b,
+      _ => a.clone(),
+    },
+    b_ordering if b_ordering == ordering => b,
+    _ => a.clone(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::test_helpers::{parse_expression, create_test_database};
+
+  #[rstest]
+  #[case("id = 2", ScanPlan::IndexPoint { column_name: "id".to_string(), value: Value::Integer(2) })]
+  #[case("score > 1 AND 1 + 1 = test.id", ScanPlan::IndexPoint { column_name: "id".to_string(), value: Value::Integer(2) })]
+  #[case(
+    "name BETWEEN 'a' AND 'c'",
+    ScanPlan::IndexRange {
+      column_name: "name".to_string(),
+      low: Bound::Included(Value::Text("a".to_string())),
+      high: Bound::Included(Value::Text("c".to_string())),
+    }
+  )]
+  #[case(
+    "id > 1 AND (3 >= id AND id < 3)",
+    ScanPlan::IndexRange {
+      column_name: "id".to_string(),
+      low: Bound::Excluded(Value::Integer(1)),
+      high: Bound::Excluded(Value::Integer(3)),
+    }
+  )]
+  #[case(
+    "name = 'b' AND score = 2",
+    ScanPlan::TableKeyPoint {
+      column_names: vec!["name".to_string(), "score".to_string()],
+      values: vec![Value::Text("b".to_string()), Value::Integer(2)],
+    }
+  )]
+  #[case("name = 'b' AND score = 2 AND id = 2", ScanPlan::IndexPoint { column_name: "id".to_string(), value: Value::Integer(2) })]
+  #[case("name = 'b'", ScanPlan::IndexPoint { column_name: "name".to_string(), value: Value::Text("b".to_string()) })]
+  #[case("score = 2", ScanPlan::FullTable)]
+  #[case("id = 2 OR id = 3", ScanPlan::FullTable)]
+  #[case("id = score", ScanPlan::FullTable)]
+  #[case("id = '2'", ScanPlan::FullTable)]
+  #[case("id < 2.5", ScanPlan::FullTable)]
+  #[case("id NOT BETWEEN 1 AND 2", ScanPlan::FullTable)]
+  #[case("other.id = 2", ScanPlan::FullTable)]
+  fn test_get_scan_plan(
+    #[case] input: &str,
+    #[case] expected: ScanPlan,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER, UNIQUE (name, score));",
+      "INSERT INTO test (name, score) VALUES ('c', 2);",
+      "INSERT INTO test (name, score) VALUES ('b', 2);",
+      "INSERT INTO test (name, score) VALUES ('d', 3);",
+      "CREATE INDEX idx_name ON test (name);",
+    ]);
+    let table = database.get_table("test".to_string()).unwrap();
+    let table_rows = vec![TableRow { table_name: "test".to_string(), table, row_id: None }];
+
+    assert_eq!(get_scan_plan(&table_rows, &Some(parse_expression(input))), expected);
+  }
+
+  #[rstest]
+  #[case("id = 2", vec![2])]
+  #[case("id = 5", vec![])]
+  #[case("score = 2 AND name = 'b'", vec![2])]
+  #[case("score = 3 AND name = 'b'", vec![])]
+  #[case("name > 'b'", vec![1, 3])]
+  #[case("name BETWEEN 'c' AND 'a'", vec![])]
+  #[case("id > 3", vec![])]
+  fn test_scan_plan_get_row_ids(
+    #[case] input: &str,
+    #[case] expected: Vec<i64>,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER, UNIQUE (name, score));",
+      "INSERT INTO test (name, score) VALUES ('c', 2);",
+      "INSERT INTO test (name, score) VALUES ('b', 2);",
+      "INSERT INTO test (name, score) VALUES ('d', 3);",
+      "CREATE INDEX idx_name ON test (name);",
+    ]);
+    let table = database.get_table("test".to_string()).unwrap();
+    let table_rows = vec![TableRow { table_name: "test".to_string(), table, row_id: None }];
+    let scan_plan = get_scan_plan(&table_rows, &Some(parse_expression(input)));
+
+    assert_ne!(scan_plan, ScanPlan::FullTable);
+    assert_eq!(scan_plan.get_row_ids(table), Ok(expected));
+  }
+}
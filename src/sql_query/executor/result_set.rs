@@ -0,0 +1,65 @@
+use prettytable::{
+  Table as PrintTable,
+  Row as PrintRow,
+  Cell as PrintCell,
+};
+
+use crate::error::{Result, NollaDBError};
+use crate::table::row::value::Value;
+
+// SELECT 执行完之后得到的结果集
+#[derive(PartialEq, Debug)]
+pub struct ResultSet {
+  pub column_names: Vec<String>,
+  pub rows: Vec<Vec<Value>>,
+}
+
+impl ResultSet {
+  pub fn new(column_names: Vec<String>) -> Self {
+    ResultSet {
+      column_names,
+      rows: vec![],
+    }
+  }
+
+  pub fn print_result_set(&self) -> Result<usize> {
+    let mut print_table = PrintTable::new();
+
+    // column name
+    // 输出为最顶部的 header
+    print_table.add_row(PrintRow::new(
+      self.column_names
+        .iter()
+        .map(|column_name| PrintCell::new(column_name))
+        .collect::<Vec<PrintCell>>(),
+    ));
+
+    for row in &self.rows {
+      print_table.add_row(PrintRow::new(
+        row
+          .iter()
+          .map(|value| value.get_print_cell())
+          .collect::<Vec<PrintCell>>(),
+      ));
+    }
+
+    print_table
+      .print_tty(false)
+      .map_err(|error| NollaDBError::Internal(error.to_string()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::{assert_eq};
+
+  #[test]
+  fn test_print_result_set() {
+    let mut result_set = ResultSet::new(vec!["id".to_string(), "name".to_string()]);
+    result_set.rows.push(vec![Value::Integer(1), Value::Text("xxx".to_string())]);
+
+    // 1 条 header + 1 条数据，外加 3 条分割线
+    assert_eq!(result_set.print_result_set(), Ok(5));
+  }
+}
@@ -0,0 +1,176 @@
+use sqlparser::ast::Expr;
+
+use crate::error::{Result, NollaDBError};
+use crate::table::Table;
+use crate::table::row::value::Value;
+use crate::table::row::json::{Json, JsonPath, JsonPathStep};
+use crate::sql_query::get_sql_ast;
+use crate::sql_query::query::create::CreateQuery;
+use crate::sql_query::query::select::SelectTable;
+use crate::sql_query::expression::{RowContext, evaluate_expression};
+use crate::sql_query::expression::json::{get_json_argument, get_json_path_argument};
+
+// json_each 输出的列，和 SQLite 一样
+const JSON_EACH_COLUMN_NAMES: [&str; 6] = ["key", "value", "type", "atom", "fullkey", "path"];
+
+// FROM 中的表值函数展开之后得到的一张临时表
+// 参数可以引用 FROM 中前面的表（比如 FROM logs, json_each(logs.payload)），所以每一组不同的参数值都展开一次
+// 所有展开出来的行都放在同一张表里面，JOIN 的时候再按照左边这一行的参数值找到对应的行
+pub struct TableFunction {
+  pub table: Table,
+  args: Vec<Expr>,
+  // 每一组参数值以及它展开出来的行，Value 没有实现 Hash（Real 是 f64），所以按顺序逐个比较
+  row_ids: Vec<(Vec<Value>, Vec<i64>)>,
+}
+
+impl TableFunction {
+  // row_contexts 是 FROM 中前面的表 JOIN 好的所有行，前面没有表的话只有一个空的 RowContext
+  pub fn new(select_table: &SelectTable, args: &[Expr], row_contexts: &[RowContext]) -> Result<TableFunction> {
+    let mut arg_values: Vec<(Vec<Value>, usize)> = vec![];
+    let mut rows: Vec<Vec<Value>> = vec![];
+    for row_context in row_contexts {
+      let values = args
+        .iter()
+        .map(|arg| evaluate_expression(arg, row_context))
+        .collect::<Result<Vec<Value>>>()?;
+      if arg_values.iter().any(|(other_values, _)| *other_values == values) {
+        continue;
+      }
+      let function_rows = get_json_each_rows(&select_table.table_name, &values)?;
+      arg_values.push((values, function_rows.len()));
+      rows.extend(function_rows);
+    }
+
+    // key、value 以及 atom 列的类型由展开出来的值决定，Json 列中的 Text 要先转换成 JSON 的字符串
+    let column_types = (0..JSON_EACH_COLUMN_NAMES.len())
+      .map(|i| get_column_type(rows.iter().map(|row| &row[i])))
+      .collect::<Vec<&str>>();
+    for row in rows.iter_mut() {
+      for (value, column_type) in row.iter_mut().zip(column_types.iter()) {
+        if *column_type == "JSON" {
+          *value = Json::from_sql_value(value).map_or(Value::Null, Value::Json);
+        }
+      }
+    }
+    let column_definitions = JSON_EACH_COLUMN_NAMES
+      .iter()
+      .zip(column_types.iter())
+      .map(|(column_name, column_type)| format!("{} {}", column_name, column_type))
+      .collect::<Vec<String>>();
+    let create_query = CreateQuery::new(&get_sql_ast(&format!(
+      "CREATE TABLE {} ({});",
+      select_table.table_name,
+      column_definitions.join(", ")
+    ))?)?;
+    let mut table = Table::new(create_query);
+
+    let column_names = JSON_EACH_COLUMN_NAMES
+      .iter()
+      .map(|column_name| column_name.to_string())
+      .collect::<Vec<String>>();
+    let mut rows = rows.into_iter();
+    let mut row_ids: Vec<(Vec<Value>, Vec<i64>)> = vec![];
+    for (values, row_count) in arg_values {
+      let mut arg_row_ids: Vec<i64> = vec![];
+      for row in rows.by_ref().take(row_count) {
+        arg_row_ids.push(table.insert_row(&column_names, &row)?);
+      }
+      row_ids.push((values, arg_row_ids));
+    }
+
+    Ok(TableFunction {
+      table,
+      args: args.to_vec(),
+      row_ids,
+    })
+  }
+
+  // 左边这一行的参数值对应的所有行
+  pub fn get_row_ids(&self, row_context: &RowContext) -> Result<Vec<i64>> {
+    let values = self.args
+      .iter()
+      .map(|arg| evaluate_expression(arg, row_context))
+      .collect::<Result<Vec<Value>>>()?;
+    Ok(
+      self.row_ids
+        .iter()
+        .find(|(other_values, _)| *other_values == values)
+        .map_or(vec![], |(_, row_ids)| row_ids.clone())
+    )
+  }
+}
+
+// 每一列都是同一种类型的话就用这种类型，Integer 和 Real 混在一起的话用 Real，其他情况用 Json
+// 全是 NULL 的列也用 Json
+fn get_column_type<'a>(values: impl Iterator<Item = &'a Value>) -> &'static str {
+  let mut column_type: Option<&'static str> = None;
+  for value in values {
+    let value_type = match value {
+      Value::Null => continue,
+      Value::Integer(_) => "INTEGER",
+      Value::Real(_) => "REAL",
+      Value::Bool(_) => "BOOLEAN",
+      Value::Text(_) => "TEXT",
+      _ => "JSON",
+    };
+    column_type = match (column_type, value_type) {
+      (None, value_type) => Some(value_type),
+      (Some(column_type), value_type) if column_type == value_type => Some(column_type),
+      (Some("INTEGER" | "REAL"), "INTEGER" | "REAL") => Some("REAL"),
+      _ => return "JSON",
+    };
+  }
+  column_type.unwrap_or("JSON")
+}
+
+// json_each(json[, path])：数组的每一个元素或者对象的每一个 key 输出一行，标量输出一行并且 key 为 NULL
+// json 为 NULL 或者 path 找不到的话没有任何行
+fn get_json_each_rows(function_name: &str, values: &[Value]) -> Result<Vec<Vec<Value>>> {
+  let (json, path) = match values {
+    [json] => (get_json_argument(json)?, Some(JsonPath::default())),
+    [json, path] => (get_json_argument(json)?, get_json_path_argument(path)?),
+    _ => return Err(NollaDBError::Internal(
+      format!("Wrong number of arguments to table function {}()", function_name)
+    )),
+  };
+  let (json, path) = match (json, path) {
+    (Some(json), Some(path)) => match json.extract(&path) {
+      Some(json) => (json, path),
+      None => return Ok(vec![]),
+    },
+    _ => return Ok(vec![]),
+  };
+
+  let rows = match json.get_children() {
+    Some(children) => children
+      .into_iter()
+      .map(|(step, child)| {
+        let mut fullkey = path.clone();
+        fullkey.push(step.clone());
+        let key = match step {
+          JsonPathStep::Key(key) => Value::Text(key),
+          JsonPathStep::Index(i) | JsonPathStep::IndexFromEnd(i) => Value::Integer(i as i64),
+        };
+        get_json_each_row(key, &child, &fullkey, &path)
+      })
+      .collect(),
+    None => vec![get_json_each_row(Value::Null, &json, &path, &path)],
+  };
+
+  Ok(rows)
+}
+
+// key, value, type, atom, fullkey, path
+// value 是对应的 SQL 值，数组和对象仍然是 JSON；atom 只有标量才有值
+fn get_json_each_row(key: Value, json: &Json, fullkey: &JsonPath, path: &JsonPath) -> Vec<Value> {
+  let value = json.get_sql_value();
+  let atom = if json.is_container() { Value::Null } else { value.clone() };
+  vec![
+    key,
+    value,
+    Value::Text(json.get_type_name().to_string()),
+    atom,
+    Value::Text(fullkey.to_string()),
+    Value::Text(path.to_string()),
+  ]
+}
@@ -0,0 +1,304 @@
+use sqlparser::ast::{
+  Expr,
+  Function,
+  FunctionArg,
+};
+
+use crate::error::{Result, NollaDBError};
+use crate::table::Table;
+use crate::table::row::value::Value;
+use crate::table::row::decimal::Decimal;
+use super::{RowContext, evaluate_expression};
+
+const AGGREGATE_FUNCTION_NAMES: [&str; 5] = ["COUNT", "SUM", "AVG", "MIN", "MAX"];
+
+// 只有一个参数的时候才算聚合函数，比如 MIN(a, b) 是普通的函数
+pub fn is_aggregate_function(function: &Function) -> bool {
+  function.args.len() == 1
+    && AGGREGATE_FUNCTION_NAMES.contains(&function.name.to_string().to_uppercase().as_str())
+}
+
+// 找出表达式里面用到的所有聚合函数，重复的只保留一个
+pub fn collect_aggregate_functions(expr: &Expr, aggregate_exprs: &mut Vec<Expr>) {
+  match expr {
+    Expr::Function(function) => {
+      if is_aggregate_function(function) {
+        if !aggregate_exprs.contains(expr) {
+          aggregate_exprs.push(expr.clone());
+        }
+        return;
+      }
+      for function_arg in &function.args {
+        match function_arg {
+          FunctionArg::Named { arg, .. } => collect_aggregate_functions(arg, aggregate_exprs),
+          FunctionArg::Unnamed(arg) => collect_aggregate_functions(arg, aggregate_exprs),
+        }
+      }
+    },
+    Expr::Nested(expr)
+    | Expr::IsNull(expr)
+    | Expr::IsNotNull(expr)
+    | Expr::UnaryOp { expr, .. }
+    | Expr::Cast { expr, .. }
+    | Expr::Trim { expr, trim_where: None } => collect_aggregate_functions(expr, aggregate_exprs),
+    Expr::Trim { expr, trim_where: Some((_, characters)) } => {
+      collect_aggregate_functions(expr, aggregate_exprs);
+      collect_aggregate_functions(characters, aggregate_exprs);
+    },
+    Expr::BinaryOp { left, right, .. } => {
+      collect_aggregate_functions(left, aggregate_exprs);
+      collect_aggregate_functions(right, aggregate_exprs);
+    },
+    Expr::Between { expr, low, high, .. } => {
+      collect_aggregate_functions(expr, aggregate_exprs);
+      collect_aggregate_functions(low, aggregate_exprs);
+      collect_aggregate_functions(high, aggregate_exprs);
+    },
+    Expr::InList { expr, list, .. } => {
+      collect_aggregate_functions(expr, aggregate_exprs);
+      for expr in list {
+        collect_aggregate_functions(expr, aggregate_exprs);
+      }
+    },
+    _ => {},
+  }
+}
+
+pub fn contains_aggregate_function(expr: &Expr) -> bool {
+  let mut aggregate_exprs: Vec<Expr> = vec![];
+  collect_aggregate_functions(expr, &mut aggregate_exprs);
+  !aggregate_exprs.is_empty()
+}
+
+// 在一个分组的所有行上计算聚合函数
+// is_all_rows 表示分组里面就是表中所有的行
+pub fn evaluate_aggregate_function(
+  function: &Function,
+  row_contexts: &[RowContext],
+  is_all_rows: bool,
+) -> Result<Value> {
+  let function_name = function.name.to_string().to_uppercase();
+  let arg = match function.args.as_slice() {
+    [FunctionArg::Unnamed(arg)] => arg,
+    _ => return Err(NollaDBError::Internal(
+      format!("Wrong number of arguments to function {}()", function_name)
+    )),
+  };
+
+  // COUNT(*) 只需要知道有多少行
+  if let Expr::Wildcard = arg {
+    if function_name != "COUNT" || function.distinct {
+      return Err(NollaDBError::Internal(
+        format!("Wrong arguments to function {}()", function_name)
+      ));
+    }
+    return Ok(Value::Integer(row_contexts.len() as i64));
+  }
+
+  let mut values = match row_contexts.first().and_then(|row_context| get_table_column(row_context, arg)) {
+    // 单表查询并且参数就是一个列的话，直接从这一列里面批量取值，不用逐行对表达式求值
+    // 分组里面是所有行的时候，直接扫描整列的 BTreeMap
+    Some((table, column_name)) => if is_all_rows {
+      table.get_column_values(&column_name, None)?
+    } else {
+      let row_ids = row_contexts
+        .iter()
+        .filter_map(|row_context| row_context.table_rows[0].row_id)
+        .collect::<Vec<i64>>();
+      table.get_column_values(&column_name, Some(&row_ids))?
+    },
+    None => row_contexts
+      .iter()
+      .map(|row_context| evaluate_expression(arg, row_context))
+      .collect::<Result<Vec<Value>>>()?,
+  };
+
+  // 聚合函数都会忽略 NULL
+  values.retain(|value| !value.is_null());
+  if function.distinct {
+    values.sort_by(|a, b| a.compare_for_order_by(b));
+    values.dedup();
+  }
+
+  match function_name.as_str() {
+    "COUNT" => Ok(Value::Integer(values.len() as i64)),
+    "SUM" => get_sum_value(&values),
+    "AVG" => match get_sum_value(&values)? {
+      Value::Integer(sum) => Ok(Value::Real(sum as f64 / values.len() as f64)),
+      Value::Real(sum) => Ok(Value::Real(sum / values.len() as f64)),
+      Value::Decimal(sum) => sum
+        .checked_div(Decimal::from(values.len() as i64))
+        .map(Value::Decimal)
+        .ok_or_else(|| NollaDBError::Internal("Decimal overflow in AVG".to_string())),
+      _ => Ok(Value::Null),
+    },
+    "MIN" => Ok(values
+      .into_iter()
+      .min_by(|a, b| a.compare_for_order_by(b))
+      .unwrap_or(Value::Null)),
+    "MAX" => Ok(values
+      .into_iter()
+      .max_by(|a, b| a.compare_for_order_by(b))
+      .unwrap_or(Value::Null)),
+    _ => Err(NollaDBError::Internal(
+      format!("Unknown aggregate function {}()", function_name)
+    )),
+  }
+}
+
+// 只有一张表的时候，如果表达式引用的是这张表上的某一列，返回这张表以及列名
+fn get_table_column<'a>(row_context: &RowContext<'a>, expr: &Expr) -> Option<(&'a Table, String)> {
+  let table_row = match row_context.table_rows.as_slice() {
+    [table_row] => table_row,
+    _ => return None,
+  };
+  let column_name = match expr {
+    Expr::Identifier(ident) => ident.to_string(),
+    Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+      [prefix, ident] if prefix.to_string() == table_row.table_name => ident.to_string(),
+      _ => return None,
+    },
+    _ => return None,
+  };
+
+  if table_row.table.has_column(column_name.to_string()) {
+    Some((table_row.table, column_name))
+  } else {
+    None
+  }
+}
+
+// 全是 Integer 的时候结果也是 Integer，有 Real 的话结果就是 Real，否则有 Decimal 的话结果是 Decimal
+// 没有值的话结果是 NULL
+fn get_sum_value(values: &[Value]) -> Result<Value> {
+  if values.is_empty() {
+    return Ok(Value::Null);
+  }
+
+  let mut integer_sum: Option<i64> = Some(0);
+  let mut decimal_sum: Option<Decimal> = Some(Decimal::from(0));
+  let mut real_sum: f64 = 0.0;
+  let mut is_real = false;
+  let mut is_decimal = false;
+  for value in values {
+    match value {
+      Value::Integer(i) => {
+        integer_sum = integer_sum.and_then(|sum| sum.checked_add(*i));
+        decimal_sum = decimal_sum.and_then(|sum| sum.checked_add(Decimal::from(*i)));
+        real_sum += *i as f64;
+      },
+      Value::Decimal(d) => {
+        is_decimal = true;
+        decimal_sum = decimal_sum.and_then(|sum| sum.checked_add(*d));
+        real_sum += d.to_real();
+      },
+      Value::Real(r) => {
+        is_real = true;
+        real_sum += *r;
+      },
+      _ => return Err(NollaDBError::TypeMismatch(
+        format!("SUM and AVG expect numeric values, but found {} value '{}'", value.get_type_name(), value)
+      )),
+    }
+  }
+
+  if is_real {
+    Ok(Value::Real(real_sum))
+  } else if is_decimal {
+    decimal_sum
+      .map(Value::Decimal)
+      .ok_or_else(|| NollaDBError::Internal("Decimal overflow in SUM".to_string()))
+  } else {
+    integer_sum
+      .map(Value::Integer)
+      .ok_or_else(|| NollaDBError::Internal("Integer overflow in SUM".to_string()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::test_helpers::{parse_expression, create_test_database};
+
+  #[rstest]
+  #[case("COUNT(*)", None, Value::Integer(3))]
+  #[case("COUNT(score)", None, Value::Integer(3))]
+  #[case("COUNT(DISTINCT score)", None, Value::Integer(2))]
+  #[case("SUM(score)", None, Value::Integer(7))]
+  #[case("SUM(score * 2)", Some(vec![1, 2]), Value::Integer(8))]
+  #[case("AVG(score)", Some(vec![1, 3]), Value::Real(2.5))]
+  #[case("MIN(name)", None, Value::Text("a".to_string()))]
+  #[case("MAX(test.score)", None, Value::Integer(3))]
+  #[case("MAX(score)", Some(vec![]), Value::Null)]
+  #[case("SUM(score)", Some(vec![]), Value::Null)]
+  #[case("COUNT(*)", Some(vec![]), Value::Integer(0))]
+  fn test_evaluate_aggregate_function(
+    #[case] input: &str,
+    #[case] row_ids: Option<Vec<i64>>,
+    #[case] expected: Value,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER);",
+      "INSERT INTO test (name, score) VALUES ('b', 2);",
+      "INSERT INTO test (name, score) VALUES ('a', 2);",
+      "INSERT INTO test (name, score) VALUES ('c', 3);",
+    ]);
+    let table = database.get_table("test".to_string()).unwrap();
+    let function = match parse_expression(input) {
+      Expr::Function(function) => function,
+      _ => panic!("Expected function"),
+    };
+
+    let is_all_rows = row_ids.is_none();
+    let row_contexts = row_ids
+      .unwrap_or_else(|| table.get_row_ids())
+      .into_iter()
+      .map(|row_id| RowContext::new(table, row_id))
+      .collect::<Vec<RowContext>>();
+
+    assert_eq!(
+      evaluate_aggregate_function(&function, &row_contexts, is_all_rows),
+      Ok(expected)
+    );
+  }
+
+  #[rstest]
+  #[case("SUM(name)")]
+  #[case("AVG(*)")]
+  fn test_evaluate_aggregate_function_error(#[case] input: &str) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER);",
+      "INSERT INTO test (name, score) VALUES ('b', 2);",
+      "INSERT INTO test (name, score) VALUES ('a', 2);",
+      "INSERT INTO test (name, score) VALUES ('c', 3);",
+    ]);
+    let table = database.get_table("test".to_string()).unwrap();
+    let function = match parse_expression(input) {
+      Expr::Function(function) => function,
+      _ => panic!("Expected function"),
+    };
+
+    let row_contexts = table
+      .get_row_ids()
+      .into_iter()
+      .map(|row_id| RowContext::new(table, row_id))
+      .collect::<Vec<RowContext>>();
+
+    assert!(evaluate_aggregate_function(&function, &row_contexts, true).is_err());
+  }
+
+  #[rstest]
+  #[case("COUNT(*) + SUM(a) * 2 > COUNT(*)", 2)]
+  #[case("MIN(a, b)", 0)]
+  #[case("a + 1", 0)]
+  fn test_collect_aggregate_functions(
+    #[case] input: &str,
+    #[case] expected_len: usize,
+  ) {
+    let mut aggregate_exprs: Vec<Expr> = vec![];
+    collect_aggregate_functions(&parse_expression(input), &mut aggregate_exprs);
+    assert_eq!(aggregate_exprs.len(), expected_len);
+  }
+}
@@ -0,0 +1,182 @@
+use sqlparser::ast::{
+  Expr,
+  BinaryOperator,
+};
+
+use crate::error::{Result, NollaDBError};
+use crate::table::row::value::Value;
+use crate::table::row::json::{Json, JsonPath, JsonPathStep};
+
+// 作为 JSON 参数的值，NULL 返回 None
+// Text 需要是合法的 JSON，Integer、Real 以及 Boolean 当作 JSON 的标量
+pub fn get_json_argument(value: &Value) -> Result<Option<Json>> {
+  match value {
+    Value::Null => Ok(None),
+    Value::Json(json) => Ok(Some(json.clone())),
+    Value::Text(s) => Ok(Some(s.parse::<Json>()?)),
+    value => Json::from_scalar(value)
+      .map(Some)
+      .ok_or_else(|| NollaDBError::TypeMismatch(
+        format!("Expected a JSON value, but found {} value '{}'", value.get_type_name(), value)
+      )),
+  }
+}
+
+// json_extract 以及 json_each 的 path 参数，必须是 $ 开头的 Text
+pub fn get_json_path_argument(value: &Value) -> Result<Option<JsonPath>> {
+  match value {
+    Value::Null => Ok(None),
+    Value::Text(s) => Ok(Some(s.parse::<JsonPath>()?)),
+    value => Err(NollaDBError::TypeMismatch(
+      format!("Expected a JSON path, but found {} value '{}'", value.get_type_name(), value)
+    )),
+  }
+}
+
+// 第一个参数是 JSON，后面的参数都是 path，有一个是 NULL 的话返回 None
+fn get_json_and_paths(values: &[Value]) -> Result<Option<(Json, Vec<JsonPath>)>> {
+  let (json, paths) = match values.split_first() {
+    Some((json, paths)) => (get_json_argument(json)?, paths),
+    None => return Ok(None),
+  };
+  let paths = paths
+    .iter()
+    .map(get_json_path_argument)
+    .collect::<Result<Option<Vec<JsonPath>>>>()?;
+  Ok(json.zip(paths))
+}
+
+// json_extract(json, path, ...)：只有一个 path 的话，标量返回对应的 SQL 值，数组和对象返回 JSON，找不到的话返回 NULL
+// 有多个 path 的话返回一个 JSON 数组，找不到的 path 对应 null
+pub fn json_extract(values: &[Value]) -> Result<Value> {
+  Ok(match get_json_and_paths(values)? {
+    Some((json, paths)) => match paths.as_slice() {
+      [path] => json.extract(path).map_or(Value::Null, |json| json.get_sql_value()),
+      paths => Value::Json(Json::from_array(
+        &paths.iter().map(|path| json.extract(path)).collect::<Vec<Option<Json>>>()
+      )),
+    },
+    None => Value::Null,
+  })
+}
+
+// json_array_length(json[, path])：不是数组的话返回 0，path 找不到的话返回 NULL
+pub fn json_array_length(values: &[Value]) -> Result<Value> {
+  Ok(match get_json_and_paths(values)? {
+    Some((json, paths)) => match paths.first() {
+      Some(path) => json.extract(path).map_or(Value::Null, |json| Value::Integer(json.get_array_length() as i64)),
+      None => Value::Integer(json.get_array_length() as i64),
+    },
+    None => Value::Null,
+  })
+}
+
+// -> 和 ->> 右边的值，$ 开头的 Text 是 path，其他的 Text 是对象的 key，整数是数组的下标（负数从末尾往前数）
+fn get_json_arrow_path(value: &Value) -> Result<Option<JsonPath>> {
+  match value {
+    Value::Text(s) if s.starts_with('$') => get_json_path_argument(value),
+    Value::Text(s) => Ok(Some(JsonPath::from(JsonPathStep::Key(s.to_string())))),
+    Value::Integer(i) if *i >= 0 => Ok(Some(JsonPath::from(JsonPathStep::Index(*i as usize)))),
+    Value::Integer(i) => Ok(Some(JsonPath::from(JsonPathStep::IndexFromEnd(i.unsigned_abs() as usize)))),
+    _ => get_json_path_argument(value),
+  }
+}
+
+// json -> path 返回 JSON，json ->> path 返回对应的 SQL 值，找不到的话都是 NULL
+pub fn evaluate_json_arrow_operation(is_sql_value: bool, left: Value, right: Value) -> Result<Value> {
+  let (json, path) = match (get_json_argument(&left)?, get_json_arrow_path(&right)?) {
+    (Some(json), Some(path)) => (json, path),
+    _ => return Ok(Value::Null),
+  };
+
+  Ok(match json.extract(&path) {
+    Some(json) if is_sql_value => json.get_sql_value(),
+    Some(json) => Value::Json(json),
+    None => Value::Null,
+  })
+}
+
+// -> 和 ->> 在解析的时候被换成了 ^ 和 |（见 tokenize_sql_query），输出的列名里面再换回来
+pub fn get_expr_display(expr: &Expr) -> String {
+  match expr {
+    Expr::BinaryOp { left, op, right } => {
+      let op = match op {
+        BinaryOperator::BitwiseXor => "->".to_string(),
+        BinaryOperator::BitwiseOr => "->>".to_string(),
+        op => op.to_string(),
+      };
+      format!("{} {} {}", get_expr_display(left), op, get_expr_display(right))
+    },
+    Expr::Nested(expr) => format!("({})", get_expr_display(expr)),
+    expr => expr.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use crate::sql_query::expression::{RowContext, evaluate_expression};
+  use pretty_assertions::{assert_eq};
+  use crate::test_helpers::parse_expression;
+  use crate::sql_query::tokenize_sql_query;
+
+  fn json(s: &str) -> Value {
+    Value::Json(s.parse::<Json>().unwrap())
+  }
+
+  #[rstest]
+  #[case(r#"json_extract('{"a": {"b": [1, 2.5, "x"]}}', '$.a.b[0]')"#, Value::Integer(1))]
+  #[case(r#"json_extract('{"a": {"b": [1, 2.5, "x"]}}', '$.a.b[#-1]')"#, Value::Text("x".to_string()))]
+  #[case(r#"json_extract('{"a": {"b": [1, 2.5, "x"]}}', '$.a')"#, json(r#"{"b":[1,2.5,"x"]}"#))]
+  #[case(r#"json_extract('{"a": {"b": [1, 2.5, "x"]}}', '$.a.c')"#, Value::Null)]
+  #[case(r#"json_extract('{"a": 1, "b": true}', '$.a', '$.b', '$.c')"#, json("[1,true,null]"))]
+  #[case("json_extract(NULL, '$.a')", Value::Null)]
+  #[case("json_array_length('[1, [2, 3]]')", Value::Integer(2))]
+  #[case("json_array_length('[1, [2, 3]]', '$[1]')", Value::Integer(2))]
+  #[case(r#"json_array_length('{"a": 1}')"#, Value::Integer(0))]
+  #[case("json_array_length('[1]', '$[5]')", Value::Null)]
+  #[case(r#"'{"a": {"b": "x"}}' -> '$.a'"#, json(r#"{"b":"x"}"#))]
+  #[case(r#"'{"a": {"b": "x"}}' -> 'a' -> 'b'"#, json(r#""x""#))]
+  #[case(r#"'{"a": {"b": "x"}}' -> 'a' ->> 'b'"#, Value::Text("x".to_string()))]
+  #[case("'[10, 20, 30]' ->> 1", Value::Integer(20))]
+  #[case("'[10, 20, 30]' ->> -1", Value::Integer(30))]
+  #[case(r#"'{"a": 1}' ->> '$.b'"#, Value::Null)]
+  #[case(r#"'{"n": 1.5}' ->> 'n' = 1.5"#, Value::Bool(true))]
+  #[case(r#"'{"n": 2}' -> 'n' = 2"#, Value::Bool(true))]
+  fn test_evaluate_json_expression(
+    #[case] input: &str,
+    #[case] expected: Value,
+  ) {
+    assert_eq!(evaluate_expression(&parse_expression(input), &RowContext::default()), Ok(expected));
+  }
+
+  #[rstest]
+  #[case("json_extract('{a: 1}', '$.a')")]
+  #[case("json_extract('{}', 'a')")]
+  #[case("json_extract('{}')")]
+  #[case("json_array_length('[]', '$', '$')")]
+  #[case("json_extract(X'00', '$')")]
+  #[case("'{' -> 'a'")]
+  fn test_evaluate_json_expression_error(#[case] input: &str) {
+    assert!(evaluate_expression(&parse_expression(input), &RowContext::default()).is_err());
+  }
+
+  #[rstest]
+  #[case("payload ->> '$.level'", "payload ->> '$.level'")]
+  #[case("(payload -> 'a') ->> 0", "(payload -> 'a') ->> 0")]
+  #[case("json_extract(payload, '$.a')", "json_extract(payload, '$.a')")]
+  fn test_get_expr_display(
+    #[case] input: &str,
+    #[case] expected: &str,
+  ) {
+    assert_eq!(get_expr_display(&parse_expression(input)), expected);
+  }
+
+  #[rstest]
+  #[case("1 ^ 2")]
+  #[case("1 | 2")]
+  fn test_unsupported_bitwise_operator(#[case] input: &str) {
+    assert!(tokenize_sql_query(input).is_err());
+  }
+}
@@ -0,0 +1,550 @@
+pub mod aggregate;
+pub mod json;
+pub mod scalar;
+pub mod sql_text;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use sqlparser::ast::{
+  Expr,
+  Value as SQLValue,
+  DataType as SQLDataType,
+  BinaryOperator,
+  UnaryOperator,
+  FunctionArg,
+  Ident,
+};
+
+use crate::error::{Result, NollaDBError};
+use crate::table::Table;
+use crate::table::row::value::Value;
+use crate::table::row::date_time::{Date, Time, Timestamp};
+use crate::table::row::decimal::Decimal;
+
+// 参与表达式求值的一张表中的某一行
+// table_name 是 SQL 里面引用这张表时用的名字
+// row_id 为 None 表示这张表上没有对应的行（比如 LEFT JOIN 没有匹配上），这时所有的列都是 NULL
+#[derive(Clone)]
+pub struct TableRow<'a> {
+  pub table_name: String,
+  pub table: &'a Table,
+  pub row_id: Option<i64>,
+}
+
+// 表达式求值时所在的上下文
+// 没有表参与的时候（比如 INSERT 的 VALUES）table_rows 为空
+// 聚合查询时，每个分组上聚合函数的结果放在 aggregate_values 里面
+// 还没有写入表中的一行（比如检查 CHECK 约束时）放在 column_values 里面
+#[derive(Default, Clone)]
+pub struct RowContext<'a> {
+  pub table_rows: Vec<TableRow<'a>>,
+  pub aggregate_values: HashMap<Expr, Value>,
+  pub column_values: HashMap<String, Value>,
+}
+
+impl<'a> RowContext<'a> {
+  pub fn new(table: &'a Table, row_id: i64) -> Self {
+    RowContext {
+      table_rows: vec![TableRow {
+        table_name: table.table_name.to_string(),
+        table,
+        row_id: Some(row_id),
+      }],
+      aggregate_values: HashMap::new(),
+      column_values: HashMap::new(),
+    }
+  }
+
+  pub fn from_column_values(column_values: HashMap<String, Value>) -> Self {
+    RowContext {
+      column_values,
+      ..RowContext::default()
+    }
+  }
+
+  // 根据列名（以及可能有的表名前缀）拿到当前行对应的值
+  fn get_column_value(&self, table_name: Option<&str>, column_name: &str) -> Result<Value> {
+    if let Some(value) = self.column_values.get(column_name) {
+      return Ok(value.clone());
+    }
+
+    let mut matched_table_rows = self
+      .table_rows
+      .iter()
+      .filter(|table_row| match table_name {
+        Some(table_name) => table_row.table_name == table_name,
+        None => true,
+      })
+      .filter(|table_row| table_row.table.has_column(column_name.to_string()));
+
+    match (matched_table_rows.next(), matched_table_rows.next()) {
+      (Some(table_row), None) => match table_row.row_id {
+        Some(row_id) => table_row.table.get_value(column_name, &row_id),
+        None => Ok(Value::Null),
+      },
+      (Some(_), Some(_)) => Err(NollaDBError::Internal(
+        format!("Ambiguous column name '{}'", column_name)
+      )),
+      _ => Err(NollaDBError::Internal(
+        format!("Unknown column '{}'", match table_name {
+          Some(table_name) => format!("{}.{}", table_name, column_name),
+          None => column_name.to_string(),
+        })
+      )),
+    }
+  }
+}
+
+// 把 SQL 里面的字面量转换成 Value
+pub fn get_literal_value(sql_value: &SQLValue) -> Result<Value> {
+  match sql_value {
+    SQLValue::Number(n, _) => {
+      if let Ok(i) = n.parse::<i64>() {
+        Ok(Value::Integer(i))
+      } else if let Ok(r) = n.parse::<f64>() {
+        Ok(Value::Real(r))
+      } else {
+        Err(NollaDBError::Internal(format!("Invalid number '{}'", n)))
+      }
+    },
+    SQLValue::SingleQuotedString(s) => Ok(Value::Text(s.to_string())),
+    // X'0A1B'，每两个十六进制字符表示一个字节
+    SQLValue::HexStringLiteral(s) => {
+      if s.len() % 2 != 0 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(NollaDBError::Internal(format!("Invalid hex literal '{}'", sql_value)));
+      }
+      Ok(Value::Blob(
+        (0..s.len())
+          .step_by(2)
+          .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+          .collect()
+      ))
+    },
+    SQLValue::Boolean(b) => Ok(Value::Bool(*b)),
+    SQLValue::Null => Ok(Value::Null),
+    _ => Err(NollaDBError::ToBeImplemented(
+      format!("Literal '{}' will be implemented soon", sql_value)
+    )),
+  }
+}
+
+// 对表达式进行求值
+pub fn evaluate_expression(expr: &Expr, row_context: &RowContext) -> Result<Value> {
+  match expr {
+    Expr::Identifier(ident) => row_context.get_column_value(None, &ident.to_string()),
+    Expr::CompoundIdentifier(idents) => match idents.as_slice() {
+      [table_name, column_name] => row_context.get_column_value(
+        Some(&table_name.to_string()),
+        &column_name.to_string(),
+      ),
+      _ => Err(NollaDBError::Internal(format!("Unknown column '{}'", expr))),
+    },
+    Expr::Value(sql_value) => get_literal_value(sql_value),
+    // DATE '2024-01-01'、TIME '12:00:00'、TIMESTAMP '2024-01-01 12:00:00' 以及 DECIMAL '1.5'
+    Expr::TypedString { data_type, value } => match data_type {
+      SQLDataType::Date => Ok(Value::Date(value.parse::<Date>()?)),
+      SQLDataType::Time => Ok(Value::Time(value.parse::<Time>()?)),
+      SQLDataType::Timestamp => Ok(Value::Timestamp(value.parse::<Timestamp>()?)),
+      SQLDataType::Decimal(_, _) => Ok(Value::Decimal(value.parse::<Decimal>()?)),
+      _ => Err(NollaDBError::ToBeImplemented(
+        format!("Expression '{}' will be implemented soon", expr)
+      )),
+    },
+    Expr::Nested(expr) => evaluate_expression(expr, row_context),
+    Expr::Function(function) => match row_context.aggregate_values.get(expr) {
+      Some(value) => Ok(value.clone()),
+      None if aggregate::is_aggregate_function(function) => Err(NollaDBError::Internal(
+        format!("Misuse of aggregate function '{}'", expr)
+      )),
+      None => scalar::evaluate_scalar_function(function, row_context),
+    },
+    Expr::Trim { expr, trim_where } => {
+      let value = evaluate_expression(expr, row_context)?;
+      match trim_where {
+        Some((trim_where_field, characters)) => {
+          let characters = evaluate_expression(characters, row_context)?;
+          scalar::evaluate_trim(&value, Some((trim_where_field, &characters)))
+        },
+        None => scalar::evaluate_trim(&value, None),
+      }
+    },
+    // a BETWEEN b AND c 等价于 a >= b AND a <= c
+    Expr::Between { expr, negated, low, high } => {
+      let value = evaluate_expression(expr, row_context)?;
+      let low = evaluate_expression(low, row_context)?;
+      let high = evaluate_expression(high, row_context)?;
+      let is_greater_than_low = get_boolean_value(
+        &evaluate_binary_operation(&BinaryOperator::GtEq, value.clone(), low)?
+      )?;
+      let is_less_than_high = get_boolean_value(
+        &evaluate_binary_operation(&BinaryOperator::LtEq, value, high)?
+      )?;
+      match (is_greater_than_low, is_less_than_high) {
+        (Some(false), _) | (_, Some(false)) => Ok(Value::Bool(*negated)),
+        (Some(true), Some(true)) => Ok(Value::Bool(!*negated)),
+        _ => Ok(Value::Null),
+      }
+    },
+    Expr::IsNull(expr) => Ok(Value::Bool(evaluate_expression(expr, row_context)?.is_null())),
+    Expr::IsNotNull(expr) => Ok(Value::Bool(!evaluate_expression(expr, row_context)?.is_null())),
+    Expr::UnaryOp { op, expr } => {
+      let value = evaluate_expression(expr, row_context)?;
+      evaluate_unary_operation(op, value)
+    },
+    Expr::BinaryOp { left, op, right } => {
+      // AND 和 OR 需要按照三值逻辑来处理 NULL
+      match op {
+        BinaryOperator::And => {
+          let left = get_boolean_value(&evaluate_expression(left, row_context)?)?;
+          if left == Some(false) { return Ok(Value::Bool(false)); }
+          let right = get_boolean_value(&evaluate_expression(right, row_context)?)?;
+          match (left, right) {
+            (_, Some(false)) => Ok(Value::Bool(false)),
+            (Some(true), Some(true)) => Ok(Value::Bool(true)),
+            _ => Ok(Value::Null),
+          }
+        },
+        BinaryOperator::Or => {
+          let left = get_boolean_value(&evaluate_expression(left, row_context)?)?;
+          if left == Some(true) { return Ok(Value::Bool(true)); }
+          let right = get_boolean_value(&evaluate_expression(right, row_context)?)?;
+          match (left, right) {
+            (_, Some(true)) => Ok(Value::Bool(true)),
+            (Some(false), Some(false)) => Ok(Value::Bool(false)),
+            _ => Ok(Value::Null),
+          }
+        },
+        _ => {
+          let left = evaluate_expression(left, row_context)?;
+          let right = evaluate_expression(right, row_context)?;
+          evaluate_binary_operation(op, left, right)
+        },
+      }
+    },
+    _ => Err(NollaDBError::ToBeImplemented(
+      format!("Expression '{}' will be implemented soon", expr)
+    )),
+  }
+}
+
+// 对 WHERE 之类的条件表达式求值，只有结果为 true 的才算满足条件
+pub fn evaluate_predicate(expr: &Expr, row_context: &RowContext) -> Result<bool> {
+  let value = evaluate_expression(expr, row_context)?;
+  Ok(get_boolean_value(&value)?.unwrap_or(false))
+}
+
+// 对 CHECK 约束求值，和 WHERE 不一样，结果为 NULL 的时候也算满足约束
+pub fn evaluate_check(expr: &Expr, row_context: &RowContext) -> Result<bool> {
+  let value = evaluate_expression(expr, row_context)?;
+  Ok(get_boolean_value(&value)?.unwrap_or(true))
+}
+
+// 找到表达式中引用的每一个列名，用来在改列名或者删除列时修改或者检查保存下来的 CHECK 约束
+pub fn visit_column_idents(expr: &mut Expr, visit: &mut dyn FnMut(&mut Ident)) {
+  visit_column_exprs(expr, &mut |column_expr| match column_expr {
+    Expr::Identifier(ident) => visit(ident),
+    Expr::CompoundIdentifier(idents) => {
+      if let Some(ident) = idents.last_mut() {
+        visit(ident);
+      }
+    },
+    _ => (),
+  });
+}
+
+// 找到表达式中引用列的每一个 Identifier 或者 CompoundIdentifier（带表名前缀的列）
+fn visit_column_exprs(expr: &mut Expr, visit: &mut dyn FnMut(&mut Expr)) {
+  match expr {
+    Expr::Identifier(_) | Expr::CompoundIdentifier(_) => visit(expr),
+    Expr::Nested(expr)
+    | Expr::IsNull(expr)
+    | Expr::IsNotNull(expr)
+    | Expr::UnaryOp { expr, .. }
+    | Expr::Trim { expr, trim_where: None } => visit_column_exprs(expr, visit),
+    Expr::Trim { expr, trim_where: Some((_, characters)) } => {
+      visit_column_exprs(expr, visit);
+      visit_column_exprs(characters, visit);
+    },
+    Expr::Between { expr, low, high, .. } => {
+      visit_column_exprs(expr, visit);
+      visit_column_exprs(low, visit);
+      visit_column_exprs(high, visit);
+    },
+    Expr::BinaryOp { left, right, .. } => {
+      visit_column_exprs(left, visit);
+      visit_column_exprs(right, visit);
+    },
+    Expr::Function(function) => {
+      for function_arg in function.args.iter_mut() {
+        match function_arg {
+          FunctionArg::Named { arg, .. } | FunctionArg::Unnamed(arg) => visit_column_exprs(arg, visit),
+        }
+      }
+    },
+    _ => (),
+  }
+}
+
+// 检查表达式中引用的列都能在 row_context 的表中找到，找不到或者有歧义时返回的错误和求值时一样
+// 用在 EXPLAIN 这种不会真正对表达式求值的地方
+pub fn check_column_references(expr: &Expr, row_context: &RowContext) -> Result<()> {
+  let mut result = Ok(());
+  visit_column_exprs(&mut expr.clone(), &mut |column_expr| {
+    if result.is_ok() {
+      result = evaluate_expression(column_expr, row_context).map(|_| ());
+    }
+  });
+  result
+}
+
+// 表达式中有没有引用某一列
+pub fn is_column_referenced(expr: &Expr, column_name: &str) -> bool {
+  let mut is_referenced = false;
+  visit_column_idents(&mut expr.clone(), &mut |ident| is_referenced |= ident.value == column_name);
+  is_referenced
+}
+
+// NULL 表示未知，返回 None
+fn get_boolean_value(value: &Value) -> Result<Option<bool>> {
+  match value {
+    Value::Bool(b) => Ok(Some(*b)),
+    Value::Integer(i) => Ok(Some(*i != 0)),
+    Value::Null => Ok(None),
+    _ => Err(NollaDBError::TypeMismatch(
+      format!("Expected a boolean value, but found {} value '{}'", value.get_type_name(), value)
+    )),
+  }
+}
+
+fn evaluate_unary_operation(op: &UnaryOperator, value: Value) -> Result<Value> {
+  match (op, value) {
+    (_, Value::Null) => Ok(Value::Null),
+    (UnaryOperator::Not, value) => match get_boolean_value(&value)? {
+      Some(b) => Ok(Value::Bool(!b)),
+      None => Ok(Value::Null),
+    },
+    (UnaryOperator::Plus, Value::Integer(i)) => Ok(Value::Integer(i)),
+    (UnaryOperator::Plus, Value::Real(r)) => Ok(Value::Real(r)),
+    (UnaryOperator::Plus, Value::Decimal(d)) => Ok(Value::Decimal(d)),
+    (UnaryOperator::Minus, Value::Integer(i)) => i
+      .checked_neg()
+      .map(Value::Integer)
+      .ok_or_else(|| NollaDBError::Internal("Integer overflow".to_string())),
+    (UnaryOperator::Minus, Value::Real(r)) => Ok(Value::Real(-r)),
+    (UnaryOperator::Minus, Value::Decimal(d)) => Ok(Value::Decimal(-d)),
+    (op, value) => Err(NollaDBError::Internal(
+      format!("Can not apply '{}' to {} value '{}'", op, value.get_type_name(), value)
+    )),
+  }
+}
+
+fn evaluate_binary_operation(op: &BinaryOperator, left: Value, right: Value) -> Result<Value> {
+  match op {
+    BinaryOperator::Eq |
+    BinaryOperator::NotEq |
+    BinaryOperator::Lt |
+    BinaryOperator::LtEq |
+    BinaryOperator::Gt |
+    BinaryOperator::GtEq => {
+      let ordering = match left.compare(&right)? {
+        Some(ordering) => ordering,
+        None => return Ok(Value::Null),
+      };
+      let result = match op {
+        BinaryOperator::Eq => ordering == Ordering::Equal,
+        BinaryOperator::NotEq => ordering != Ordering::Equal,
+        BinaryOperator::Lt => ordering == Ordering::Less,
+        BinaryOperator::LtEq => ordering != Ordering::Greater,
+        BinaryOperator::Gt => ordering == Ordering::Greater,
+        _ => ordering != Ordering::Less,
+      };
+      Ok(Value::Bool(result))
+    },
+    BinaryOperator::Plus |
+    BinaryOperator::Minus |
+    BinaryOperator::Multiply |
+    BinaryOperator::Divide |
+    BinaryOperator::Modulo => evaluate_arithmetic_operation(op, left, right),
+    // 有一边是 NULL 的话结果是 NULL，其他类型的值按照打印出来的样子拼接
+    BinaryOperator::StringConcat => match (&left, &right) {
+      (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+      (Value::Blob(_), _) | (_, Value::Blob(_)) => Err(NollaDBError::TypeMismatch(
+        format!("Can not apply '{}' to Blob value", op)
+      )),
+      _ => Ok(Value::Text(format!("{}{}", left, right))),
+    },
+    // -> 和 ->> 在解析的时候被换成了 ^ 和 |
+    BinaryOperator::BitwiseXor => json::evaluate_json_arrow_operation(false, left, right),
+    BinaryOperator::BitwiseOr => json::evaluate_json_arrow_operation(true, left, right),
+    _ => Err(NollaDBError::ToBeImplemented(
+      format!("Operator '{}' will be implemented soon", op)
+    )),
+  }
+}
+
+fn evaluate_arithmetic_operation(op: &BinaryOperator, left: Value, right: Value) -> Result<Value> {
+  // 有一边是 Decimal 的话按照 Decimal 精确计算，Real 先转换成最短的十进制表示，所以 DECIMAL 的 0.1 + 0.2 就是 0.3
+  // Real 转换不了（比如超出了精度）的话按照 Real 计算
+  if let (Some(a), Some(b), true) = (
+    get_decimal_value(&left),
+    get_decimal_value(&right),
+    matches!(left, Value::Decimal(_)) || matches!(right, Value::Decimal(_)),
+  ) {
+    let is_zero = b == Decimal::from(0);
+    let result = match op {
+      BinaryOperator::Plus => a.checked_add(b),
+      BinaryOperator::Minus => a.checked_sub(b),
+      BinaryOperator::Multiply => a.checked_mul(b),
+      BinaryOperator::Divide => if is_zero { return Ok(Value::Null) } else { a.checked_div(b) },
+      _ => if is_zero { return Ok(Value::Null) } else { a.checked_rem(b) },
+    };
+    return result
+      .map(Value::Decimal)
+      .ok_or_else(|| NollaDBError::Internal("Decimal overflow".to_string()));
+  }
+
+  match (left, right) {
+    (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+    (Value::Integer(a), Value::Integer(b)) => {
+      let result = match op {
+        BinaryOperator::Plus => a.checked_add(b),
+        BinaryOperator::Minus => a.checked_sub(b),
+        BinaryOperator::Multiply => a.checked_mul(b),
+        // 除以 0 的结果是 NULL
+        BinaryOperator::Divide => if b == 0 { return Ok(Value::Null) } else { a.checked_div(b) },
+        _ => if b == 0 { return Ok(Value::Null) } else { a.checked_rem(b) },
+      };
+      result
+        .map(Value::Integer)
+        .ok_or_else(|| NollaDBError::Internal("Integer overflow".to_string()))
+    },
+    (
+      left @ (Value::Integer(_) | Value::Real(_) | Value::Decimal(_)),
+      right @ (Value::Integer(_) | Value::Real(_) | Value::Decimal(_)),
+    ) => {
+      let a = get_real_value(&left);
+      let b = get_real_value(&right);
+      let result = match op {
+        BinaryOperator::Plus => a + b,
+        BinaryOperator::Minus => a - b,
+        BinaryOperator::Multiply => a * b,
+        BinaryOperator::Divide => if b == 0.0 { return Ok(Value::Null) } else { a / b },
+        _ => if b == 0.0 { return Ok(Value::Null) } else { a % b },
+      };
+      Ok(Value::Real(result))
+    },
+    (left, right) => Err(NollaDBError::Internal(
+      format!(
+        "Can not apply '{}' to {} value '{}' and {} value '{}'",
+        op, left.get_type_name(), left, right.get_type_name(), right
+      )
+    )),
+  }
+}
+
+fn get_real_value(value: &Value) -> f64 {
+  match value {
+    Value::Integer(i) => *i as f64,
+    Value::Real(r) => *r,
+    Value::Decimal(d) => d.to_real(),
+    _ => 0.0,
+  }
+}
+
+fn get_decimal_value(value: &Value) -> Option<Decimal> {
+  match value {
+    Value::Integer(i) => Some(Decimal::from(*i)),
+    Value::Real(r) => Decimal::from_real(*r),
+    Value::Decimal(d) => Some(*d),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::test_helpers::{parse_expression, create_test_database};
+
+  #[rstest]
+  #[case("1 + 2 * 3", Value::Integer(7))]
+  #[case("-1.5 + 1", Value::Real(-0.5))]
+  #[case("7 / 0", Value::Null)]
+  #[case("2147483647 + 1", Value::Integer(2147483648))]
+  #[case("0.1 + 0.2", Value::Real(0.1 + 0.2))]
+  #[case("X'0aFF'", Value::Blob(vec![0x0a, 0xff]))]
+  #[case("DATE '2024-02-29' < TIMESTAMP '2024-02-29 00:00:01'", Value::Bool(true))]
+  #[case("TIME '12:30' = '12:30:00'", Value::Bool(true))]
+  #[case("DECIMAL '0.1' + 0.2", Value::Decimal("0.3".parse::<Decimal>().unwrap()))]
+  #[case("-DECIMAL '1.50' * 2", Value::Decimal("-3".parse::<Decimal>().unwrap()))]
+  #[case("'a' < 'b'", Value::Bool(true))]
+  #[case("1 = NULL", Value::Null)]
+  #[case("NULL IS NULL", Value::Bool(true))]
+  #[case("NOT (1 > 2)", Value::Bool(true))]
+  #[case("NULL AND false", Value::Bool(false))]
+  #[case("NULL OR true", Value::Bool(true))]
+  #[case("NULL OR false", Value::Null)]
+  #[case("2 BETWEEN 1 AND 2.5", Value::Bool(true))]
+  #[case("'c' NOT BETWEEN 'a' AND 'b'", Value::Bool(true))]
+  #[case("5 BETWEEN NULL AND 4", Value::Bool(false))]
+  #[case("3 BETWEEN NULL AND 4", Value::Null)]
+  #[case("'a' || 1 || 2.5", Value::Text("a12.5".to_string()))]
+  #[case("'a' || NULL", Value::Null)]
+  #[case("'ab' || 'c' = 'abc'", Value::Bool(true))]
+  fn test_evaluate_expression(
+    #[case] input: &str,
+    #[case] expected: Value,
+  ) {
+    let expr = parse_expression(input);
+    assert_eq!(evaluate_expression(&expr, &RowContext::default()), Ok(expected));
+  }
+
+  #[rstest]
+  #[case("id = 2", vec![2])]
+  #[case("id <> 2", vec![1, 3])]
+  #[case("score >= 2 AND active = true", vec![3])]
+  #[case("name = 'xxx' OR test.score < 1.5", vec![1])]
+  #[case("NOT active", vec![2])]
+  #[case("id >= 2 AND NOT (name = 'zzz')", vec![2])]
+  fn test_evaluate_predicate(
+    #[case] input: &str,
+    #[case] expected_row_ids: Vec<i64>,
+  ) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, active BOOLEAN, score REAL);",
+      "INSERT INTO test (name, active, score) VALUES ('xxx', true, 1.0);",
+      "INSERT INTO test (name, active, score) VALUES ('yyy', false, 2.0);",
+      "INSERT INTO test (name, active, score) VALUES ('zzz', true, 3.0);",
+    ]);
+    let table = database.get_table("test".to_string()).unwrap();
+    let expr = parse_expression(input);
+
+    let row_ids = table
+      .get_row_ids()
+      .into_iter()
+      .filter(|row_id| evaluate_predicate(&expr, &RowContext::new(table, *row_id)).unwrap())
+      .collect::<Vec<i64>>();
+    assert_eq!(row_ids, expected_row_ids);
+  }
+
+  #[rstest]
+  #[case("email = 'xxx'")]
+  #[case("other.id = 1")]
+  #[case("name = 1")]
+  #[case("name AND true")]
+  #[case("name || X'00' = 'x'")]
+  fn test_evaluate_predicate_error(#[case] input: &str) {
+    let database = create_test_database(&[
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, active BOOLEAN, score REAL);",
+      "INSERT INTO test (name, active, score) VALUES ('xxx', true, 1.0);",
+      "INSERT INTO test (name, active, score) VALUES ('yyy', false, 2.0);",
+      "INSERT INTO test (name, active, score) VALUES ('zzz', true, 3.0);",
+    ]);
+    let table = database.get_table("test".to_string()).unwrap();
+    let expr = parse_expression(input);
+
+    assert!(evaluate_predicate(&expr, &RowContext::new(table, 1)).is_err());
+  }
+}
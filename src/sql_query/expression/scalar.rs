@@ -0,0 +1,427 @@
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::Range;
+
+use sqlparser::ast::{
+  Function,
+  FunctionArg,
+  TrimWhereField,
+};
+
+use crate::error::{Result, NollaDBError};
+use crate::table::row::value::Value;
+use crate::table::row::decimal::Decimal;
+use crate::sql_query::get_last_insert_row_id;
+use super::{RowContext, evaluate_expression};
+use super::json::{json_extract, json_array_length};
+
+// 内置的标量函数
+struct ScalarFunction {
+  name: &'static str,
+  // 参数个数的范围，max_args 为 None 表示个数不限
+  min_args: usize,
+  max_args: Option<usize>,
+  // 参数个数检查过之后才会调用，参数都已经求好值了
+  evaluate: fn(&[Value]) -> Result<Value>,
+}
+
+// MIN 和 MAX 只有一个参数的时候是聚合函数，所以这里至少两个参数
+// TRIM 会被 sqlparser 解析成 Expr::Trim，见 evaluate_trim
+const SCALAR_FUNCTIONS: [ScalarFunction; 20] = [
+  ScalarFunction { name: "UPPER", min_args: 1, max_args: Some(1), evaluate: upper },
+  ScalarFunction { name: "LOWER", min_args: 1, max_args: Some(1), evaluate: lower },
+  ScalarFunction { name: "LENGTH", min_args: 1, max_args: Some(1), evaluate: length },
+  ScalarFunction { name: "SUBSTR", min_args: 2, max_args: Some(3), evaluate: substr },
+  ScalarFunction { name: "LTRIM", min_args: 1, max_args: Some(2), evaluate: ltrim },
+  ScalarFunction { name: "RTRIM", min_args: 1, max_args: Some(2), evaluate: rtrim },
+  ScalarFunction { name: "REPLACE", min_args: 3, max_args: Some(3), evaluate: replace },
+  ScalarFunction { name: "INSTR", min_args: 2, max_args: Some(2), evaluate: instr },
+  ScalarFunction { name: "ABS", min_args: 1, max_args: Some(1), evaluate: abs },
+  ScalarFunction { name: "ROUND", min_args: 1, max_args: Some(2), evaluate: round },
+  ScalarFunction { name: "MIN", min_args: 2, max_args: None, evaluate: min },
+  ScalarFunction { name: "MAX", min_args: 2, max_args: None, evaluate: max },
+  ScalarFunction { name: "RANDOM", min_args: 0, max_args: Some(0), evaluate: random },
+  ScalarFunction { name: "COALESCE", min_args: 2, max_args: None, evaluate: coalesce },
+  ScalarFunction { name: "IFNULL", min_args: 2, max_args: Some(2), evaluate: coalesce },
+  ScalarFunction { name: "NULLIF", min_args: 2, max_args: Some(2), evaluate: nullif },
+  ScalarFunction { name: "TYPEOF", min_args: 1, max_args: Some(1), evaluate: typeof_value },
+  ScalarFunction { name: "LAST_INSERT_ROWID", min_args: 0, max_args: Some(0), evaluate: last_insert_rowid },
+  ScalarFunction { name: "JSON_EXTRACT", min_args: 2, max_args: None, evaluate: json_extract },
+  ScalarFunction { name: "JSON_ARRAY_LENGTH", min_args: 1, max_args: Some(2), evaluate: json_array_length },
+];
+
+fn get_scalar_function(function: &Function) -> Option<&'static ScalarFunction> {
+  let function_name = function.name.to_string().to_uppercase();
+  SCALAR_FUNCTIONS
+    .iter()
+    .find(|scalar_function| scalar_function.name == function_name)
+}
+
+// 先对每一个参数求值，再检查参数个数，最后调用对应的函数
+pub fn evaluate_scalar_function(function: &Function, row_context: &RowContext) -> Result<Value> {
+  let scalar_function = match get_scalar_function(function) {
+    Some(scalar_function) => scalar_function,
+    None => return Err(NollaDBError::ToBeImplemented(
+      format!("Function '{}' will be implemented soon", function.name)
+    )),
+  };
+  if function.distinct {
+    return Err(NollaDBError::Internal(
+      format!("DISTINCT is not allowed in function {}()", scalar_function.name)
+    ));
+  }
+
+  let values = function
+    .args
+    .iter()
+    .map(|function_arg| match function_arg {
+      FunctionArg::Unnamed(arg) => evaluate_expression(arg, row_context),
+      FunctionArg::Named { .. } => Err(NollaDBError::Internal(
+        format!("Named arguments are not supported in function {}()", scalar_function.name)
+      )),
+    })
+    .collect::<Result<Vec<Value>>>()?;
+  if values.len() < scalar_function.min_args
+    || scalar_function.max_args.is_some_and(|max_args| values.len() > max_args) {
+    return Err(NollaDBError::Internal(
+      format!("Wrong number of arguments to function {}()", scalar_function.name)
+    ));
+  }
+
+  (scalar_function.evaluate)(values.as_slice())
+}
+
+fn get_type_mismatch_error(function_name: &str, value: &Value) -> NollaDBError {
+  NollaDBError::TypeMismatch(
+    format!("Can not apply {}() to {} value '{}'", function_name, value.get_type_name(), value)
+  )
+}
+
+// 字符串函数的参数，NULL 返回 None，其他类型的值按照打印出来的样子转换成 Text
+// Blob 的内容不一定是合法的字符串，所以不能作为参数
+fn get_text_argument(function_name: &str, value: &Value) -> Result<Option<String>> {
+  match value {
+    Value::Null => Ok(None),
+    Value::Text(s) => Ok(Some(s.to_string())),
+    Value::Blob(_) => Err(get_type_mismatch_error(function_name, value)),
+    value => Ok(Some(value.to_string())),
+  }
+}
+
+// 整数参数，NULL 返回 None，Real 和 Decimal 去掉小数部分
+fn get_integer_argument(function_name: &str, value: &Value) -> Result<Option<i64>> {
+  match value {
+    Value::Null => Ok(None),
+    Value::Integer(i) => Ok(Some(*i)),
+    Value::Real(r) => Ok(Some(r.trunc() as i64)),
+    Value::Decimal(d) => Ok(Some(d.to_real().trunc() as i64)),
+    value => Err(get_type_mismatch_error(function_name, value)),
+  }
+}
+
+fn upper(values: &[Value]) -> Result<Value> {
+  Ok(get_text_argument("UPPER", &values[0])?.map_or(Value::Null, |s| Value::Text(s.to_uppercase())))
+}
+
+fn lower(values: &[Value]) -> Result<Value> {
+  Ok(get_text_argument("LOWER", &values[0])?.map_or(Value::Null, |s| Value::Text(s.to_lowercase())))
+}
+
+// Text 返回字符个数，Blob 返回字节数
+fn length(values: &[Value]) -> Result<Value> {
+  match &values[0] {
+    Value::Null => Ok(Value::Null),
+    Value::Blob(bytes) => Ok(Value::Integer(bytes.len() as i64)),
+    value => Ok(Value::Integer(value.to_string().chars().count() as i64)),
+  }
+}
+
+// 和 SQLite 的 substr 一样：start 从 1 开始，负数表示从末尾往前数
+// length 为负数表示取 start 前面的 |length| 个，没有 length 的话一直取到末尾
+fn get_substr_range(len: usize, start: i64, length: Option<i64>) -> Range<usize> {
+  let len = len as i64;
+  let mut begin = start;
+  let mut count = length.map_or(i64::MAX, i64::saturating_abs);
+  if begin < 0 {
+    begin = begin.saturating_add(len);
+    if begin < 0 {
+      count = count.saturating_add(begin).max(0);
+      begin = 0;
+    }
+  } else if begin > 0 {
+    begin -= 1;
+  } else if count > 0 {
+    // start 为 0 的时候，第 0 个字符也算在 length 里面
+    count -= 1;
+  }
+  if length.is_some_and(|length| length < 0) {
+    begin -= count;
+    if begin < 0 {
+      count += begin;
+      begin = 0;
+    }
+  }
+
+  let end = begin.saturating_add(count).min(len);
+  let begin = begin.min(end);
+  begin as usize..end as usize
+}
+
+// substr(X, start[, length])，Blob 按照字节截取
+fn substr(values: &[Value]) -> Result<Value> {
+  let start = get_integer_argument("SUBSTR", &values[1])?;
+  let length = match values.get(2) {
+    Some(value) => match get_integer_argument("SUBSTR", value)? {
+      Some(length) => Some(length),
+      None => return Ok(Value::Null),
+    },
+    None => None,
+  };
+  let start = match start {
+    Some(start) => start,
+    None => return Ok(Value::Null),
+  };
+
+  match &values[0] {
+    Value::Null => Ok(Value::Null),
+    Value::Blob(bytes) => Ok(Value::Blob(bytes[get_substr_range(bytes.len(), start, length)].to_vec())),
+    value => {
+      let chars = value.to_string().chars().collect::<Vec<char>>();
+      Ok(Value::Text(chars[get_substr_range(chars.len(), start, length)].iter().collect()))
+    },
+  }
+}
+
+// 去掉开头和（或者）结尾的所有在 characters 中出现的字符
+fn trim_characters(function_name: &str, value: &Value, characters: &Value, is_leading: bool, is_trailing: bool) -> Result<Value> {
+  let (s, characters) = match (
+    get_text_argument(function_name, value)?,
+    get_text_argument(function_name, characters)?,
+  ) {
+    (Some(s), Some(characters)) => (s, characters),
+    _ => return Ok(Value::Null),
+  };
+
+  let is_trimmed = |c: char| characters.contains(c);
+  let mut trimmed = s.as_str();
+  if is_leading {
+    trimmed = trimmed.trim_start_matches(is_trimmed);
+  }
+  if is_trailing {
+    trimmed = trimmed.trim_end_matches(is_trimmed);
+  }
+  Ok(Value::Text(trimmed.to_string()))
+}
+
+// TRIM([BOTH | LEADING | TRAILING characters FROM] X)，没有写 characters 的话去掉空格
+pub fn evaluate_trim(value: &Value, trim_where: Option<(&TrimWhereField, &Value)>) -> Result<Value> {
+  let space = Value::Text(" ".to_string());
+  let (trim_where_field, characters) = trim_where.unwrap_or((&TrimWhereField::Both, &space));
+  match trim_where_field {
+    TrimWhereField::Both => trim_characters("TRIM", value, characters, true, true),
+    TrimWhereField::Leading => trim_characters("TRIM", value, characters, true, false),
+    TrimWhereField::Trailing => trim_characters("TRIM", value, characters, false, true),
+  }
+}
+
+// ltrim(X[, characters]) 以及 rtrim(X[, characters])
+fn ltrim(values: &[Value]) -> Result<Value> {
+  let space = Value::Text(" ".to_string());
+  trim_characters("LTRIM", &values[0], values.get(1).unwrap_or(&space), true, false)
+}
+
+fn rtrim(values: &[Value]) -> Result<Value> {
+  let space = Value::Text(" ".to_string());
+  trim_characters("RTRIM", &values[0], values.get(1).unwrap_or(&space), false, true)
+}
+
+// replace(X, pattern, replacement)，pattern 为空字符串的话 X 不变
+fn replace(values: &[Value]) -> Result<Value> {
+  let texts = values
+    .iter()
+    .map(|value| get_text_argument("REPLACE", value))
+    .collect::<Result<Option<Vec<String>>>>()?;
+  Ok(match texts.as_deref() {
+    Some([s, pattern, _]) if pattern.is_empty() => Value::Text(s.to_string()),
+    Some([s, pattern, replacement]) => Value::Text(s.replace(pattern.as_str(), replacement)),
+    _ => Value::Null,
+  })
+}
+
+// instr(X, Y)，Y 在 X 中第一次出现的位置（从 1 开始的字符位置），没有出现的话返回 0
+fn instr(values: &[Value]) -> Result<Value> {
+  match (get_text_argument("INSTR", &values[0])?, get_text_argument("INSTR", &values[1])?) {
+    (Some(s), Some(pattern)) => Ok(Value::Integer(
+      s.find(pattern.as_str()).map_or(0, |i| s[..i].chars().count() as i64 + 1)
+    )),
+    _ => Ok(Value::Null),
+  }
+}
+
+fn abs(values: &[Value]) -> Result<Value> {
+  match &values[0] {
+    Value::Null => Ok(Value::Null),
+    Value::Integer(i) => i
+      .checked_abs()
+      .map(Value::Integer)
+      .ok_or_else(|| NollaDBError::Internal("Integer overflow".to_string())),
+    Value::Real(r) => Ok(Value::Real(r.abs())),
+    Value::Decimal(d) if *d < Decimal::from(0) => Ok(Value::Decimal(-*d)),
+    Value::Decimal(d) => Ok(Value::Decimal(*d)),
+    value => Err(get_type_mismatch_error("ABS", value)),
+  }
+}
+
+// round(X[, digits])，保留 digits 位小数，四舍五入（远离 0 的方向），digits 为负数的话当作 0
+// 和 SQLite 一样 Integer 和 Real 的结果都是 Real，Decimal 的结果仍然是 Decimal
+fn round(values: &[Value]) -> Result<Value> {
+  let digits = match values.get(1) {
+    Some(value) => match get_integer_argument("ROUND", value)? {
+      Some(digits) => digits.max(0),
+      None => return Ok(Value::Null),
+    },
+    None => 0,
+  };
+
+  match &values[0] {
+    Value::Null => Ok(Value::Null),
+    Value::Integer(i) => Ok(Value::Real(*i as f64)),
+    // 超过 15 位小数的话 f64 本身的精度已经不够了，直接返回
+    Value::Real(r) if digits > 15 => Ok(Value::Real(*r)),
+    Value::Real(r) => {
+      let factor = 10f64.powi(digits as i32);
+      Ok(Value::Real((r * factor).round() / factor))
+    },
+    Value::Decimal(d) => Ok(Value::Decimal(d.round(digits.min(u32::MAX as i64) as u32))),
+    value => Err(get_type_mismatch_error("ROUND", value)),
+  }
+}
+
+// 标量的 min 和 max，有一个参数是 NULL 的话返回 NULL
+fn get_extreme_value(values: &[Value], ordering: Ordering) -> Result<Value> {
+  let mut extreme_value = &values[0];
+  for value in values {
+    match value.compare(extreme_value)? {
+      Some(value_ordering) if value_ordering == ordering => extreme_value = value,
+      Some(_) => {},
+      None => return Ok(Value::Null),
+    }
+  }
+  Ok(extreme_value.clone())
+}
+
+fn min(values: &[Value]) -> Result<Value> {
+  get_extreme_value(values, Ordering::Less)
+}
+
+fn max(values: &[Value]) -> Result<Value> {
+  get_extreme_value(values, Ordering::Greater)
+}
+
+// 不想为了这一个函数引入随机数的依赖，RandomState 每次创建时的 key 都不一样，哈希出来的值就是一个随机的 i64
+fn random(_: &[Value]) -> Result<Value> {
+  Ok(Value::Integer(RandomState::new().build_hasher().finish() as i64))
+}
+
+// coalesce(X, Y, ...) 以及 ifnull(X, Y)，返回第一个不是 NULL 的参数
+fn coalesce(values: &[Value]) -> Result<Value> {
+  Ok(values.iter().find(|value| !value.is_null()).cloned().unwrap_or(Value::Null))
+}
+
+// nullif(X, Y)，X 和 Y 相等的话返回 NULL，否则返回 X
+// 类型不能比较的两个值肯定不相等
+fn nullif(values: &[Value]) -> Result<Value> {
+  match values[0].compare(&values[1]) {
+    Ok(Some(Ordering::Equal)) => Ok(Value::Null),
+    _ => Ok(values[0].clone()),
+  }
+}
+
+// 类型名的小写，比如 integer、text、null
+fn typeof_value(values: &[Value]) -> Result<Value> {
+  Ok(Value::Text(values[0].get_type_name().to_lowercase()))
+}
+
+// last_insert_rowid()，最近一次 INSERT 成功插入的一行的 row id
+fn last_insert_rowid(_: &[Value]) -> Result<Value> {
+  Ok(Value::Integer(get_last_insert_row_id()))
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::test_helpers::parse_expression;
+
+  fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+  }
+
+  #[rstest]
+  #[case("upper('abc')", text("ABC"))]
+  #[case("LOWER('AbC')", text("abc"))]
+  #[case("upper(NULL)", Value::Null)]
+  #[case("length('héllo')", Value::Integer(5))]
+  #[case("length(X'0A0B')", Value::Integer(2))]
+  #[case("length(12.5)", Value::Integer(4))]
+  #[case("substr('hello', 2, 3)", text("ell"))]
+  #[case("substr('hello', -3)", text("llo"))]
+  #[case("substr('hello', 0, 2)", text("h"))]
+  #[case("substr('hello', 3, -2)", text("he"))]
+  #[case("substr('hello', 10)", text(""))]
+  #[case("substr(X'0A0B0C', 2)", Value::Blob(vec![0x0b, 0x0c]))]
+  #[case("substr('hello', NULL)", Value::Null)]
+  #[case("trim('  a b  ')", text("a b"))]
+  #[case("TRIM(LEADING 'x' FROM 'xxaxx')", text("axx"))]
+  #[case("TRIM(TRAILING 'xy' FROM 'xxayx')", text("xxa"))]
+  #[case("ltrim('  a  ')", text("a  "))]
+  #[case("rtrim('a--', '-')", text("a"))]
+  #[case("replace('a-b-c', '-', '+')", text("a+b+c"))]
+  #[case("replace('abc', '', 'x')", text("abc"))]
+  #[case("instr('héllo', 'l')", Value::Integer(3))]
+  #[case("instr('hello', 'z')", Value::Integer(0))]
+  #[case("abs(-3)", Value::Integer(3))]
+  #[case("abs(-1.5)", Value::Real(1.5))]
+  #[case("abs(DECIMAL '-0.10')", Value::Decimal("0.1".parse::<Decimal>().unwrap()))]
+  #[case("round(2.5)", Value::Real(3.0))]
+  #[case("round(-2.345, 2)", Value::Real(-2.35))]
+  #[case("round(7)", Value::Real(7.0))]
+  #[case("round(DECIMAL '1.005', 2)", Value::Decimal("1.01".parse::<Decimal>().unwrap()))]
+  #[case("min(3, 1.5, 2)", Value::Real(1.5))]
+  #[case("max('a', 'c', 'b')", text("c"))]
+  #[case("max(1, NULL)", Value::Null)]
+  #[case("typeof(random())", text("integer"))]
+  #[case("coalesce(NULL, NULL, 'x', 'y')", text("x"))]
+  #[case("coalesce(NULL, NULL)", Value::Null)]
+  #[case("ifnull(NULL, 0)", Value::Integer(0))]
+  #[case("nullif(1, 1.0)", Value::Null)]
+  #[case("nullif(1, 'a')", Value::Integer(1))]
+  #[case("typeof(NULL)", text("null"))]
+  #[case("typeof(1.5)", text("real"))]
+  #[case("typeof(DATE '2024-01-01')", text("date"))]
+  #[case("upper(substr('hello', 1, 1)) || substr('hello', 2)", text("Hello"))]
+  fn test_evaluate_scalar_function(
+    #[case] input: &str,
+    #[case] expected: Value,
+  ) {
+    assert_eq!(evaluate_expression(&parse_expression(input), &RowContext::default()), Ok(expected));
+  }
+
+  #[rstest]
+  #[case("upper()", NollaDBError::Internal("Wrong number of arguments to function UPPER()".to_string()))]
+  #[case("coalesce(1)", NollaDBError::Internal("Wrong number of arguments to function COALESCE()".to_string()))]
+  #[case("last_insert_rowid(1)", NollaDBError::Internal("Wrong number of arguments to function LAST_INSERT_ROWID()".to_string()))]
+  #[case("abs('x')", NollaDBError::TypeMismatch("Can not apply ABS() to Text value 'x'".to_string()))]
+  #[case("upper(X'00')", NollaDBError::TypeMismatch("Can not apply UPPER() to Blob value 'X'00''".to_string()))]
+  #[case("substr('abc', 'x')", NollaDBError::TypeMismatch("Can not apply SUBSTR() to Text value 'x'".to_string()))]
+  #[case("foo(1)", NollaDBError::ToBeImplemented("Function 'foo' will be implemented soon".to_string()))]
+  fn test_evaluate_scalar_function_error(
+    #[case] input: &str,
+    #[case] expected: NollaDBError,
+  ) {
+    assert_eq!(evaluate_expression(&parse_expression(input), &RowContext::default()), Err(expected));
+  }
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error as DeError;
+use sqlparser::ast::Expr;
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Tokenizer, Token};
+
+use crate::error::{Result, NollaDBError};
+
+// DEFAULT 和 CHECK 的表达式保存到文件里面的时候存的是 SQL 文本，读取的时候重新解析
+// 这样文件的格式不依赖 sqlparser 中 AST 的结构
+// -> 和 ->> 在 AST 中是 BitwiseXor 和 BitwiseOr（见 tokenize_sql_query），输出成文本是 ^ 和 |
+// 所以这里不经过 tokenize_sql_query，直接分词解析就能得到原来的 AST
+pub fn parse_sql_text(sql_text: &str) -> Result<Expr> {
+  let dialect = SQLiteDialect {};
+  let tokens = Tokenizer::new(&dialect, sql_text)
+    .tokenize()
+    .map_err(ParserError::from)?;
+  let mut parser = Parser::new(tokens, &dialect);
+  let expr = parser.parse_expr()?;
+  match parser.peek_token() {
+    Token::EOF => Ok(expr),
+    token => Err(NollaDBError::SQLParseError(ParserError::ParserError(
+      format!("Expected end of expression, found: {}", token)
+    ))),
+  }
+}
+
+fn from_sql_text<'de, D: Deserializer<'de>>(sql_text: String) -> std::result::Result<Expr, D::Error> {
+  parse_sql_text(&sql_text)
+    .map_err(|error| D::Error::custom(format!("Invalid expression '{}': {}", sql_text, error)))
+}
+
+// 用在 Option<Expr> 的字段上：#[serde(with = "sql_text::option")]
+pub mod option {
+  use super::*;
+
+  pub fn serialize<S: Serializer>(expr: &Option<Expr>, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    expr.as_ref().map(|expr| expr.to_string()).serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<Expr>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+      .map(from_sql_text::<D>)
+      .transpose()
+  }
+}
+
+// 用在 Vec<Expr> 的字段上：#[serde(with = "sql_text::vec")]
+pub mod vec {
+  use super::*;
+
+  pub fn serialize<S: Serializer>(exprs: &[Expr], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    exprs.iter().map(|expr| expr.to_string()).collect::<Vec<String>>().serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Vec<Expr>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+      .into_iter()
+      .map(from_sql_text::<D>)
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::test_helpers::parse_expression;
+
+  #[rstest]
+  #[case("1 + 1")]
+  #[case("-2.5")]
+  #[case("'it''s'")]
+  #[case("length(name) > 0 AND score BETWEEN 0 AND 100")]
+  #[case("name IS NOT NULL OR name IN ('a', 'b')")]
+  #[case("CAST(score AS INTEGER) * (2 - 1)")]
+  #[case("CASE WHEN score > 1 THEN 'x' ELSE NULL END")]
+  #[case("DATE '2022-01-01'")]
+  #[case("j -> 'a'")]
+  #[case("length(j ->> '$.a') > 0")]
+  fn test_sql_text_round_trip(#[case] input: &str) {
+    let expr = parse_expression(input);
+
+    assert_eq!(parse_sql_text(&expr.to_string()), Ok(expr));
+  }
+
+  #[test]
+  fn test_parse_sql_text_error() {
+    assert!(parse_sql_text("1 +").is_err());
+    assert!(parse_sql_text("1 1").is_err());
+  }
+}
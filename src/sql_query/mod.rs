@@ -1,15 +1,35 @@
 pub mod query;
+pub mod executor;
+pub mod expression;
+
+use std::cell::Cell;
 
 use sqlparser::parser::{Parser, ParserError};
 use sqlparser::dialect::SQLiteDialect;
-use sqlparser::ast::Statement;
+use sqlparser::tokenizer::{Tokenizer, Token};
+use sqlparser::ast::{Statement, ObjectType};
 
 use crate::error::{Result, NollaDBError};
 use crate::database::Database;
 use crate::table::{Table};
+use crate::table::row::value::Value;
 
 use query::create::{CreateQuery};
+use query::create_index::{CreateIndexQuery};
 use query::insert::{InsertQuery};
+use query::select::{SelectQuery};
+use query::update::{UpdateQuery};
+use query::delete::{DeleteQuery};
+use query::drop::{DropQuery};
+use query::alter::{AlterQuery, AlterOperation};
+use query::explain::{ExplainQuery, remove_query_plan_keywords};
+use executor::{
+  execute_select_query,
+  execute_update_query,
+  execute_delete_query,
+};
+use executor::explain::explain_statement;
+use executor::foreign_key::check_foreign_key_values;
 
 #[derive(Debug, PartialEq)]
 pub enum SQLQuery {
@@ -18,13 +38,16 @@ pub enum SQLQuery {
   Insert(String),
   Update(String),
   Delete(String),
+  Drop(String),
+  Alter(String),
+  Explain(String),
   Unknown(String),
 }
 
 impl SQLQuery {
   pub fn new(command: String) -> SQLQuery {
     let args: Vec<&str> = command.split_whitespace().collect();
-    if args.len() == 0 {
+    if args.is_empty() {
       return SQLQuery::Unknown(command);
     }
     let first_cmd = args[0].to_owned();
@@ -34,22 +57,91 @@ impl SQLQuery {
       "insert" => SQLQuery::Insert(command),
       "update" => SQLQuery::Update(command),
       "delete" => SQLQuery::Delete(command),
+      "drop" => SQLQuery::Drop(command),
+      "alter" => SQLQuery::Alter(command),
+      "explain" => SQLQuery::Explain(command),
       _ => SQLQuery::Unknown(command),
     }
   }
 }
 
+thread_local! {
+  // 最近一次 INSERT 成功插入的一行的 row id，和 SQLite 一样是连接级别的，不属于某一个数据库
+  // REPL 只有一个连接，所以每个线程保存一份就可以了
+  static LAST_INSERT_ROW_ID: Cell<i64> = const { Cell::new(0) };
+}
+
+// last_insert_rowid()，还没有插入过任何行的话是 0
+pub fn get_last_insert_row_id() -> i64 {
+  LAST_INSERT_ROW_ID.with(|last_insert_row_id| last_insert_row_id.get())
+}
+
+fn set_last_insert_row_id(row_id: i64) {
+  LAST_INSERT_ROW_ID.with(|last_insert_row_id| last_insert_row_id.set(row_id));
+}
+
+// sqlparser 0.13 不认识 SQLite 的 JSON 运算符 -> 和 ->>，会把它们拆成 - 和 > 或者 - 和 >>
+// 这里在词法分析之后把它们换成 ^ 和 |，解析成 BitwiseXor 以及 BitwiseOr，求值的时候再当作 JSON 运算符处理
+// 所以它们的优先级和 ^、| 一样比算术运算符低，比如 a -> '$.x' + 1 需要写成 (a -> '$.x') + 1
+// SQL 里面本来的 ^ 和 | 还没有实现，直接报错，避免被当成 JSON 运算符
+pub fn tokenize_sql_query(sql_query: &str) -> Result<Vec<Token>> {
+  let dialect = SQLiteDialect {};
+  let mut tokens = Tokenizer::new(&dialect, sql_query)
+    .tokenize()
+    .map_err(ParserError::from)?
+    .into_iter()
+    .peekable();
+
+  let mut rewritten_tokens: Vec<Token> = vec![];
+  while let Some(token) = tokens.next() {
+    match (&token, tokens.peek()) {
+      (Token::Minus, Some(Token::Gt)) => {
+        tokens.next();
+        rewritten_tokens.push(Token::Caret);
+      },
+      (Token::Minus, Some(Token::ShiftRight)) => {
+        tokens.next();
+        rewritten_tokens.push(Token::Pipe);
+      },
+      (Token::Caret | Token::Pipe, _) => return Err(NollaDBError::ToBeImplemented(
+        format!("Operator '{}' will be implemented soon", token)
+      )),
+      _ => rewritten_tokens.push(token),
+    }
+  }
+
+  Ok(rewritten_tokens)
+}
+
 pub fn get_sql_ast(sql_query: &str) -> Result<Statement> {
   let dialect = SQLiteDialect {};
-  let mut ast =
-    Parser::parse_sql(&dialect, &sql_query)
-      .map_err(NollaDBError::from)?;
+  let tokens = tokenize_sql_query(&remove_query_plan_keywords(sql_query))?;
 
-  if ast.len() == 0 {
+  // 和 Parser::parse_sql 一样，语句之间用分号分隔，忽略空的语句
+  let mut parser = Parser::new(tokens, &dialect);
+  let mut ast: Vec<Statement> = vec![];
+  let mut is_expecting_statement_delimiter = false;
+  loop {
+    while parser.consume_token(&Token::SemiColon) {
+      is_expecting_statement_delimiter = false;
+    }
+    if parser.peek_token() == Token::EOF {
+      break;
+    }
+    if is_expecting_statement_delimiter {
+      return Err(NollaDBError::SQLParseError(ParserError::ParserError(
+        format!("Expected end of statement, found: {}", parser.peek_token())
+      )));
+    }
+    ast.push(parser.parse_statement()?);
+    is_expecting_statement_delimiter = true;
+  }
+
+  if ast.is_empty() {
     return Err(
       NollaDBError::SQLParseError(
         ParserError::ParserError(
-          format!("Expected a correct SQL query statement")
+          "Expected a correct SQL query statement".to_string()
         )
       )
     );
@@ -69,7 +161,9 @@ pub fn get_sql_ast(sql_query: &str) -> Result<Statement> {
     );
   }
 
-  Ok(ast.pop().unwrap())
+  ast
+    .pop()
+    .ok_or_else(|| NollaDBError::Internal("Expected a single query statement".to_string()))
 }
 
 pub fn handle_sql_query(sql_query: &str, database: &mut Database) -> Result<String> {
@@ -94,8 +188,9 @@ pub fn handle_sql_query(sql_query: &str, database: &mut Database) -> Result<Stri
                 ));
               }
 
-              // 创建表
-              let table = Table::new(create_query);
+              // 创建表，外键引用的表和列必须存在
+              let mut table = Table::new(create_query);
+              database.check_foreign_key_definitions(&mut table)?;
               // 打印表 schema
               let _ = table.print_column_of_schema();
               // 把表插入到数据库中
@@ -107,7 +202,33 @@ pub fn handle_sql_query(sql_query: &str, database: &mut Database) -> Result<Stri
           }
         },
         Statement::Query(_) => {
-          message = String::from("SELECT statement done");
+          match SelectQuery::new(&statement) {
+            Ok(select_query) => {
+              // 检查表是否已经被创建，表值函数不是数据库中的表，不需要检查
+              if let Some(select_table) = select_query
+                .tables
+                .iter()
+                .find(|select_table| {
+                  select_table.function_args.is_none()
+                    && !database.has_table(select_table.table_name.to_string())
+                }) {
+                return Err(NollaDBError::TableNotFound(select_table.table_name.to_string()));
+              }
+
+              // 在对应表中执行查询操作，并打印查询出来的结果集
+              match execute_select_query(&select_query, database) {
+                Ok(result_set) => {
+                  let _ = result_set.print_result_set();
+                  message = format!(
+                    "SELECT statement done, {} rows in set",
+                    result_set.rows.len()
+                  );
+                },
+                Err(error) => return Err(error),
+              }
+            },
+            Err(error) => return Err(error),
+          }
         },
         Statement::Insert {
           ..
@@ -122,75 +243,302 @@ pub fn handle_sql_query(sql_query: &str, database: &mut Database) -> Result<Stri
 
               // 检查表是否已经被创建
               if !database.has_table(table_name.to_string()) {
-                return Err(NollaDBError::Internal(
-                  format!(
-                    "Table '{}' does not exist",
-                    table_name
-                  )
-                ));
+                return Err(NollaDBError::TableNotFound(table_name.to_string()));
               }
 
               // 在对应表中执行插入操作
-              let table = database.get_table_mut(table_name.to_string()).unwrap();
+              let table = database.get_table(table_name.to_string())?;
               // 检查要插入的 column name 是否在表中存在
               if !table_column_names
                 .iter()
                 .all(|column_name| table.has_column(column_name.to_string())) {
-                return Err(NollaDBError::Internal(format!(
-                  "Can not insert, because some of the columns do not exist"
-                )));
+                return Err(NollaDBError::Internal(
+                  "Can not insert, because some of the columns do not exist".to_string()
+                ));
               }
 
-              // TODO: 这里有一种情况是 SQL 里面没有指定列名，那么就按照顺序写入
+              // SQL 里面没有指定列名，那么就按照表中列定义的顺序写入
+              let table_column_names = if table_column_names.is_empty() {
+                table
+                  .table_columns
+                  .iter()
+                  .map(|table_column| table_column.column_name.to_string())
+                  .collect::<Vec<String>>()
+              } else {
+                table_column_names
+              };
 
-              for table_column_value in table_column_values {
-                // 1. 检查要插入的 column value 的个数是否和 column name 一致
-                let v_len = table_column_value.len();
-                let n_len = table_column_names.len();
-                if v_len != n_len {
+              // 有一行插入失败的话，撤销这条语句已经插入的所有行，保证多行 INSERT 要么全部成功要么全部失败
+              let most_recent_row_id = database.get_table(table_name.to_string())?.most_recent_row_id;
+              let last_insert_row_id = get_last_insert_row_id();
+              let mut inserted_row_ids: Vec<i64> = vec![];
+              if let Err(error) = insert_rows(
+                database,
+                &table_name,
+                &table_column_names,
+                table_column_values,
+                &mut inserted_row_ids,
+              ) {
+                let table = database.get_table_mut(table_name.to_string())?;
+                for row_id in inserted_row_ids.iter().rev() {
+                  table.delete_row(*row_id);
+                }
+                table.most_recent_row_id = most_recent_row_id;
+                set_last_insert_row_id(last_insert_row_id);
+                return Err(error);
+              }
+
+              // 打印插入完成后的表数据
+              let _ = database.get_table(table_name.to_string())?.print_table_data();
+
+              message = format!(
+                "INSERT statement done, {} rows inserted, last insert rowid {}",
+                inserted_row_ids.len(),
+                get_last_insert_row_id()
+              );
+            },
+            Err(error) => return Err(error),
+          }
+        },
+        Statement::Update {
+          ..
+        } => {
+          match UpdateQuery::new(&statement) {
+            Ok(update_query) => {
+              // 检查表是否已经被创建
+              if !database.has_table(update_query.table_name.to_string()) {
+                return Err(NollaDBError::TableNotFound(update_query.table_name.to_string()));
+              }
+
+              // 在对应表中执行更新操作
+              match execute_update_query(&update_query, database) {
+                Ok(affected_rows) => {
+                  // 打印更新完成后的表数据
+                  let table = database.get_table(update_query.table_name.to_string())?;
+                  let _ = table.print_table_data();
+
+                  message = format!(
+                    "UPDATE statement done, {} rows affected",
+                    affected_rows
+                  );
+                },
+                Err(error) => return Err(error),
+              }
+            },
+            Err(error) => return Err(error),
+          }
+        },
+        Statement::Delete {
+          ..
+        } => {
+          match DeleteQuery::new(&statement) {
+            Ok(delete_query) => {
+              // 检查表是否已经被创建
+              if !database.has_table(delete_query.table_name.to_string()) {
+                return Err(NollaDBError::TableNotFound(delete_query.table_name.to_string()));
+              }
+
+              // 在对应表中执行删除操作
+              match execute_delete_query(&delete_query, database) {
+                Ok(affected_rows) => {
+                  // 打印删除完成后的表数据
+                  let table = database.get_table(delete_query.table_name.to_string())?;
+                  let _ = table.print_table_data();
+
+                  message = format!(
+                    "DELETE statement done, {} rows affected",
+                    affected_rows
+                  );
+                },
+                Err(error) => return Err(error),
+              }
+            },
+            Err(error) => return Err(error),
+          }
+        },
+        Statement::Drop {
+          ..
+        } => {
+          match DropQuery::new(&statement) {
+            Ok(DropQuery { object_type: ObjectType::Index, names, if_exists }) => {
+              // 先检查所有的索引都存在，避免只删除了一部分索引
+              let mut index_table_names: Vec<(String, String)> = vec![];
+              for index_name in names {
+                match database.get_index_table_name(&index_name) {
+                  Some(table_name) => index_table_names.push((index_name, table_name)),
+                  None if if_exists => continue,
+                  None => return Err(NollaDBError::Internal(
+                    format!("Index {} not found", index_name)
+                  )),
+                }
+              }
+
+              // 被外键引用的 UNIQUE 约束不能删除
+              for (index_name, table_name) in &index_table_names {
+                let column_name = database.get_table(table_name.to_string())?.indexes[index_name].to_string();
+                if let Some((child_table_name, foreign_key)) = database
+                  .get_referencing_foreign_keys(table_name)
+                  .into_iter()
+                  .find(|(_, foreign_key)| foreign_key.referred_column_names == [column_name.to_string()]) {
                   return Err(NollaDBError::Internal(
                     format!(
-                      "{} values for {} columns",
-                      v_len,
-                      n_len
+                      "Can not drop index {}, because it is referenced by {} in table {}",
+                      index_name,
+                      foreign_key,
+                      child_table_name
                     )
                   ));
                 }
+              }
+
+              for (index_name, table_name) in &index_table_names {
+                database.get_table_mut(table_name.to_string())?.drop_index(index_name)?;
+              }
 
-                // 2. 检查唯一约束
-                if let Err(error) =
-                  table.check_unique_constraint(&table_column_names, &table_column_value) {
+              message = format!(
+                "DROP INDEX statement done, {} indexes dropped",
+                index_table_names.len()
+              );
+            },
+            Ok(drop_query) => {
+              // 先检查所有的表都存在，避免只删除了一部分表
+              if !drop_query.if_exists {
+                if let Some(table_name) = drop_query
+                  .names
+                  .iter()
+                  .find(|table_name| !database.has_table(table_name.to_string())) {
+                  return Err(NollaDBError::TableNotFound(table_name.to_string()));
+                }
+              }
+
+              // 被别的表的外键引用的表不能删除，除非引用它的表也一起删除
+              for table_name in &drop_query.names {
+                if let Some((child_table_name, foreign_key)) = database
+                  .get_referencing_foreign_keys(table_name)
+                  .into_iter()
+                  .find(|(child_table_name, _)| !drop_query.names.contains(child_table_name)) {
                   return Err(NollaDBError::Internal(
                     format!(
-                      "Unique key constraint violation: {}",
-                      error
+                      "Can not drop table {}, because it is referenced by {} in table {}",
+                      table_name,
+                      foreign_key,
+                      child_table_name
                     )
                   ));
                 }
+              }
+
+              // 从数据库中删除表，IF EXISTS 的情况下不存在的表直接跳过
+              let dropped_tables = drop_query
+                .names
+                .iter()
+                .filter(|table_name| database.drop_table(table_name.to_string()).is_ok())
+                .count();
+
+              message = format!(
+                "DROP TABLE statement done, {} tables dropped",
+                dropped_tables
+              );
+            },
+            Err(error) => return Err(error),
+          }
+        },
+        Statement::CreateIndex {
+          ..
+        } => {
+          match CreateIndexQuery::new(&statement) {
+            Ok(create_index_query) => {
+              let CreateIndexQuery {
+                index_name,
+                table_name,
+                column_name,
+                is_unique,
+                if_not_exists,
+              } = create_index_query;
 
-                // 3. 以上 2 点检查完毕，说明没有唯一约束，可以插入
-                table.insert_row(&table_column_names, &table_column_value);
+              // 索引名在整个数据库中不能重复
+              if database.get_index_table_name(&index_name).is_some() {
+                if if_not_exists {
+                  return Ok(String::from("CREATE INDEX statement done"));
+                }
+                return Err(NollaDBError::Internal(
+                  format!(
+                    "Can not create index, because index '{}' already exists",
+                    index_name
+                  )
+                ));
               }
 
-              // 打印插入完成后的表数据
-              let _ = table.print_table_data();
+              // 建好索引并打印表 schema
+              let table = database.get_table_mut(table_name)?;
+              table.create_index(&index_name, &column_name, is_unique)?;
+              let _ = table.print_column_of_schema();
 
-              message = String::from("INSERT statement done");
+              message = String::from("CREATE INDEX statement done");
             },
             Err(error) => return Err(error),
           }
         },
-        Statement::Update {
+        Statement::AlterTable {
           ..
         } => {
-          // TODO: 在表中更新
-          message = String::from("UPDATE statement done");
+          match AlterQuery::new(&statement) {
+            Ok(alter_query) => {
+              let AlterQuery { table_name, operation } = alter_query;
+              // 被别的表的外键引用的列不能删除
+              if let AlterOperation::DropColumn { column_name, .. } = &operation {
+                if let Some((child_table_name, foreign_key)) = database
+                  .get_referencing_foreign_keys(&table_name)
+                  .into_iter()
+                  .find(|(_, foreign_key)| foreign_key.referred_column_names.contains(column_name)) {
+                  return Err(NollaDBError::Internal(
+                    format!(
+                      "Can not drop column {}, because it is referenced by {} in table {}",
+                      column_name,
+                      foreign_key,
+                      child_table_name
+                    )
+                  ));
+                }
+              }
+              let table = database.get_table_mut(table_name.to_string())?;
+
+              // 在对应表中执行修改表结构的操作
+              match operation {
+                AlterOperation::AddColumn { table_metadata_column, default_value } =>
+                  table.add_column(&table_metadata_column, &default_value)?,
+                AlterOperation::DropColumn { column_name, if_exists } => {
+                  if !if_exists || table.has_column(column_name.to_string()) {
+                    table.drop_column(&column_name)?;
+                  }
+                },
+                AlterOperation::RenameColumn { old_column_name, new_column_name } =>
+                  database.rename_column(&table_name, &old_column_name, &new_column_name)?,
+                AlterOperation::RenameTable(new_table_name) =>
+                  database.rename_table(table_name, new_table_name)?,
+              }
+
+              message = String::from("ALTER TABLE statement done");
+            },
+            Err(error) => return Err(error),
+          }
         },
-        Statement::Delete {
+        Statement::Explain {
           ..
         } => {
-          // TODO: 在表中删除
-          message = String::from("DELETE statement done");
+          match ExplainQuery::new(&statement) {
+            Ok(explain_query) => {
+              // 只生成查询计划并打印出来，不会真正执行
+              let query_plan = explain_statement(&explain_query.statement, database)?;
+              let _ = query_plan.print_query_plan();
+
+              message = format!(
+                "EXPLAIN statement done, {} plan nodes",
+                query_plan.nodes.len()
+              );
+            },
+            Err(error) => return Err(error),
+          }
         },
         _ => {
           return Err(
@@ -208,31 +556,111 @@ pub fn handle_sql_query(sql_query: &str, database: &mut Database) -> Result<Stri
   Ok(message)
 }
 
+// 逐行插入，插入成功的 row id 放到 inserted_row_ids 里面，出错的时候由调用者撤销
+fn insert_rows(
+  database: &mut Database,
+  table_name: &str,
+  table_column_names: &[String],
+  table_column_values: Vec<Vec<Value>>,
+  inserted_row_ids: &mut Vec<i64>,
+) -> Result<()> {
+  for table_column_value in table_column_values {
+    // 1. 检查要插入的 column value 的个数是否和 column name 一致
+    let v_len = table_column_value.len();
+    let n_len = table_column_names.len();
+    if v_len != n_len {
+      return Err(NollaDBError::Internal(
+        format!(
+          "{} values for {} columns",
+          v_len,
+          n_len
+        )
+      ));
+    }
+
+    // 2. 检查唯一约束
+    let table = database.get_table(table_name.to_string())?;
+    table.check_unique_constraint(table_column_names, &table_column_value)?;
+
+    // 3. 检查外键约束，外键上的值必须在父表中存在
+    check_foreign_key_values(database, table_name, |column_name| {
+      match table_column_names.iter().position(|name| name == column_name) {
+        Some(position) => table.cast_value(column_name, &table_column_value[position]),
+        None => Ok(Value::Null),
+      }
+    })?;
+
+    // 4. 以上 3 点检查完毕就可以插入，插入时还会检查类型以及 NOT NULL 约束
+    let row_id = database
+      .get_table_mut(table_name.to_string())?
+      .insert_row(table_column_names, &table_column_value)?;
+    set_last_insert_row_id(row_id);
+    inserted_row_ids.push(row_id);
+  }
+
+  Ok(())
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
   use std::result::Result;
   use rstest::rstest;
   use pretty_assertions::{assert_eq};
+  use crate::table::row::value::Value;
+  use crate::error::ConstraintKind;
 
   #[rstest]
-  #[case("SELECT * FROM test;", "SELECT statement done")]
-  #[case("DELETE FROM test WHERE id=1;", "DELETE statement done")]
-  #[case("UPDATE test SET name='xxx' WHERE id=1;", "UPDATE statement done")]
-  fn test_handle_query_statement_sql(
-    #[case] input: &str,
+  #[case("DELETE FROM test WHERE id = 1;", "DELETE statement done, 1 rows affected")]
+  #[case("DELETE FROM test WHERE id = 2;", "DELETE statement done, 0 rows affected")]
+  fn test_handle_delete_sql(
+    #[case] delete_query: &str,
     #[case] expected: &str,
   ) {
     let mut database = Database::new("testdb".to_string());
-    let _ = match handle_sql_query(input, &mut database) {
+    handle_sql_query("CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);", &mut database).unwrap();
+    handle_sql_query("INSERT INTO test (name) VALUES ('xxx');", &mut database).unwrap();
+
+    match handle_sql_query(delete_query, &mut database) {
       Ok(response) => assert_eq!(response, expected),
-      Err(error) => {
-        eprintln!("Error: {}", error);
-        assert!(false)
-      }
+      Err(error) => panic!("Error: {}", error),
     };
   }
 
+  #[rstest]
+  #[case("DELETE FROM test WHERE id = 1;")]
+  fn test_handle_delete_sql_without_table(#[case] input: &str) {
+    let mut database = Database::new("testdb".to_string());
+    assert!(handle_sql_query(input, &mut database).is_err());
+  }
+
+  #[test]
+  fn test_handle_insert_sql_without_column_names() {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER);",
+      "INSERT INTO test VALUES (5, 'xxx', 1), (NULL, 'yyy', 2);",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+    let table = database.get_table("test".to_string()).unwrap();
+
+    assert_eq!(table.get_row_ids(), vec![5, 6]);
+    assert_eq!(table.get_value("name", &5), Ok(Value::Text("xxx".to_string())));
+    assert_eq!(table.get_value("score", &6), Ok(Value::Integer(2)));
+  }
+
+  #[rstest]
+  #[case("INSERT INTO test VALUES (1);")]
+  #[case("INSERT INTO test VALUES (1, 'xxx', 2);")]
+  #[case("INSERT INTO test (email) VALUES ('xxx');")]
+  fn test_handle_insert_sql_error(#[case] insert_query: &str) {
+    let mut database = Database::new("testdb".to_string());
+    handle_sql_query("CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);", &mut database).unwrap();
+
+    assert!(handle_sql_query(insert_query, &mut database).is_err());
+  }
+
   #[rstest]
   #[case(
     "testdb",
@@ -241,7 +669,7 @@ mod tests {
       name TEXT
     );",
     "INSERT INTO test (name) Values ('xxx');",
-    "INSERT statement done",
+    "INSERT statement done, 1 rows inserted, last insert rowid 1",
   )]
   #[case(
     "testdb",
@@ -249,7 +677,16 @@ mod tests {
       name TEXT
     );",
     "INSERT INTO test (name) Values ('xxx');",
-    "INSERT statement done",
+    "INSERT statement done, 1 rows inserted, last insert rowid 1",
+  )]
+  #[case(
+    "testdb",
+    "CREATE TABLE test (
+      id INTEGER PRIMARY KEY,
+      name TEXT
+    );",
+    "INSERT INTO test VALUES (1, 'xxx'), (NULL, 'yyy');",
+    "INSERT statement done, 2 rows inserted, last insert rowid 2",
   )]
   fn test_handle_insert_sql(
     #[case] database_name: &str,
@@ -257,20 +694,639 @@ mod tests {
     #[case] insert_query: &str,
     #[case] expected: &str,
   ) {
-    let _ = match
+    match
       insert_table_into_database_and_insert_data_into_table(
         database_name,
         query,
         insert_query,
       ) {
         Ok(response) => assert_eq!(response, expected),
-        Err(error) => {
-          eprintln!("Error: {}", error);
-          assert!(false)
-        },
+        Err(error) => panic!("Error: {}", error),
     };
   }
 
+  #[rstest]
+  #[case(
+    "testdb",
+    "CREATE TABLE test (
+      id INTEGER PRIMARY KEY,
+      name TEXT
+    );",
+    "INSERT INTO test (name) Values ('xxx');",
+    "SELECT * FROM test;",
+    "SELECT statement done, 1 rows in set",
+  )]
+  #[case(
+    "testdb",
+    "CREATE TABLE test (
+      id INTEGER PRIMARY KEY,
+      name TEXT
+    );",
+    "INSERT INTO test (name) Values ('xxx');",
+    "SELECT id, name AS n FROM test;",
+    "SELECT statement done, 1 rows in set",
+  )]
+  fn test_handle_select_sql(
+    #[case] database_name: &str,
+    #[case] query: &str,
+    #[case] insert_query: &str,
+    #[case] select_query: &str,
+    #[case] expected: &str,
+  ) {
+    let mut database = Database::new(database_name.to_string());
+    handle_sql_query(query, &mut database).unwrap();
+    handle_sql_query(insert_query, &mut database).unwrap();
+
+    match handle_sql_query(select_query, &mut database) {
+      Ok(response) => assert_eq!(response, expected),
+      Err(error) => panic!("Error: {}", error),
+    };
+  }
+
+  #[rstest]
+  #[case("SELECT * FROM json_each('[1, \"two\"]');", "SELECT statement done, 2 rows in set")]
+  #[case("SELECT test.name, j.value FROM test, json_each(test.tags) AS j;", "SELECT statement done, 3 rows in set")]
+  fn test_handle_select_table_function_sql(
+    #[case] select_query: &str,
+    #[case] expected: &str,
+  ) {
+    let mut database = Database::new("testdb".to_string());
+    handle_sql_query("CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, tags JSON);", &mut database).unwrap();
+    handle_sql_query("INSERT INTO test (name, tags) VALUES ('a', '[1, 2]'), ('b', '[3]');", &mut database).unwrap();
+
+    match handle_sql_query(select_query, &mut database) {
+      Ok(response) => assert_eq!(response, expected),
+      Err(error) => panic!("Error: {}", error),
+    };
+  }
+
+  #[rstest]
+  #[case("SELECT * FROM test;")]
+  #[case("SELECT * FROM test, json_each('[1]');")]
+  fn test_handle_select_sql_without_table(#[case] input: &str) {
+    let mut database = Database::new("testdb".to_string());
+    assert!(handle_sql_query(input, &mut database).is_err());
+  }
+
+  #[rstest]
+  #[case("UPDATE test SET name = 'yyy' WHERE id = 1;", "UPDATE statement done, 1 rows affected")]
+  #[case("UPDATE test SET name = 'yyy' WHERE id = 2;", "UPDATE statement done, 0 rows affected")]
+  fn test_handle_update_sql(
+    #[case] update_query: &str,
+    #[case] expected: &str,
+  ) {
+    let mut database = Database::new("testdb".to_string());
+    handle_sql_query("CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);", &mut database).unwrap();
+    handle_sql_query("INSERT INTO test (name) VALUES ('xxx');", &mut database).unwrap();
+
+    match handle_sql_query(update_query, &mut database) {
+      Ok(response) => assert_eq!(response, expected),
+      Err(error) => panic!("Error: {}", error),
+    };
+  }
+
+  #[rstest]
+  #[case("UPDATE test SET name = 'xxx' WHERE id = 1;")]
+  fn test_handle_update_sql_without_table(#[case] input: &str) {
+    let mut database = Database::new("testdb".to_string());
+    assert!(handle_sql_query(input, &mut database).is_err());
+  }
+
+  #[rstest]
+  #[case(
+    "INSERT INTO test (id, name) VALUES ('abc', 'xxx');",
+    NollaDBError::TypeMismatch("Can not store Text value 'abc' into Integer column".to_string())
+  )]
+  #[case(
+    "INSERT INTO test (id, name, birthday) VALUES (2, 'yyy', '2023-02-29');",
+    NollaDBError::TypeMismatch("Can not store Text value '2023-02-29' into Date column".to_string())
+  )]
+  #[case(
+    "INSERT INTO test (id, name, profile) VALUES (2, 'yyy', '{\"a\": 1');",
+    NollaDBError::TypeMismatch("Can not store Text value '{\"a\": 1' into Json column".to_string())
+  )]
+  #[case(
+    "SELECT 1 ^ 2;",
+    NollaDBError::ToBeImplemented("Operator '^' will be implemented soon".to_string())
+  )]
+  #[case(
+    "INSERT INTO test (id, name) VALUES (1, 'yyy');",
+    NollaDBError::ConstraintViolation { kind: ConstraintKind::PrimaryKey, column: "test.id".to_string() }
+  )]
+  #[case(
+    "INSERT INTO test (id) VALUES (2);",
+    NollaDBError::NotNullViolation("test.name".to_string())
+  )]
+  #[case(
+    "INSERT INTO foo (id) VALUES (2);",
+    NollaDBError::TableNotFound("foo".to_string())
+  )]
+  #[case(
+    "SELECT * FROM foo;",
+    NollaDBError::TableNotFound("foo".to_string())
+  )]
+  fn test_handle_sql_typed_error(
+    #[case] input: &str,
+    #[case] expected: NollaDBError,
+  ) {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL, birthday DATE, profile JSON);",
+      "INSERT INTO test (id, name) VALUES (1, 'xxx');",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+
+    assert_eq!(handle_sql_query(input, &mut database), Err(expected));
+    assert_eq!(database.get_table("test".to_string()).unwrap().get_row_ids(), vec![1]);
+  }
+
+  #[rstest]
+  #[case("DROP TABLE test;", Ok("DROP TABLE statement done, 1 tables dropped".to_string()), vec!["other"])]
+  #[case("DROP TABLE IF EXISTS test, foo;", Ok("DROP TABLE statement done, 1 tables dropped".to_string()), vec!["other"])]
+  #[case("DROP TABLE IF EXISTS foo;", Ok("DROP TABLE statement done, 0 tables dropped".to_string()), vec!["other", "test"])]
+  #[case("DROP TABLE test, foo;", Err(NollaDBError::TableNotFound("foo".to_string())), vec!["other", "test"])]
+  fn test_handle_drop_sql(
+    #[case] input: &str,
+    #[case] expected: Result<String, NollaDBError>,
+    #[case] expected_table_names: Vec<&str>,
+  ) {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "CREATE TABLE other (id INTEGER PRIMARY KEY);",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+
+    assert_eq!(handle_sql_query(input, &mut database), expected);
+    let mut table_names = database.tables.keys().cloned().collect::<Vec<String>>();
+    table_names.sort();
+    assert_eq!(table_names, expected_table_names);
+  }
+
+  #[test]
+  fn test_handle_drop_sql_and_create_again() {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "INSERT INTO test (name) VALUES ('xxx');",
+      "DROP TABLE test;",
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, score INTEGER);",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+
+    let table = database.get_table("test".to_string()).unwrap();
+    assert!(table.get_row_ids().is_empty());
+    assert!(!table.has_column("name".to_string()));
+  }
+
+  #[test]
+  fn test_handle_alter_sql() {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "INSERT INTO test (name) VALUES ('xxx'), ('yyy');",
+      "ALTER TABLE test ADD COLUMN score INTEGER DEFAULT 10;",
+      "ALTER TABLE test ADD COLUMN active BOOLEAN;",
+      "ALTER TABLE test RENAME COLUMN name TO nickname;",
+      "ALTER TABLE test DROP COLUMN active;",
+      "ALTER TABLE test DROP COLUMN IF EXISTS foo;",
+      "ALTER TABLE test RENAME TO users;",
+      "INSERT INTO users (nickname) VALUES ('zzz');",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+
+    assert!(!database.has_table("test".to_string()));
+    let table = database.get_table("users".to_string()).unwrap();
+    assert_eq!(table.table_name, "users");
+    assert_eq!(
+      table.table_columns.iter().map(|table_column| table_column.column_name.as_str()).collect::<Vec<&str>>(),
+      vec!["id", "nickname", "score"]
+    );
+    assert_eq!(
+      table.get_column_values("score", None),
+      Ok(vec![Value::Integer(10), Value::Integer(10), Value::Integer(10)])
+    );
+    assert_eq!(table.get_value("nickname", &3), Ok(Value::Text("zzz".to_string())));
+  }
+
+  #[rstest]
+  #[case("ALTER TABLE test ADD COLUMN name TEXT;")]
+  #[case("ALTER TABLE test ADD COLUMN score INTEGER NOT NULL;")]
+  #[case("ALTER TABLE test ADD COLUMN score INTEGER DEFAULT 'abc';")]
+  #[case("ALTER TABLE test ADD COLUMN uid INTEGER PRIMARY KEY;")]
+  #[case("ALTER TABLE test DROP COLUMN id;")]
+  #[case("ALTER TABLE test DROP COLUMN foo;")]
+  #[case("ALTER TABLE test RENAME COLUMN name TO id;")]
+  #[case("ALTER TABLE test RENAME TO other;")]
+  #[case("ALTER TABLE foo RENAME TO bar;")]
+  fn test_handle_alter_sql_error(#[case] input: &str) {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "CREATE TABLE other (id INTEGER PRIMARY KEY);",
+      "INSERT INTO test (name) VALUES ('xxx');",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+
+    assert!(handle_sql_query(input, &mut database).is_err());
+    let table = database.get_table("test".to_string()).unwrap();
+    assert_eq!(table.table_columns.len(), 2);
+    assert_eq!(table.get_value("name", &1), Ok(Value::Text("xxx".to_string())));
+  }
+
+  #[test]
+  fn test_handle_create_and_drop_index_sql() {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "INSERT INTO test (name) VALUES ('xxx'), ('yyy');",
+      "CREATE UNIQUE INDEX idx_name ON test (name);",
+      "CREATE UNIQUE INDEX IF NOT EXISTS idx_name ON test (name);",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+
+    // UNIQUE 索引会在 INSERT 和 UPDATE 的时候检查唯一性约束
+    let unique_error = Err(NollaDBError::ConstraintViolation {
+      kind: ConstraintKind::Unique,
+      column: "test.name".to_string(),
+    });
+    assert_eq!(handle_sql_query("INSERT INTO test (name) VALUES ('xxx');", &mut database), unique_error);
+    assert_eq!(handle_sql_query("UPDATE test SET name = 'xxx' WHERE id = 2;", &mut database), unique_error);
+    assert!(handle_sql_query("CREATE INDEX idx_name ON test (id);", &mut database).is_err());
+
+    // UPDATE 和 DELETE 的时候同步更新索引
+    handle_sql_query("UPDATE test SET name = 'zzz' WHERE id = 2;", &mut database).unwrap();
+    handle_sql_query("DELETE FROM test WHERE id = 1;", &mut database).unwrap();
+    handle_sql_query("INSERT INTO test (name) VALUES ('xxx');", &mut database).unwrap();
+    let index = &database.get_table("test".to_string()).unwrap().get_column("name".to_string()).unwrap().index;
+    assert_eq!(index.get_ordered_row_ids(), vec![3, 2]);
+
+    assert_eq!(
+      handle_sql_query("DROP INDEX idx_name;", &mut database),
+      Ok("DROP INDEX statement done, 1 indexes dropped".to_string())
+    );
+    assert!(handle_sql_query("DROP INDEX idx_name;", &mut database).is_err());
+    assert!(handle_sql_query("DROP INDEX IF EXISTS idx_name;", &mut database).is_ok());
+    handle_sql_query("INSERT INTO test (name) VALUES ('xxx');", &mut database).unwrap();
+  }
+
+  #[rstest]
+  #[case("EXPLAIN SELECT * FROM test WHERE id = 1;", Ok("EXPLAIN statement done, 2 plan nodes"))]
+  #[case("EXPLAIN QUERY PLAN SELECT * FROM test ORDER BY id LIMIT 1;", Ok("EXPLAIN statement done, 2 plan nodes"))]
+  #[case("explain query plan DELETE FROM test;", Ok("EXPLAIN statement done, 2 plan nodes"))]
+  #[case("EXPLAIN SELECT * FROM foo;", Err(NollaDBError::TableNotFound("foo".to_string())))]
+  fn test_handle_explain_sql(
+    #[case] input: &str,
+    #[case] expected: Result<&str, NollaDBError>,
+  ) {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT);",
+      "INSERT INTO test (name) VALUES ('xxx');",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+
+    assert_eq!(handle_sql_query(input, &mut database), expected.map(|message| message.to_string()));
+    // EXPLAIN 不会真正执行语句
+    assert_eq!(database.get_table("test".to_string()).unwrap().get_row_ids(), vec![1]);
+  }
+
+  #[test]
+  fn test_handle_composite_key_sql() {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE enrollments (student_id INTEGER, course TEXT, seat INTEGER, PRIMARY KEY (student_id, course), UNIQUE (course, seat));",
+      "INSERT INTO enrollments VALUES (1, 'math', 1), (1, 'art', 1), (2, 'math', 2);",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+
+    assert_eq!(
+      handle_sql_query("INSERT INTO enrollments VALUES (1, 'math', 3);", &mut database),
+      Err(NollaDBError::ConstraintViolation {
+        kind: ConstraintKind::PrimaryKey,
+        column: "enrollments.student_id, enrollments.course".to_string(),
+      })
+    );
+    assert_eq!(
+      handle_sql_query("UPDATE enrollments SET seat = 1 WHERE student_id = 2;", &mut database),
+      Err(NollaDBError::ConstraintViolation {
+        kind: ConstraintKind::Unique,
+        column: "enrollments.course, enrollments.seat".to_string(),
+      })
+    );
+
+    // 删掉之后同样的 key 又可以插入了
+    handle_sql_query("DELETE FROM enrollments WHERE course = 'math' AND student_id = 1;", &mut database).unwrap();
+    handle_sql_query("INSERT INTO enrollments VALUES (1, 'math', 1);", &mut database).unwrap();
+    assert_eq!(database.get_table("enrollments".to_string()).unwrap().get_row_ids(), vec![2, 3, 4]);
+  }
+
+  #[test]
+  fn test_handle_foreign_key_sql() {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT UNIQUE);",
+      "CREATE TABLE orders (id INTEGER PRIMARY KEY, user_id INTEGER REFERENCES users ON DELETE CASCADE ON UPDATE CASCADE);",
+      "CREATE TABLE reviews (id INTEGER PRIMARY KEY, email TEXT, FOREIGN KEY (email) REFERENCES users (email) ON DELETE SET NULL);",
+      "CREATE TABLE items (id INTEGER PRIMARY KEY, order_id INTEGER REFERENCES orders (id));",
+      "INSERT INTO users VALUES (1, 'a@x.com'), (2, 'b@x.com');",
+      "INSERT INTO orders VALUES (1, 1), (2, 1), (3, 2), (4, NULL);",
+      "INSERT INTO reviews VALUES (1, 'a@x.com'), (2, 'b@x.com');",
+      "INSERT INTO items VALUES (1, 3);",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+    let get_column_values = |database: &Database, table_name: &str, column_name: &str| {
+      database.get_table(table_name.to_string()).unwrap().get_column_values(column_name, None).unwrap()
+    };
+
+    // 引用的行必须存在
+    let orders_error = Err(NollaDBError::ConstraintViolation {
+      kind: ConstraintKind::ForeignKey,
+      column: "orders.user_id".to_string(),
+    });
+    assert_eq!(handle_sql_query("INSERT INTO orders VALUES (5, 3);", &mut database), orders_error);
+    assert_eq!(handle_sql_query("UPDATE orders SET user_id = 3 WHERE id = 1;", &mut database), orders_error);
+    assert!(handle_sql_query("CREATE TABLE test (a INTEGER REFERENCES users (id, email));", &mut database).is_err());
+    assert!(handle_sql_query("CREATE TABLE test (a INTEGER REFERENCES orders (user_id));", &mut database).is_err());
+    assert!(handle_sql_query("CREATE TABLE test (a INTEGER REFERENCES unknown);", &mut database).is_err());
+
+    // 被引用的表和列不能删除
+    assert!(handle_sql_query("DROP TABLE users;", &mut database).is_err());
+    assert!(handle_sql_query("ALTER TABLE users DROP COLUMN email;", &mut database).is_err());
+
+    // 默认是 RESTRICT
+    let items_error = Err(NollaDBError::ConstraintViolation {
+      kind: ConstraintKind::ForeignKey,
+      column: "items.order_id".to_string(),
+    });
+    assert_eq!(handle_sql_query("DELETE FROM orders WHERE id = 3;", &mut database), items_error);
+    // CASCADE 到 orders 之后，orders 中 id = 3 的行被 items 引用，所以整条语句都不会执行
+    assert_eq!(handle_sql_query("DELETE FROM users WHERE id = 2;", &mut database), items_error);
+    assert_eq!(get_column_values(&database, "users", "id"), vec![Value::Integer(1), Value::Integer(2)]);
+    assert_eq!(get_column_values(&database, "orders", "id").len(), 4);
+
+    // ON UPDATE CASCADE
+    handle_sql_query("UPDATE users SET id = 10 WHERE id = 1;", &mut database).unwrap();
+    assert_eq!(
+      get_column_values(&database, "orders", "user_id"),
+      vec![Value::Integer(10), Value::Integer(10), Value::Integer(2), Value::Null]
+    );
+
+    // ON DELETE CASCADE 以及 ON DELETE SET NULL
+    handle_sql_query("DELETE FROM users WHERE id = 10;", &mut database).unwrap();
+    assert_eq!(
+      get_column_values(&database, "orders", "id"),
+      vec![Value::Integer(3), Value::Integer(4)]
+    );
+    assert_eq!(
+      get_column_values(&database, "reviews", "email"),
+      vec![Value::Null, Value::Text("b@x.com".to_string())]
+    );
+
+    // 改名之后外键跟着改
+    handle_sql_query("ALTER TABLE users RENAME TO customers;", &mut database).unwrap();
+    handle_sql_query("ALTER TABLE customers RENAME COLUMN email TO mail;", &mut database).unwrap();
+    assert_eq!(
+      database.get_table("reviews".to_string()).unwrap().foreign_keys[0].to_string(),
+      "FOREIGN KEY (email) REFERENCES customers (mail) ON DELETE SET NULL ON UPDATE RESTRICT"
+    );
+    assert_eq!(
+      handle_sql_query("UPDATE customers SET mail = 'c@x.com';", &mut database),
+      Err(NollaDBError::ConstraintViolation {
+        kind: ConstraintKind::ForeignKey,
+        column: "reviews.email".to_string(),
+      })
+    );
+
+    // 引用它的表一起删除的话可以删除
+    handle_sql_query("DROP TABLE items, orders, reviews, customers;", &mut database).unwrap();
+  }
+
+  #[test]
+  fn test_handle_self_referencing_foreign_key_sql() {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE nodes (id INTEGER PRIMARY KEY, parent_id INTEGER, FOREIGN KEY (parent_id) REFERENCES nodes (id) ON DELETE CASCADE);",
+      "INSERT INTO nodes VALUES (1, 1), (2, 1), (3, 2), (4, NULL);",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+
+    assert!(handle_sql_query("INSERT INTO nodes VALUES (5, 6);", &mut database).is_err());
+
+    // 递归删除所有子孙节点
+    handle_sql_query("DELETE FROM nodes WHERE id = 1;", &mut database).unwrap();
+    assert_eq!(database.get_table("nodes".to_string()).unwrap().get_row_ids(), vec![4]);
+  }
+
+  #[test]
+  fn test_handle_check_and_default_sql() {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE products (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL DEFAULT 'unknown',
+        price REAL DEFAULT 1.5 CHECK (price > 0),
+        discount REAL DEFAULT 0,
+        CHECK (discount <= price)
+      );",
+      "INSERT INTO products (id) VALUES (1);",
+      "INSERT INTO products (id, price, discount) VALUES (2, NULL, 10);",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+
+    // 没有写到的列用 DEFAULT 的值，CHECK 的结果为 NULL 的话不算违反约束
+    let table = database.get_table("products".to_string()).unwrap();
+    assert_eq!(table.get_value("name", &1), Ok(Value::Text("unknown".to_string())));
+    assert_eq!(table.get_value("price", &1), Ok(Value::Real(1.5)));
+    assert_eq!(table.get_value("discount", &1), Ok(Value::Real(0.0)));
+    assert_eq!(table.get_value("price", &2), Ok(Value::Null));
+
+    assert_eq!(
+      handle_sql_query("INSERT INTO products (id, price) VALUES (3, -1);", &mut database),
+      Err(NollaDBError::CheckViolation("price > 0".to_string()))
+    );
+    assert_eq!(
+      handle_sql_query("UPDATE products SET discount = 2 WHERE id = 1;", &mut database),
+      Err(NollaDBError::CheckViolation("discount <= price".to_string()))
+    );
+    assert_eq!(
+      handle_sql_query("ALTER TABLE products ADD COLUMN stock INTEGER DEFAULT 0 CHECK (stock > 0);", &mut database),
+      Err(NollaDBError::CheckViolation("stock > 0".to_string()))
+    );
+    assert!(handle_sql_query("ALTER TABLE products DROP COLUMN discount;", &mut database).is_err());
+
+    // 改列名之后 CHECK 约束跟着改，只有自己用到这一列的 CHECK 的话可以直接删除
+    handle_sql_query("ALTER TABLE products RENAME COLUMN price TO cost;", &mut database).unwrap();
+    handle_sql_query("UPDATE products SET discount = 1 WHERE id = 1;", &mut database).unwrap();
+    assert_eq!(
+      handle_sql_query("UPDATE products SET cost = 0.5 WHERE id = 1;", &mut database),
+      Err(NollaDBError::CheckViolation("discount <= cost".to_string()))
+    );
+    handle_sql_query("ALTER TABLE products ADD COLUMN stock INTEGER DEFAULT 1 CHECK (stock > 0);", &mut database).unwrap();
+    handle_sql_query("ALTER TABLE products DROP COLUMN stock;", &mut database).unwrap();
+
+    // 保存到文件时 DEFAULT 以及 CHECK 的表达式也要保存下来
+    let table = database.get_table("products".to_string()).unwrap();
+    let deserialized_table: Table = bincode::deserialize(&bincode::serialize(table).unwrap()).unwrap();
+    assert_eq!(&deserialized_table, table);
+  }
+
+  #[test]
+  fn test_handle_decimal_precision_sql() {
+    let mut database = Database::new("testdb".to_string());
+    for query in [
+      "CREATE TABLE prices (id INTEGER PRIMARY KEY, price DECIMAL(5, 2) UNIQUE DEFAULT 0.125, amount DECIMAL(3));",
+      "INSERT INTO prices (id, price, amount) VALUES (1, 1.005, 12.5);",
+      "INSERT INTO prices (id, price) VALUES (2, '-999.994');",
+      "INSERT INTO prices (id) VALUES (3);",
+      "UPDATE prices SET price = price / 2 - 0.0049 WHERE id = 2;",
+      "ALTER TABLE prices ADD COLUMN rate DECIMAL(2, 1) DEFAULT 0.25;",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+    }
+
+    // 写入的值保留 s 位小数，四舍五入
+    let decimal = |s: &str| Value::Decimal(s.parse().unwrap());
+    let table = database.get_table("prices".to_string()).unwrap();
+    assert_eq!(table.get_value("price", &1), Ok(decimal("1.01")));
+    assert_eq!(table.get_value("amount", &1), Ok(decimal("13")));
+    assert_eq!(table.get_value("price", &2), Ok(decimal("-500")));
+    assert_eq!(table.get_value("price", &3), Ok(decimal("0.13")));
+    assert_eq!(table.get_value("rate", &1), Ok(decimal("0.3")));
+
+    // 整数部分超过 p - s 位的话报错
+    assert_eq!(
+      handle_sql_query("INSERT INTO prices (id, price) VALUES (4, 999.995);", &mut database),
+      Err(NollaDBError::TypeMismatch("Value '999.995' is out of range for DECIMAL(5, 2) column price".to_string()))
+    );
+    assert!(handle_sql_query("UPDATE prices SET price = price * 1000 WHERE id = 1;", &mut database).is_err());
+    assert!(handle_sql_query("UPDATE prices SET amount = 999.5 WHERE id = 1;", &mut database).is_err());
+    assert!(handle_sql_query("ALTER TABLE prices ADD COLUMN fee DECIMAL(2, 1) DEFAULT 10;", &mut database).is_err());
+
+    // 唯一性约束检查的是四舍五入之后的值，查询的时候不四舍五入
+    assert!(matches!(
+      handle_sql_query("INSERT INTO prices (id, price) VALUES (5, 0.131);", &mut database),
+      Err(NollaDBError::ConstraintViolation { .. })
+    ));
+    for (query, expected) in [
+      ("SELECT id FROM prices WHERE price > 0.125 AND price < 1.01;", "SELECT statement done, 1 rows in set"),
+      ("SELECT id FROM prices WHERE price = 0.125;", "SELECT statement done, 0 rows in set"),
+    ] {
+      assert_eq!(handle_sql_query(query, &mut database), Ok(expected.to_string()));
+    }
+
+    for query in [
+      "CREATE TABLE invalid (price DECIMAL(0));",
+      "CREATE TABLE invalid (price DECIMAL(3, 5));",
+      "CREATE TABLE invalid (price DECIMAL(39, 2));",
+    ] {
+      assert!(handle_sql_query(query, &mut database).is_err());
+    }
+  }
+
+  #[rstest]
+  #[case(false, vec![1, 2, 100, 101, 5, 101])]
+  #[case(true, vec![1, 2, 100, 101, 5, 102])]
+  fn test_handle_row_id_allocation_sql(
+    #[case] is_autoincrement: bool,
+    #[case] expected_last_insert_row_ids: Vec<i64>,
+  ) {
+    let mut database = Database::new("testdb".to_string());
+    handle_sql_query(
+      &format!(
+        "CREATE TABLE test (id INTEGER PRIMARY KEY {}, name TEXT);",
+        if is_autoincrement { "AUTOINCREMENT" } else { "" }
+      ),
+      &mut database,
+    ).unwrap();
+
+    let mut last_insert_row_ids: Vec<i64> = vec![];
+    for query in [
+      "INSERT INTO test (name) VALUES ('a');",
+      "INSERT INTO test (name) VALUES ('b');",
+      "INSERT INTO test VALUES (100, 'c');",
+      "INSERT INTO test (name) VALUES ('d');",
+      // 显式写入更小的 row id 之后，自动分配的 row id 也不会变小
+      "INSERT INTO test VALUES (5, 'e');",
+      // 删除最大的一行之后，只有 AUTOINCREMENT 不会再次使用它的 row id
+      "DELETE FROM test WHERE id = 101;",
+      "INSERT INTO test (name) VALUES ('f');",
+    ] {
+      handle_sql_query(query, &mut database).unwrap();
+      if !query.starts_with("DELETE") {
+        last_insert_row_ids.push(get_last_insert_row_id());
+      }
+    }
+    assert_eq!(last_insert_row_ids, expected_last_insert_row_ids);
+
+    // last_insert_rowid() 可以在 SQL 里面使用
+    let select_query = SelectQuery::new(
+      &get_sql_ast("SELECT name FROM test WHERE id = last_insert_rowid();").unwrap()
+    ).unwrap();
+    assert_eq!(
+      execute_select_query(&select_query, &database).unwrap().rows,
+      vec![vec![Value::Text("f".to_string())]]
+    );
+  }
+
+  #[rstest]
+  // UNIQUE、NOT NULL、CHECK 以及外键约束在后面的行上失败
+  #[case("INSERT INTO test (name, score) VALUES ('b', 1), ('a', 2);")]
+  #[case("INSERT INTO test (name, score) VALUES ('b', 1), ('b', 2);")]
+  #[case("INSERT INTO test (name, score) VALUES ('b', 1), (NULL, 2);")]
+  #[case("INSERT INTO test (name, score) VALUES ('b', 1), ('c', -1);")]
+  #[case("INSERT INTO test (name, score, parent_id) VALUES ('b', 1, 1), ('c', 2, 99);")]
+  #[case("INSERT INTO test (name, score) VALUES ('b', 1), ('c', 2, 3);")]
+  fn test_handle_multi_row_insert_sql_error(#[case] insert_query: &str) {
+    let mut database = Database::new("testdb".to_string());
+    handle_sql_query(
+      "CREATE TABLE test (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL UNIQUE,
+        score INTEGER CHECK (score >= 0),
+        parent_id INTEGER REFERENCES test (id)
+      );",
+      &mut database,
+    ).unwrap();
+    handle_sql_query("INSERT INTO test (name, score) VALUES ('a', 0);", &mut database).unwrap();
+
+    assert!(handle_sql_query(insert_query, &mut database).is_err());
+    assert_eq!(get_last_insert_row_id(), 1);
+
+    // 前面插入成功的行也被撤销了，row id 也没有被占用
+    assert_eq!(
+      handle_sql_query("INSERT INTO test (name, score) VALUES ('b', 1);", &mut database),
+      Ok("INSERT statement done, 1 rows inserted, last insert rowid 2".to_string())
+    );
+    let select_query = SelectQuery::new(&get_sql_ast("SELECT id, name FROM test;").unwrap()).unwrap();
+    assert_eq!(
+      execute_select_query(&select_query, &database).unwrap().rows,
+      vec![
+        vec![Value::Integer(1), Value::Text("a".to_string())],
+        vec![Value::Integer(2), Value::Text("b".to_string())],
+      ]
+    );
+  }
+
+  #[rstest]
+  #[case("CREATE TABLE test (id TEXT PRIMARY KEY AUTOINCREMENT);")]
+  #[case("CREATE TABLE test (id INTEGER AUTOINCREMENT);")]
+  fn test_handle_autoincrement_sql_error(#[case] query: &str) {
+    let mut database = Database::new("testdb".to_string());
+    assert!(handle_sql_query(query, &mut database).is_err());
+  }
+
   fn insert_table_into_database_and_insert_data_into_table(
     database_name: &str,
     query: &str,
@@ -278,7 +1334,7 @@ mod tests {
   ) -> Result<String, NollaDBError> {
     let mut database = Database::new(database_name.to_string());
     let dialect = SQLiteDialect {};
-    let mut ast = Parser::parse_sql(&dialect, &query).unwrap();
+    let mut ast = Parser::parse_sql(&dialect, query).unwrap();
     let create_query = CreateQuery::new(&ast.pop().unwrap()).unwrap();
 
     database.tables.insert(
@@ -286,9 +1342,6 @@ mod tests {
       Table::new(create_query),
     );
 
-    match handle_sql_query(&insert_query, &mut database) {
-      Ok(response) => Ok(response),
-      Err(error) => Err(error),
-    }
+    handle_sql_query(insert_query, &mut database)
   }
 }
@@ -0,0 +1,126 @@
+use sqlparser::ast::{
+  Statement,
+  AlterTableOperation,
+  ColumnOption,
+};
+
+use crate::error::{Result, NollaDBError};
+use crate::table::row::value::Value;
+use crate::sql_query::expression::{RowContext, evaluate_expression};
+use super::create::SchemaOfSQLColumn;
+
+#[derive(Debug, PartialEq)]
+pub enum AlterOperation {
+  // 已经存在的行在新的列上的值为 default_value
+  AddColumn { table_metadata_column: Box<SchemaOfSQLColumn>, default_value: Value },
+  DropColumn { column_name: String, if_exists: bool },
+  RenameColumn { old_column_name: String, new_column_name: String },
+  RenameTable(String),
+}
+
+#[derive(Debug)]
+pub struct AlterQuery {
+  pub table_name: String,
+  pub operation: AlterOperation,
+}
+
+impl AlterQuery {
+  pub fn new(statement: &Statement) -> Result<AlterQuery> {
+    match statement {
+      Statement::AlterTable {
+        name,
+        operation,
+      } => {
+        let operation = match operation {
+          AlterTableOperation::AddColumn { column_def } => {
+            if column_def
+              .options
+              .iter()
+              .any(|column_option| matches!(column_option.option, ColumnOption::ForeignKey { .. })) {
+              return Err(NollaDBError::ToBeImplemented(
+                "ALTER TABLE ADD COLUMN with REFERENCES will be implemented soon".to_string()
+              ));
+            }
+            // DEFAULT 只能是常量表达式，所以不需要任何行就可以求值
+            let table_metadata_column = SchemaOfSQLColumn::new(column_def)?;
+            let default_value = match &table_metadata_column.default_expr {
+              Some(expr) => evaluate_expression(expr, &RowContext::default())?,
+              None => Value::Null,
+            };
+            AlterOperation::AddColumn {
+              table_metadata_column: Box::new(table_metadata_column),
+              default_value,
+            }
+          },
+          AlterTableOperation::DropColumn { column_name, if_exists, .. } =>
+            AlterOperation::DropColumn {
+              column_name: column_name.to_string(),
+              if_exists: *if_exists,
+            },
+          AlterTableOperation::RenameColumn { old_column_name, new_column_name } =>
+            AlterOperation::RenameColumn {
+              old_column_name: old_column_name.to_string(),
+              new_column_name: new_column_name.to_string(),
+            },
+          AlterTableOperation::RenameTable { table_name } =>
+            AlterOperation::RenameTable(table_name.to_string()),
+          _ => return Err(NollaDBError::ToBeImplemented(
+            format!("ALTER TABLE {} will to be implemented soon", operation)
+          )),
+        };
+
+        Ok(AlterQuery {
+          table_name: name.to_string(),
+          operation,
+        })
+      },
+      _ => Err(NollaDBError::Internal("Parsing ALTER SQL query error".to_string())),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::test_helpers::parse_expression;
+  use crate::sql_query::get_sql_ast;
+
+  #[rstest]
+  #[case(
+    "ALTER TABLE test ADD COLUMN score INTEGER DEFAULT 1 + 1;",
+    AlterOperation::AddColumn {
+      table_metadata_column: Box::new(SchemaOfSQLColumn {
+        column_name: "score".to_string(),
+        column_datatype: "Integer".to_string(),
+        is_primary_key: false,
+        is_unique_constraint: false,
+        is_not_null_constraint: false,
+        is_autoincrement: false,
+        default_expr: Some(parse_expression("1 + 1")),
+        check_exprs: vec![],
+        decimal_precision: None,
+      }),
+      default_value: Value::Integer(2),
+    }
+  )]
+  #[case(
+    "ALTER TABLE test DROP COLUMN IF EXISTS name;",
+    AlterOperation::DropColumn { column_name: "name".to_string(), if_exists: true }
+  )]
+  #[case(
+    "ALTER TABLE test RENAME COLUMN name TO nickname;",
+    AlterOperation::RenameColumn { old_column_name: "name".to_string(), new_column_name: "nickname".to_string() }
+  )]
+  #[case("ALTER TABLE test RENAME TO users;", AlterOperation::RenameTable("users".to_string()))]
+  fn test_alter_query(
+    #[case] query: &str,
+    #[case] expected: AlterOperation,
+  ) {
+    let alter_query = AlterQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert_eq!(alter_query.table_name, "test");
+    assert_eq!(alter_query.operation, expected);
+  }
+}
@@ -1,5 +1,19 @@
-use sqlparser::ast::{Statement, DataType, ColumnOption};
+use std::collections::HashMap;
+
+use sqlparser::ast::{
+  Statement,
+  DataType,
+  ColumnDef,
+  ColumnOption,
+  TableConstraint,
+  Ident,
+  ReferentialAction,
+  Expr,
+};
 use crate::error::{Result, NollaDBError};
+use crate::table::foreign_key::{ForeignKey, ForeignKeyAction};
+use crate::table::row::value::Value;
+use crate::sql_query::expression::{RowContext, evaluate_expression, evaluate_check};
 
 #[derive(Debug, PartialEq)]
 // TODO: 待优化
@@ -9,12 +23,137 @@ pub struct SchemaOfSQLColumn {
   pub is_primary_key: bool,
   pub is_unique_constraint: bool,
   pub is_not_null_constraint: bool,
+  // INTEGER PRIMARY KEY AUTOINCREMENT，分配 row id 时不会再次使用已经删除的 row id
+  pub is_autoincrement: bool,
+  pub default_expr: Option<Expr>,
+  pub check_exprs: Vec<Expr>,
+  // DECIMAL(p, s) 的 p 和 s，DECIMAL(p) 的 s 是 0，没有写的话不限制
+  pub decimal_precision: Option<(u32, u32)>,
+}
+
+impl SchemaOfSQLColumn {
+  // 从 SQL 中的列定义解析出列名、类型以及约束
+  pub fn new(column: &ColumnDef) -> Result<SchemaOfSQLColumn> {
+    let column_name = column.name.to_string();
+    let column_datatype = match &column.data_type {
+      DataType::SmallInt(_) => "Integer", // bytes
+      DataType::Int(_) => "Integer", // bytes
+      DataType::BigInt(_) => "Integer", // bytes
+      DataType::Text => "Text",
+      DataType::Varchar(_) => "Text", // bytes
+      DataType::Boolean => "Bool",
+      DataType::Real => "Real",
+      DataType::Float(_) => "Real", // precision
+      DataType::Double => "Real",
+      DataType::Decimal(_, _) => "Decimal", // precision
+      DataType::Blob(_) => "Blob", // bytes
+      DataType::Binary(_) => "Blob", // bytes
+      DataType::Varbinary(_) => "Blob", // bytes
+      DataType::Bytea => "Blob",
+      DataType::Date => "Date",
+      DataType::Time => "Time",
+      DataType::Timestamp => "Timestamp",
+      // sqlparser 不认识的类型名，以及没有写长度的 BLOB
+      DataType::Custom(name) if name.to_string().eq_ignore_ascii_case("BOOL") => "Bool",
+      DataType::Custom(name) if name.to_string().eq_ignore_ascii_case("BLOB") => "Blob",
+      DataType::Custom(name) if name.to_string().eq_ignore_ascii_case("DATETIME") => "Timestamp",
+      DataType::Custom(name) if name.to_string().eq_ignore_ascii_case("JSON") => "Json",
+      data_type => return Err(NollaDBError::ToBeImplemented(
+        format!("Data type {} will be implemented soon", data_type)
+      )),
+    };
+
+    let decimal_precision = match &column.data_type {
+      DataType::Decimal(Some(precision), scale) => {
+        let scale = scale.unwrap_or(0);
+        if *precision == 0 || *precision > 38 || scale > *precision {
+          return Err(NollaDBError::Internal(
+            format!("Invalid DECIMAL({}, {}) for column {}, precision must be 1 to 38 and scale can not exceed it", precision, scale, column_name)
+          ));
+        }
+        Some((*precision as u32, scale as u32))
+      },
+      _ => None,
+    };
+
+    let mut is_primary_key: bool = false;
+    let mut is_unique_constraint: bool = false;
+    let mut is_not_null_constraint: bool = false;
+    let mut is_autoincrement: bool = false;
+    let mut default_expr: Option<Expr> = None;
+    let mut check_exprs: Vec<Expr> = vec![];
+
+    for column_option in &column.options {
+      match &column_option.option {
+        ColumnOption::Unique {
+          is_primary
+        } => {
+          // PRIMARY KEY 和 UNIQUE 约束都需要唯一
+          is_unique_constraint = true;
+          // 而只有是 PRIMARY KEY 的情况下，才隐含 NOT NULL 约束
+          if *is_primary {
+            is_primary_key = true;
+            is_not_null_constraint = true;
+          }
+
+        },
+        ColumnOption::NotNull => {
+          is_not_null_constraint = true;
+        },
+        ColumnOption::Default(expr) => {
+          // DEFAULT 只能是常量表达式，所以不需要任何行就可以求值，这里先求值一次检查是否合法
+          evaluate_expression(expr, &RowContext::default())?;
+          default_expr = Some(expr.clone());
+        },
+        // 列上的 CHECK 也可以引用同一行的其他列，等所有列都解析完之后再检查
+        ColumnOption::Check(expr) => check_exprs.push(expr.clone()),
+        ColumnOption::DialectSpecific(tokens)
+          if tokens.len() == 1 && tokens[0].to_string().eq_ignore_ascii_case("AUTOINCREMENT") => {
+          is_autoincrement = true;
+        },
+        _ => (),
+      };
+    }
+
+    // 和 SQLite 一样，只有 INTEGER PRIMARY KEY 才能是 AUTOINCREMENT
+    if is_autoincrement && !(is_primary_key && column_datatype == "Integer") {
+      return Err(NollaDBError::Internal(
+        format!("AUTOINCREMENT is only allowed on an INTEGER PRIMARY KEY, but found column {}", column_name)
+      ));
+    }
+
+    Ok(SchemaOfSQLColumn {
+      column_name,
+      column_datatype: column_datatype.to_string(),
+      is_primary_key,
+      is_unique_constraint,
+      is_not_null_constraint,
+      is_autoincrement,
+      default_expr,
+      check_exprs,
+      decimal_precision,
+    })
+  }
+}
+
+// 表级别的 PRIMARY KEY (a, b) 或者 UNIQUE (a, b) 约束
+// 只有一列的约束会直接合并到对应的 SchemaOfSQLColumn 里面，这里只有由多列组成的约束
+#[derive(Debug, PartialEq)]
+pub struct SchemaOfSQLKey {
+  pub column_names: Vec<String>,
+  pub is_primary: bool,
 }
 
 #[derive(Debug)]
 pub struct CreateQuery {
   pub table_name: String,
   pub table_metadata_columns: Vec<SchemaOfSQLColumn>,
+  pub table_metadata_keys: Vec<SchemaOfSQLKey>,
+  // 列上的 REFERENCES 以及表级别的 FOREIGN KEY 约束
+  // 没有写被引用的列的话 referred_column_names 为空，建表的时候再换成父表的 PRIMARY KEY
+  pub table_metadata_foreign_keys: Vec<ForeignKey>,
+  // 表级别的 CHECK 约束
+  pub table_metadata_checks: Vec<Expr>,
 }
 
 impl CreateQuery {
@@ -22,6 +161,9 @@ impl CreateQuery {
     #[allow(unused_assignments)]
     let mut option_table_name: Option<String> = None;
     let mut table_metadata_columns: Vec<SchemaOfSQLColumn> = vec![];
+    let mut table_metadata_keys: Vec<SchemaOfSQLKey> = vec![];
+    let mut table_metadata_foreign_keys: Vec<ForeignKey> = vec![];
+    let mut table_metadata_checks: Vec<Expr> = vec![];
 
     match statement {
       Statement::CreateTable {
@@ -51,76 +193,125 @@ impl CreateQuery {
             );
           }
 
-          let column_datatype = match &column.data_type {
-            DataType::SmallInt(_) => "Integer", // bytes
-            DataType::Int(_) => "Integer", // bytes
-            DataType::BigInt(_) => "Integer", // bytes
-            DataType::Text => "Text",
-            DataType::Varchar(_) => "Text", // bytes
-            DataType::Boolean => "Bool",
-            DataType::Real => "Real",
-            DataType::Float(_) => "Real", // precision
-            DataType::Double => "Real",
-            DataType::Decimal(_, _) => "Real", // precision
-            _ => {
-              eprintln!("not matched on custom type");
-              "Invalid"
-            }
-          };
+          let table_metadata_column = SchemaOfSQLColumn::new(column)?;
 
-          let mut is_primary_key: bool = false;
-          let mut is_unique_constraint: bool = false;
-          let mut is_not_null_constraint: bool = false;
+          // 这里还要检查创建表时，表里面是否已经有 PRIMARY KEY
+          if table_metadata_column.is_primary_key && table_metadata_columns
+              .iter()
+              .any(|table_metadata_column| table_metadata_column.is_primary_key) {
+            return Err(
+              NollaDBError::Internal(
+                format!("Table '{}' has more than one PRIMARY KEY", &name)
+              )
+            );
+          }
 
+          // 列上的 REFERENCES other (col)
           for column_option in &column.options {
-            match column_option.option {
-              ColumnOption::Unique {
-                is_primary
-              } => {
-                // 只有 Integer 和 Text 类型可以作为 PRIMARY KEY 和 Unique 约束
-                if column_datatype == "Bool" ||
-                   column_datatype == "Real" ||
-                   !is_primary { continue; }
-
-                // 这里还要检查创建表时，表里面是否已经有 PRIMARY KEY
-                if table_metadata_columns
-                    .iter()
-                    .any(|table_metadata_column| table_metadata_column.is_primary_key == true) {
-                  return Err(
-                    NollaDBError::Internal(
-                      format!("Table '{}' has more than one PRIMARY KEY", &name)
-                    )
-                  );
-                }
-
-                is_primary_key = is_primary;
-                is_unique_constraint = true;
-                // 而只有是 PRIMARY KEY 的情况下，才可以是 NOT NULL 约束
-                is_not_null_constraint = true;
-
-              },
-              ColumnOption::NotNull => {
-                is_not_null_constraint = true;
-              },
-              _ => (),
-            };
+            if let ColumnOption::ForeignKey {
+              foreign_table,
+              referred_columns,
+              on_delete,
+              on_update,
+            } = &column_option.option {
+              table_metadata_foreign_keys.push(ForeignKey {
+                column_names: vec![column_name.to_string()],
+                foreign_table_name: foreign_table.to_string(),
+                referred_column_names: referred_columns
+                  .iter()
+                  .map(|referred_column| referred_column.to_string())
+                  .collect(),
+                on_delete: get_foreign_key_action(on_delete)?,
+                on_update: get_foreign_key_action(on_update)?,
+              });
+            }
           }
 
           // 组装 table_metadata_columns
-          table_metadata_columns.push(SchemaOfSQLColumn {
-            column_name,
-            column_datatype: column_datatype.to_string(),
-            is_primary_key,
-            is_unique_constraint,
-            is_not_null_constraint,
-          });
+          table_metadata_columns.push(table_metadata_column);
         }
 
-        // TODO: 处理 constraints
+        // 处理表级别的约束
         for constraint in constraints {
-          println!("{:?}", constraint);
+          let (columns, is_primary) = match constraint {
+            TableConstraint::Unique { columns, is_primary, .. } => (columns, *is_primary),
+            TableConstraint::ForeignKey {
+              columns,
+              foreign_table,
+              referred_columns,
+              on_delete,
+              on_update,
+              ..
+            } => {
+              table_metadata_foreign_keys.push(ForeignKey {
+                column_names: get_constraint_column_names(columns, &table_metadata_columns, constraint)?,
+                foreign_table_name: foreign_table.to_string(),
+                referred_column_names: referred_columns
+                  .iter()
+                  .map(|referred_column| referred_column.to_string())
+                  .collect(),
+                on_delete: get_foreign_key_action(on_delete)?,
+                on_update: get_foreign_key_action(on_update)?,
+              });
+              continue;
+            },
+            TableConstraint::Check { expr, .. } => {
+              table_metadata_checks.push(*expr.clone());
+              continue;
+            },
+          };
+
+          let column_names = get_constraint_column_names(columns, &table_metadata_columns, constraint)?;
+
+          // PRIMARY KEY 不管是写在列上还是写在表上，都只能有一个
+          if is_primary && (
+            table_metadata_columns
+              .iter()
+              .any(|table_metadata_column| table_metadata_column.is_primary_key)
+            || table_metadata_keys
+              .iter()
+              .any(|table_metadata_key| table_metadata_key.is_primary)
+          ) {
+            return Err(
+              NollaDBError::Internal(
+                format!("Table '{}' has more than one PRIMARY KEY", &name)
+              )
+            );
+          }
+
+          // PRIMARY KEY 的每一列都隐含 NOT NULL 约束
+          for table_metadata_column in table_metadata_columns.iter_mut() {
+            if !column_names.contains(&table_metadata_column.column_name) { continue; }
+            if is_primary {
+              table_metadata_column.is_not_null_constraint = true;
+            }
+            // 只有一列的话和写在列上的约束是一样的
+            if column_names.len() == 1 {
+              table_metadata_column.is_unique_constraint = true;
+              table_metadata_column.is_primary_key |= is_primary;
+            }
+          }
+
+          if column_names.len() > 1 && !table_metadata_keys
+              .iter()
+              .any(|table_metadata_key| table_metadata_key.column_names == column_names && !is_primary) {
+            table_metadata_keys.push(SchemaOfSQLKey { column_names, is_primary });
+          }
         }
 
+        // CHECK 约束中只能引用表中的列，对每一列都是 NULL 的一行求值一次，引用了不存在的列的话会报错
+        let row_context = RowContext::from_column_values(
+          table_metadata_columns
+            .iter()
+            .map(|table_metadata_column| (table_metadata_column.column_name.to_string(), Value::Null))
+            .collect::<HashMap<String, Value>>()
+        );
+        for expr in table_metadata_columns
+          .iter()
+          .flat_map(|table_metadata_column| table_metadata_column.check_exprs.iter())
+          .chain(table_metadata_checks.iter()) {
+          evaluate_check(expr, &row_context)?;
+        }
       },
       _ => return Err(NollaDBError::Internal("Parsing CREATE SQL query error".to_string())),
     }
@@ -129,9 +320,55 @@ impl CreateQuery {
       Some(table_name) => Ok(CreateQuery {
           table_name,
           table_metadata_columns,
+          table_metadata_keys,
+          table_metadata_foreign_keys,
+          table_metadata_checks,
         }),
-      _ => return Err(NollaDBError::Internal("Parsing CREATE SQL query error".to_string())),
+      _ => Err(NollaDBError::Internal("Parsing CREATE SQL query error".to_string())),
+    }
+  }
+}
+
+// 表级别的约束中的列必须都在表中，并且不能重复
+fn get_constraint_column_names(
+  columns: &[Ident],
+  table_metadata_columns: &[SchemaOfSQLColumn],
+  constraint: &TableConstraint,
+) -> Result<Vec<String>> {
+  let mut column_names: Vec<String> = vec![];
+  for column in columns {
+    let column_name = column.to_string();
+    if !table_metadata_columns
+        .iter()
+        .any(|table_metadata_column| table_metadata_column.column_name == column_name) {
+      return Err(
+        NollaDBError::Internal(
+          format!("No such column {} in constraint {}", &column_name, constraint)
+        )
+      );
+    }
+    if column_names.contains(&column_name) {
+      return Err(
+        NollaDBError::Internal(
+          format!("Duplicate column name {} in constraint {}", &column_name, constraint)
+        )
+      );
     }
+    column_names.push(column_name);
+  }
+
+  Ok(column_names)
+}
+
+// 没有写 ON DELETE / ON UPDATE 的话默认是 NO ACTION，也就是 RESTRICT
+fn get_foreign_key_action(referential_action: &Option<ReferentialAction>) -> Result<ForeignKeyAction> {
+  match referential_action {
+    None | Some(ReferentialAction::NoAction) | Some(ReferentialAction::Restrict) => Ok(ForeignKeyAction::Restrict),
+    Some(ReferentialAction::Cascade) => Ok(ForeignKeyAction::Cascade),
+    Some(ReferentialAction::SetNull) => Ok(ForeignKeyAction::SetNull),
+    Some(referential_action) => Err(NollaDBError::ToBeImplemented(
+      format!("FOREIGN KEY action {} will be implemented soon", referential_action)
+    )),
   }
 }
 
@@ -143,6 +380,7 @@ mod tests {
   use sqlparser::parser::Parser;
   use sqlparser::dialect::SQLiteDialect;
   use sqlparser::ast::Statement;
+  use crate::sql_query::get_sql_ast;
 
   #[rstest]
   #[case(
@@ -158,20 +396,136 @@ mod tests {
     #[case] expected: &str,
   ) {
     let dialect = SQLiteDialect {};
-    let mut ast = Parser::parse_sql(&dialect, &query).unwrap();
+    let mut ast = Parser::parse_sql(&dialect, query).unwrap();
     let statement = ast.pop().unwrap();
 
-    let _ = match statement {
-      Statement::CreateTable {..} => {
-        match CreateQuery::new(&statement) {
-          Ok(create_query) => assert_eq!(create_query.table_name, expected),
-          Err(error) => {
-            eprintln!("Error: {}", error);
-            assert!(false)
-          },
-        }
-      },
-      _ => (),
+    if let Statement::CreateTable {..} = statement {
+      match CreateQuery::new(&statement) {
+        Ok(create_query) => assert_eq!(create_query.table_name, expected),
+        Err(error) => panic!("Error: {}", error),
+      }
     };
   }
+
+  #[rstest]
+  #[case(
+    "CREATE TABLE test (a INTEGER, b TEXT, c REAL, PRIMARY KEY (a, b), UNIQUE (b, c), UNIQUE (b, c));",
+    vec![(vec!["a", "b"], true), (vec!["b", "c"], false)],
+    vec![(true, false, false), (true, false, false), (false, false, false)],
+  )]
+  #[case(
+    "CREATE TABLE test (a INTEGER, b TEXT, c REAL, PRIMARY KEY (a), UNIQUE (c));",
+    vec![],
+    vec![(true, true, true), (false, false, false), (false, true, false)],
+  )]
+  fn test_create_table_constraints(
+    #[case] query: &str,
+    #[case] expected_keys: Vec<(Vec<&str>, bool)>,
+    // 每一列的 (NOT NULL, UNIQUE, PRIMARY KEY)
+    #[case] expected_columns: Vec<(bool, bool, bool)>,
+  ) {
+    let create_query = CreateQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert_eq!(
+      create_query.table_metadata_keys,
+      expected_keys
+        .into_iter()
+        .map(|(column_names, is_primary)| SchemaOfSQLKey {
+          column_names: column_names.iter().map(|column_name| column_name.to_string()).collect(),
+          is_primary,
+        })
+        .collect::<Vec<SchemaOfSQLKey>>()
+    );
+    assert_eq!(
+      create_query
+        .table_metadata_columns
+        .iter()
+        .map(|column| (column.is_not_null_constraint, column.is_unique_constraint, column.is_primary_key))
+        .collect::<Vec<(bool, bool, bool)>>(),
+      expected_columns
+    );
+  }
+
+  #[rstest]
+  #[case("CREATE TABLE test (a INTEGER PRIMARY KEY, b TEXT, PRIMARY KEY (a, b));")]
+  #[case("CREATE TABLE test (a INTEGER, b TEXT, PRIMARY KEY (a, b), PRIMARY KEY (b));")]
+  #[case("CREATE TABLE test (a INTEGER, b TEXT, UNIQUE (a, c));")]
+  #[case("CREATE TABLE test (a INTEGER, b TEXT, UNIQUE (a, a));")]
+  #[case("CREATE TABLE test (a INTEGER, FOREIGN KEY (c) REFERENCES other (id));")]
+  #[case("CREATE TABLE test (a INTEGER REFERENCES other (id) ON DELETE SET DEFAULT);")]
+  #[case("CREATE TABLE test (a INTEGER, CHECK (b > 0));")]
+  #[case("CREATE TABLE test (a INTEGER CHECK (a > 0), b INTEGER DEFAULT a);")]
+  #[case("CREATE TABLE test (a UUID);")]
+  fn test_create_table_constraints_error(#[case] query: &str) {
+    assert!(CreateQuery::new(&get_sql_ast(query).unwrap()).is_err());
+  }
+
+  #[rstest]
+  #[case(
+    "CREATE TABLE test (a INTEGER REFERENCES users, b TEXT);",
+    vec!["FOREIGN KEY (a) REFERENCES users () ON DELETE RESTRICT ON UPDATE RESTRICT"],
+  )]
+  #[case(
+    "CREATE TABLE test (
+      a INTEGER REFERENCES users (id) ON DELETE CASCADE,
+      b TEXT,
+      c REAL,
+      FOREIGN KEY (b, c) REFERENCES other (x, y) ON DELETE NO ACTION ON UPDATE SET NULL
+    );",
+    vec![
+      "FOREIGN KEY (a) REFERENCES users (id) ON DELETE CASCADE ON UPDATE RESTRICT",
+      "FOREIGN KEY (b, c) REFERENCES other (x, y) ON DELETE RESTRICT ON UPDATE SET NULL",
+    ],
+  )]
+  fn test_create_table_foreign_keys(
+    #[case] query: &str,
+    #[case] expected: Vec<&str>,
+  ) {
+    let create_query = CreateQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert_eq!(
+      create_query
+        .table_metadata_foreign_keys
+        .iter()
+        .map(|foreign_key| foreign_key.to_string())
+        .collect::<Vec<String>>(),
+      expected
+    );
+  }
+
+  #[test]
+  fn test_create_table_column_types() {
+    let create_query = CreateQuery::new(&get_sql_ast(
+      "CREATE TABLE test (
+        a BIGINT, b BOOL, c DOUBLE, d DECIMAL(10, 2), e BLOB, f BYTEA, g DATE, h TIME, i TIMESTAMP, j DATETIME, k JSON
+      );"
+    ).unwrap()).unwrap();
+
+    assert_eq!(
+      create_query
+        .table_metadata_columns
+        .iter()
+        .map(|column| column.column_datatype.as_str())
+        .collect::<Vec<&str>>(),
+      vec!["Integer", "Bool", "Real", "Decimal", "Blob", "Blob", "Date", "Time", "Timestamp", "Timestamp", "Json"]
+    );
+  }
+
+  #[test]
+  fn test_create_table_default_and_check() {
+    let create_query = CreateQuery::new(&get_sql_ast(
+      "CREATE TABLE test (a INTEGER DEFAULT 1 + 1 CHECK (a > 0) CHECK (a < b), b INTEGER, CHECK (a <> b));"
+    ).unwrap()).unwrap();
+
+    let table_metadata_column = &create_query.table_metadata_columns[0];
+    assert_eq!(table_metadata_column.default_expr.as_ref().map(|expr| expr.to_string()), Some("1 + 1".to_string()));
+    assert_eq!(
+      table_metadata_column.check_exprs.iter().map(|expr| expr.to_string()).collect::<Vec<String>>(),
+      vec!["a > 0", "a < b"]
+    );
+    assert_eq!(
+      create_query.table_metadata_checks.iter().map(|expr| expr.to_string()).collect::<Vec<String>>(),
+      vec!["a <> b"]
+    );
+  }
 }
@@ -0,0 +1,88 @@
+use sqlparser::ast::{
+  Statement,
+  Expr,
+};
+
+use crate::error::{Result, NollaDBError};
+
+#[derive(Debug, PartialEq)]
+pub struct CreateIndexQuery {
+  pub index_name: String,
+  pub table_name: String,
+  pub column_name: String,
+  pub is_unique: bool,
+  pub if_not_exists: bool,
+}
+
+impl CreateIndexQuery {
+  pub fn new(statement: &Statement) -> Result<CreateIndexQuery> {
+    match statement {
+      Statement::CreateIndex {
+        name,
+        table_name,
+        columns,
+        unique,
+        if_not_exists,
+      } => {
+        // 目前只支持单列索引
+        let column_name = match columns.as_slice() {
+          [order_by_expr] => match &order_by_expr.expr {
+            Expr::Identifier(ident) => ident.to_string(),
+            expr => return Err(NollaDBError::ToBeImplemented(
+              format!("Index on expression {} will to be implemented soon", expr)
+            )),
+          },
+          _ => return Err(NollaDBError::ToBeImplemented(
+            "Index on multiple columns will to be implemented soon".to_string()
+          )),
+        };
+
+        Ok(CreateIndexQuery {
+          index_name: name.to_string(),
+          table_name: table_name.to_string(),
+          column_name,
+          is_unique: *unique,
+          if_not_exists: *if_not_exists,
+        })
+      },
+      _ => Err(NollaDBError::Internal("Parsing CREATE INDEX SQL query error".to_string())),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::sql_query::get_sql_ast;
+
+  #[rstest]
+  #[case("CREATE INDEX idx_name ON test (name);", false, false)]
+  #[case("CREATE UNIQUE INDEX IF NOT EXISTS idx_name ON test (name);", true, true)]
+  fn test_create_index_query(
+    #[case] query: &str,
+    #[case] expected_is_unique: bool,
+    #[case] expected_if_not_exists: bool,
+  ) {
+    let create_index_query = CreateIndexQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert_eq!(
+      create_index_query,
+      CreateIndexQuery {
+        index_name: "idx_name".to_string(),
+        table_name: "test".to_string(),
+        column_name: "name".to_string(),
+        is_unique: expected_is_unique,
+        if_not_exists: expected_if_not_exists,
+      }
+    );
+  }
+
+  #[rstest]
+  #[case("CREATE INDEX idx_name ON test (id, name);")]
+  #[case("CREATE INDEX idx_name ON test (id + 1);")]
+  fn test_create_index_query_error(#[case] query: &str) {
+    assert!(CreateIndexQuery::new(&get_sql_ast(query).unwrap()).is_err());
+  }
+}
@@ -0,0 +1,48 @@
+use sqlparser::ast::{
+  Statement,
+  Expr,
+};
+
+use crate::error::{Result, NollaDBError};
+
+#[derive(Debug)]
+pub struct DeleteQuery {
+  pub table_name: String,
+  pub selection: Option<Expr>,
+}
+
+impl DeleteQuery {
+  pub fn new(statement: &Statement) -> Result<DeleteQuery> {
+    match statement {
+      Statement::Delete {
+        table_name,
+        selection,
+      } => Ok(DeleteQuery {
+        table_name: table_name.to_string(),
+        selection: selection.clone(),
+      }),
+      _ => Err(NollaDBError::Internal("Parsing DELETE SQL query error".to_string())),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::sql_query::get_sql_ast;
+
+  #[rstest]
+  #[case("DELETE FROM test WHERE id = 1;", true)]
+  #[case("DELETE FROM test;", false)]
+  fn test_delete_query(
+    #[case] query: &str,
+    #[case] expected_has_selection: bool,
+  ) {
+    let delete_query = DeleteQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert_eq!(delete_query.table_name, "test");
+    assert_eq!(delete_query.selection.is_some(), expected_has_selection);
+  }
+}
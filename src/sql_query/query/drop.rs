@@ -0,0 +1,74 @@
+use sqlparser::ast::{
+  Statement,
+  ObjectType,
+};
+
+use crate::error::{Result, NollaDBError};
+
+#[derive(Debug)]
+pub struct DropQuery {
+  // 目前只支持 TABLE 和 INDEX
+  pub object_type: ObjectType,
+  pub names: Vec<String>,
+  // 为 true 时表或者索引不存在也不报错
+  pub if_exists: bool,
+}
+
+impl DropQuery {
+  pub fn new(statement: &Statement) -> Result<DropQuery> {
+    match statement {
+      Statement::Drop {
+        object_type: object_type @ (ObjectType::Table | ObjectType::Index),
+        if_exists,
+        names,
+        ..
+      } => Ok(DropQuery {
+        object_type: object_type.clone(),
+        names: names
+          .iter()
+          .map(|name| name.to_string())
+          .collect(),
+        if_exists: *if_exists,
+      }),
+      Statement::Drop {
+        object_type,
+        ..
+      } => Err(NollaDBError::ToBeImplemented(
+        format!("DROP {} will to be implemented soon", object_type)
+      )),
+      _ => Err(NollaDBError::Internal("Parsing DROP SQL query error".to_string())),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::sql_query::get_sql_ast;
+
+  #[rstest]
+  #[case("DROP TABLE test;", ObjectType::Table, vec!["test"], false)]
+  #[case("DROP TABLE IF EXISTS a, b;", ObjectType::Table, vec!["a", "b"], true)]
+  #[case("DROP INDEX idx_name;", ObjectType::Index, vec!["idx_name"], false)]
+  fn test_drop_query(
+    #[case] query: &str,
+    #[case] expected_object_type: ObjectType,
+    #[case] expected_names: Vec<&str>,
+    #[case] expected_if_exists: bool,
+  ) {
+    let drop_query = DropQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert_eq!(drop_query.object_type, expected_object_type);
+    assert_eq!(drop_query.names, expected_names);
+    assert_eq!(drop_query.if_exists, expected_if_exists);
+  }
+
+  #[rstest]
+  #[case("DROP VIEW test;")]
+  #[case("DELETE FROM test;")]
+  fn test_drop_query_error(#[case] query: &str) {
+    assert!(DropQuery::new(&get_sql_ast(query).unwrap()).is_err());
+  }
+}
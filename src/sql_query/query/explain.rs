@@ -0,0 +1,83 @@
+use sqlparser::ast::Statement;
+
+use crate::error::{Result, NollaDBError};
+
+#[derive(Debug)]
+pub struct ExplainQuery {
+  // 要查看查询计划的 SQL 语句
+  pub statement: Statement,
+}
+
+impl ExplainQuery {
+  pub fn new(statement: &Statement) -> Result<ExplainQuery> {
+    match statement {
+      Statement::Explain {
+        analyze: true,
+        ..
+      } => Err(NollaDBError::ToBeImplemented(
+        "EXPLAIN ANALYZE will be implemented soon".to_string()
+      )),
+      Statement::Explain {
+        statement,
+        ..
+      } => Ok(ExplainQuery {
+        statement: *statement.clone(),
+      }),
+      _ => Err(NollaDBError::Internal("Parsing EXPLAIN SQL query error".to_string())),
+    }
+  }
+}
+
+// sqlparser 不支持 SQLite 的 EXPLAIN QUERY PLAN，这里把 QUERY PLAN 去掉，当作 EXPLAIN 处理
+pub fn remove_query_plan_keywords(sql_query: &str) -> String {
+  let words: Vec<&str> = sql_query.split_whitespace().take(3).collect();
+  match words.as_slice() {
+    [explain, query, plan] if explain.eq_ignore_ascii_case("explain")
+      && query.eq_ignore_ascii_case("query")
+      && plan.eq_ignore_ascii_case("plan") => {
+      let sql_query = sql_query.trim_start()[explain.len()..].trim_start();
+      let sql_query = sql_query[query.len()..].trim_start();
+      format!("EXPLAIN{}", &sql_query[plan.len()..])
+    },
+    _ => sql_query.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::sql_query::get_sql_ast;
+
+  #[rstest]
+  #[case("EXPLAIN SELECT * FROM test;", "SELECT * FROM test")]
+  #[case("EXPLAIN QUERY PLAN DELETE FROM test WHERE id = 1;", "DELETE FROM test WHERE id = 1")]
+  #[case("explain  query\nplan UPDATE test SET a = 1;", "UPDATE test SET a = 1")]
+  fn test_explain_query(
+    #[case] query: &str,
+    #[case] expected: &str,
+  ) {
+    let explain_query = ExplainQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert_eq!(explain_query.statement.to_string(), expected);
+  }
+
+  #[rstest]
+  #[case("EXPLAIN ANALYZE SELECT * FROM test;")]
+  #[case("SELECT * FROM test;")]
+  fn test_explain_query_error(#[case] query: &str) {
+    assert!(ExplainQuery::new(&get_sql_ast(query).unwrap()).is_err());
+  }
+
+  #[rstest]
+  #[case("EXPLAIN QUERY PLAN SELECT 1;", "EXPLAIN SELECT 1;")]
+  #[case("SELECT plan FROM query;", "SELECT plan FROM query;")]
+  #[case("EXPLAIN SELECT plan FROM query;", "EXPLAIN SELECT plan FROM query;")]
+  fn test_remove_query_plan_keywords(
+    #[case] query: &str,
+    #[case] expected: &str,
+  ) {
+    assert_eq!(remove_query_plan_keywords(query), expected);
+  }
+}
@@ -4,16 +4,17 @@ use sqlparser::ast::{
   SetExpr,
   Values,
   Expr,
-  Value,
 };
 
 use crate::error::{Result, NollaDBError};
+use crate::table::row::value::Value;
+use crate::sql_query::expression::{RowContext, evaluate_expression};
 
 #[derive(Debug)]
 pub struct InsertQuery {
   pub table_name: String,
   pub table_column_names: Vec<String>,
-  pub table_column_values: Vec<Vec<String>>,
+  pub table_column_values: Vec<Vec<Value>>,
 }
 
 impl InsertQuery {
@@ -21,7 +22,7 @@ impl InsertQuery {
     #[allow(unused_assignments)]
     let mut option_table_name: Option<String> = None;
     let mut table_column_names: Vec<String> = vec![];
-    let mut table_column_values: Vec<Vec<String>> = vec![];
+    let mut table_column_values: Vec<Vec<Value>> = vec![];
 
     match statement {
       Statement::Insert {
@@ -32,53 +33,46 @@ impl InsertQuery {
       } => {
         option_table_name = Some(name.to_string());
         for column in columns {
+          // 同一个列不能被写入多次
+          if table_column_names.contains(&column.to_string()) {
+            return Err(
+              NollaDBError::Internal(
+                format!("Duplicate column name in INSERT: {}", column)
+              )
+            );
+          }
           table_column_names.push(column.to_string());
         }
 
         // &Query
-        match &**source {
-          Query {
-            body,
-            // order_by,
-            // limit,
-            // offset,
-            // fetch,
-            ..
-          } => {
-            // 解析类似于
-            //-- Values stored as TEXT, INTEGER, INTEGER, REAL, TEXT.
-            // INSERT INTO t1 VALUES('500.0', '500.0', '500.0', '500.0', '500.0');
-            // 的语句
-            // body 里面是解析之后的 INSERT 之后的 ast
-            // 把里面对应的表达式抽出来然后一个一个转成字符串
-            if let SetExpr::Values(values) = body {
-              #[allow(irrefutable_let_patterns)]
-              if let Values(expressions) = values {
-                for expression in expressions {
-                  let mut table_column_value: Vec<String> = vec![];
-                  for expr in expression {
-                    match expr {
-                      Expr::Value(v) => match v {
-                        Value::Number(n, _) => table_column_value.push(n.to_string()),
-                        Value::Boolean(b) => match *b {
-                          true => table_column_value.push("true".to_string()),
-                          false => table_column_value.push("false".to_string()),
-                        },
-                        Value::SingleQuotedString(sqs) => table_column_value.push(sqs.to_string()),
-                        Value::Null => table_column_value.push("Null".to_string()),
-                        _ => {},
-                      },
-                      Expr::Identifier(i) => table_column_value.push(i.to_string()),
-                      _ => {},
-                    }
-                  }
+        let Query {
+          body,
+          // order_by,
+          // limit,
+          // offset,
+          // fetch,
+          ..
+        } = &**source;
+
+        // 解析类似于
+        //-- Values stored as TEXT, INTEGER, INTEGER, REAL, TEXT.
+        // INSERT INTO t1 VALUES('500.0', '500.0', '500.0', '500.0', '500.0');
+        // 的语句
+        // body 里面是解析之后的 INSERT 之后的 ast
+        // 把里面对应的表达式抽出来求值，写入的时候再转换成每一列对应的类型
+        if let SetExpr::Values(Values(expressions)) = body {
+          for expression in expressions {
+            let mut table_column_value: Vec<Value> = vec![];
+            for expr in expression {
+              match expr {
+                Expr::Identifier(i) => table_column_value.push(Value::Text(i.value.to_string())),
+                _ => table_column_value.push(evaluate_expression(expr, &RowContext::default())?),
+              }
+            }
 
-                  table_column_values.push(table_column_value);
-                }
-              };
-            };
+            table_column_values.push(table_column_value);
           }
-        }
+        };
       },
       _ => return Err(NollaDBError::Internal("Parsing INSERT SQL query error".to_string())),
     }
@@ -89,7 +83,48 @@ impl InsertQuery {
         table_column_names,
         table_column_values,
       }),
-      _ => return Err(NollaDBError::Internal("Parsing INSERT SQL query error".to_string())),
+      _ => Err(NollaDBError::Internal("Parsing INSERT SQL query error".to_string())),
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::sql_query::get_sql_ast;
+
+  #[rstest]
+  #[case(
+    "INSERT INTO test (id, name, score) VALUES (1, 'xxx', 1.5);",
+    vec!["id", "name", "score"],
+    vec![vec![Value::Integer(1), Value::Text("xxx".to_string()), Value::Real(1.5)]],
+  )]
+  #[case(
+    "INSERT INTO test (id, score) VALUES (-1, 2 * 3), (1 + 1, NULL);",
+    vec!["id", "score"],
+    vec![
+      vec![Value::Integer(-1), Value::Integer(6)],
+      vec![Value::Integer(2), Value::Null],
+    ],
+  )]
+  fn test_insert_query(
+    #[case] query: &str,
+    #[case] expected_table_column_names: Vec<&str>,
+    #[case] expected_table_column_values: Vec<Vec<Value>>,
+  ) {
+    let insert_query = InsertQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+
+    assert_eq!(insert_query.table_name, "test");
+    assert_eq!(insert_query.table_column_names, expected_table_column_names);
+    assert_eq!(insert_query.table_column_values, expected_table_column_values);
+  }
+
+  #[rstest]
+  #[case("INSERT INTO test (name, name) VALUES ('xxx', 'yyy');")]
+  #[case("INSERT INTO test (id, name, id) VALUES (1, 'xxx', 2);")]
+  fn test_insert_query_duplicate_column(#[case] query: &str) {
+    assert!(InsertQuery::new(&get_sql_ast(query).unwrap()).is_err());
+  }
+}
@@ -1,2 +1,9 @@
 pub mod create;
+pub mod create_index;
 pub mod insert;
+pub mod select;
+pub mod update;
+pub mod delete;
+pub mod drop;
+pub mod alter;
+pub mod explain;
@@ -0,0 +1,322 @@
+use sqlparser::ast::{
+  Statement,
+  Query,
+  SetExpr,
+  Select,
+  SelectItem,
+  TableWithJoins,
+  TableFactor,
+  Join,
+  JoinOperator,
+  JoinConstraint,
+  FunctionArg,
+  Expr,
+  OrderByExpr,
+};
+
+use crate::error::{Result, NollaDBError};
+
+#[derive(PartialEq, Debug)]
+pub enum JoinKind {
+  Inner,
+  LeftOuter,
+  Cross,
+}
+
+// 目前支持的表值函数
+const TABLE_FUNCTION_NAMES: [&str; 1] = ["JSON_EACH"];
+
+// FROM 中的一张表
+#[derive(Debug)]
+pub struct SelectTable {
+  pub table_name: String,
+  pub alias: Option<String>,
+  // 表值函数（比如 json_each(payload)）的参数，普通的表为 None
+  pub function_args: Option<Vec<Expr>>,
+  // 和前面的表怎样 JOIN，以及 ON 的条件，FROM 中的第一张表为 None
+  pub join: Option<(JoinKind, Option<Expr>)>,
+}
+
+impl SelectTable {
+  fn new(relation: &TableFactor, join: Option<(JoinKind, Option<Expr>)>) -> Result<SelectTable> {
+    match relation {
+      TableFactor::Table { name, alias, args, .. } => {
+        let function_args = if args.is_empty() {
+          None
+        } else if TABLE_FUNCTION_NAMES.contains(&name.to_string().to_uppercase().as_str()) {
+          Some(
+            args
+              .iter()
+              .map(|function_arg| match function_arg {
+                FunctionArg::Unnamed(arg) => Ok(arg.clone()),
+                FunctionArg::Named { .. } => Err(NollaDBError::Internal(
+                  format!("Named arguments are not supported in table function {}()", name)
+                )),
+              })
+              .collect::<Result<Vec<Expr>>>()?
+          )
+        } else {
+          return Err(NollaDBError::ToBeImplemented(
+            format!("Table function '{}' will be implemented soon", name)
+          ));
+        };
+
+        Ok(SelectTable {
+          table_name: name.to_string(),
+          alias: alias.as_ref().map(|alias| alias.name.to_string()),
+          function_args,
+          join,
+        })
+      },
+      _ => Err(NollaDBError::ToBeImplemented(
+        "SELECT from subquery or table function will be implemented soon".to_string()
+      )),
+    }
+  }
+
+  // SQL 里面引用这张表时用的名字，有别名的话就只能用别名
+  pub fn get_reference_name(&self) -> String {
+    match &self.alias {
+      Some(alias) => alias.to_string(),
+      None => self.table_name.to_string(),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct SelectQuery {
+  pub tables: Vec<SelectTable>,
+  pub projection: Vec<SelectItem>,
+  pub selection: Option<Expr>,
+  pub group_by: Vec<Expr>,
+  pub having: Option<Expr>,
+  pub order_by: Vec<OrderByExpr>,
+  pub limit: Option<Expr>,
+  pub offset: Option<Expr>,
+}
+
+impl SelectQuery {
+  pub fn new(statement: &Statement) -> Result<SelectQuery> {
+    let mut tables: Vec<SelectTable> = vec![];
+    let mut projection: Vec<SelectItem> = vec![];
+    #[allow(unused_assignments)]
+    let mut selection: Option<Expr> = None;
+    let mut group_by: Vec<Expr> = vec![];
+    #[allow(unused_assignments)]
+    let mut having: Option<Expr> = None;
+    let mut order_by: Vec<OrderByExpr> = vec![];
+    #[allow(unused_assignments)]
+    let mut limit: Option<Expr> = None;
+    #[allow(unused_assignments)]
+    let mut offset: Option<Expr> = None;
+
+    match statement {
+      Statement::Query(query) => {
+        let Query {
+          body,
+          order_by: query_order_by,
+          limit: query_limit,
+          offset: query_offset,
+          ..
+        } = &**query;
+
+        let select = match body {
+          SetExpr::Select(select) => select,
+          _ => return Err(NollaDBError::ToBeImplemented(
+            "Only simple SELECT statement is supported now".to_string()
+          )),
+        };
+        let Select {
+          projection: select_projection,
+          from,
+          selection: select_selection,
+          group_by: select_group_by,
+          having: select_having,
+          ..
+        } = &**select;
+
+        // 没有 FROM 的话 tables 为空，相当于只有一行并且没有任何列
+        // FROM a, b 相当于 a CROSS JOIN b
+        for (i, TableWithJoins { relation, joins }) in from.iter().enumerate() {
+          let join = if i == 0 { None } else { Some((JoinKind::Cross, None)) };
+          tables.push(SelectTable::new(relation, join)?);
+          for Join { relation, join_operator } in joins {
+            let join = match join_operator {
+              JoinOperator::Inner(join_constraint) =>
+                (JoinKind::Inner, get_join_constraint_expr(join_constraint)?),
+              JoinOperator::LeftOuter(join_constraint) =>
+                (JoinKind::LeftOuter, get_join_constraint_expr(join_constraint)?),
+              JoinOperator::CrossJoin => (JoinKind::Cross, None),
+              _ => return Err(NollaDBError::ToBeImplemented(
+                "RIGHT, FULL JOIN and APPLY will be implemented soon".to_string()
+              )),
+            };
+            tables.push(SelectTable::new(relation, Some(join))?);
+          }
+        }
+
+        for select_item in select_projection {
+          projection.push(select_item.clone());
+        }
+        selection = select_selection.clone();
+        for expr in select_group_by {
+          group_by.push(expr.clone());
+        }
+        having = select_having.clone();
+
+        for order_by_expr in query_order_by {
+          order_by.push(order_by_expr.clone());
+        }
+        limit = query_limit.clone();
+        offset = query_offset.as_ref().map(|query_offset| query_offset.value.clone());
+      },
+      _ => return Err(NollaDBError::Internal("Parsing SELECT SQL query error".to_string())),
+    }
+
+    Ok(SelectQuery {
+      tables,
+      projection,
+      selection,
+      group_by,
+      having,
+      order_by,
+      limit,
+      offset,
+    })
+  }
+}
+
+fn get_join_constraint_expr(join_constraint: &JoinConstraint) -> Result<Option<Expr>> {
+  match join_constraint {
+    JoinConstraint::On(expr) => Ok(Some(expr.clone())),
+    JoinConstraint::None => Ok(None),
+    _ => Err(NollaDBError::ToBeImplemented(
+      "JOIN with USING or NATURAL JOIN will be implemented soon".to_string()
+    )),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use sqlparser::parser::Parser;
+  use sqlparser::dialect::SQLiteDialect;
+
+  #[rstest]
+  #[case("SELECT * FROM test;", "test", 1, false)]
+  #[case("SELECT id, name AS n FROM test WHERE id = 1;", "test", 2, true)]
+  fn test_select_query(
+    #[case] query: &str,
+    #[case] expected_table_name: &str,
+    #[case] expected_projection_len: usize,
+    #[case] expected_has_selection: bool,
+  ) {
+    let dialect = SQLiteDialect {};
+    let mut ast = Parser::parse_sql(&dialect, query).unwrap();
+    let statement = ast.pop().unwrap();
+
+    match SelectQuery::new(&statement) {
+      Ok(select_query) => {
+        assert_eq!(select_query.tables[0].table_name, expected_table_name);
+        assert_eq!(select_query.projection.len(), expected_projection_len);
+        assert_eq!(select_query.selection.is_some(), expected_has_selection);
+      },
+      Err(error) => panic!("Error: {}", error),
+    }
+  }
+
+  #[rstest]
+  #[case("SELECT * FROM test ORDER BY id DESC, name LIMIT 10 OFFSET 5;", 2, true, true)]
+  #[case("SELECT * FROM test LIMIT 10;", 0, true, false)]
+  fn test_select_query_order_by_limit_offset(
+    #[case] query: &str,
+    #[case] expected_order_by_len: usize,
+    #[case] expected_has_limit: bool,
+    #[case] expected_has_offset: bool,
+  ) {
+    let dialect = SQLiteDialect {};
+    let mut ast = Parser::parse_sql(&dialect, query).unwrap();
+    let select_query = SelectQuery::new(&ast.pop().unwrap()).unwrap();
+
+    assert_eq!(select_query.order_by.len(), expected_order_by_len);
+    assert_eq!(select_query.limit.is_some(), expected_has_limit);
+    assert_eq!(select_query.offset.is_some(), expected_has_offset);
+  }
+
+  #[rstest]
+  #[case("SELECT name, COUNT(*) FROM test GROUP BY name HAVING COUNT(*) > 1;", 1, true)]
+  #[case("SELECT COUNT(*) FROM test;", 0, false)]
+  fn test_select_query_group_by_having(
+    #[case] query: &str,
+    #[case] expected_group_by_len: usize,
+    #[case] expected_has_having: bool,
+  ) {
+    let dialect = SQLiteDialect {};
+    let mut ast = Parser::parse_sql(&dialect, query).unwrap();
+    let select_query = SelectQuery::new(&ast.pop().unwrap()).unwrap();
+
+    assert_eq!(select_query.group_by.len(), expected_group_by_len);
+    assert_eq!(select_query.having.is_some(), expected_has_having);
+  }
+
+  #[rstest]
+  #[case("SELECT * FROM a AS x, b;", vec!["x", "b"], vec![None, Some(JoinKind::Cross)])]
+  #[case(
+    "SELECT * FROM a JOIN b ON a.id = b.id LEFT JOIN c AS z ON z.id = b.id CROSS JOIN d;",
+    vec!["a", "b", "z", "d"],
+    vec![None, Some(JoinKind::Inner), Some(JoinKind::LeftOuter), Some(JoinKind::Cross)]
+  )]
+  fn test_select_query_join(
+    #[case] query: &str,
+    #[case] expected_reference_names: Vec<&str>,
+    #[case] expected_join_kinds: Vec<Option<JoinKind>>,
+  ) {
+    let dialect = SQLiteDialect {};
+    let mut ast = Parser::parse_sql(&dialect, query).unwrap();
+    let select_query = SelectQuery::new(&ast.pop().unwrap()).unwrap();
+
+    let reference_names = select_query.tables
+      .iter()
+      .map(|select_table| select_table.get_reference_name())
+      .collect::<Vec<String>>();
+    let join_kinds = select_query.tables
+      .iter()
+      .map(|select_table| select_table.join.as_ref().map(|(join_kind, _)| join_kind))
+      .collect::<Vec<Option<&JoinKind>>>();
+
+    assert_eq!(reference_names, expected_reference_names);
+    assert_eq!(join_kinds, expected_join_kinds.iter().map(Option::as_ref).collect::<Vec<Option<&JoinKind>>>());
+  }
+
+  #[rstest]
+  #[case("SELECT * FROM json_each('[1, 2]');", Some(1))]
+  #[case("SELECT * FROM logs, JSON_EACH(logs.payload, '$.tags') AS tag;", Some(2))]
+  #[case("SELECT * FROM json_each;", None)]
+  fn test_select_query_table_function(
+    #[case] query: &str,
+    #[case] expected_function_args_len: Option<usize>,
+  ) {
+    let dialect = SQLiteDialect {};
+    let mut ast = Parser::parse_sql(&dialect, query).unwrap();
+    let select_query = SelectQuery::new(&ast.pop().unwrap()).unwrap();
+    let select_table = select_query.tables.last().unwrap();
+
+    assert_eq!(select_table.function_args.as_ref().map(Vec::len), expected_function_args_len);
+  }
+
+  #[rstest]
+  #[case("SELECT * FROM (SELECT * FROM test);")]
+  #[case("SELECT * FROM generate_series(1, 10);")]
+  #[case("SELECT * FROM a RIGHT JOIN b ON a.id = b.id;")]
+  #[case("SELECT * FROM a JOIN b USING (id);")]
+  #[case("SELECT id FROM a UNION SELECT id FROM b;")]
+  fn test_select_query_to_be_implemented(#[case] query: &str) {
+    let dialect = SQLiteDialect {};
+    let mut ast = Parser::parse_sql(&dialect, query).unwrap();
+    let statement = ast.pop().unwrap();
+
+    assert!(SelectQuery::new(&statement).is_err());
+  }
+}
@@ -0,0 +1,111 @@
+use sqlparser::ast::{
+  Statement,
+  TableFactor,
+  Expr,
+};
+
+use crate::error::{Result, NollaDBError};
+
+#[derive(Debug)]
+pub struct UpdateQuery {
+  pub table_name: String,
+  // (列名, 新的值对应的表达式)
+  pub assignments: Vec<(String, Expr)>,
+  pub selection: Option<Expr>,
+}
+
+impl UpdateQuery {
+  pub fn new(statement: &Statement) -> Result<UpdateQuery> {
+    #[allow(unused_assignments)]
+    let mut option_table_name: Option<String> = None;
+    let mut assignments: Vec<(String, Expr)> = vec![];
+    #[allow(unused_assignments)]
+    let mut selection: Option<Expr> = None;
+
+    match statement {
+      Statement::Update {
+        table,
+        assignments: update_assignments,
+        selection: update_selection,
+      } => {
+        if !table.joins.is_empty() {
+          return Err(NollaDBError::ToBeImplemented(
+            "UPDATE with JOIN will be implemented soon".to_string()
+          ));
+        }
+        match &table.relation {
+          TableFactor::Table { name, .. } => {
+            option_table_name = Some(name.to_string());
+          },
+          _ => return Err(NollaDBError::Internal("Parsing UPDATE SQL query error".to_string())),
+        }
+
+        for assignment in update_assignments {
+          // 类似于 SET test.name = 'xxx' 这种写法，取最后的列名
+          let column_name = match assignment.id.last() {
+            Some(ident) => ident.to_string(),
+            None => return Err(NollaDBError::Internal("Parsing UPDATE SQL query error".to_string())),
+          };
+
+          // 同一个列不能被 SET 多次
+          if assignments
+              .iter()
+              .any(|(assignment_column_name, _)| *assignment_column_name == column_name) {
+            return Err(
+              NollaDBError::Internal(
+                format!("Duplicate column name in SET: {}", &column_name)
+              )
+            );
+          }
+
+          assignments.push((column_name, assignment.value.clone()));
+        }
+        selection = update_selection.clone();
+      },
+      _ => return Err(NollaDBError::Internal("Parsing UPDATE SQL query error".to_string())),
+    }
+
+    match option_table_name {
+      Some(table_name) => Ok(UpdateQuery {
+        table_name,
+        assignments,
+        selection,
+      }),
+      _ => Err(NollaDBError::Internal("Parsing UPDATE SQL query error".to_string())),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+  use crate::sql_query::get_sql_ast;
+
+  #[rstest]
+  #[case("UPDATE test SET name = 'xxx' WHERE id = 1;", vec!["name"], true)]
+  #[case("UPDATE test SET name = 'xxx', score = score + 1;", vec!["name", "score"], false)]
+  fn test_update_query(
+    #[case] query: &str,
+    #[case] expected_column_names: Vec<&str>,
+    #[case] expected_has_selection: bool,
+  ) {
+    let update_query = UpdateQuery::new(&get_sql_ast(query).unwrap()).unwrap();
+    let column_names = update_query
+      .assignments
+      .iter()
+      .map(|(column_name, _)| column_name.as_str())
+      .collect::<Vec<&str>>();
+
+    assert_eq!(update_query.table_name, "test");
+    assert_eq!(column_names, expected_column_names);
+    assert_eq!(update_query.selection.is_some(), expected_has_selection);
+  }
+
+  #[rstest]
+  #[case("UPDATE test SET name = 'xxx', name = 'yyy';")]
+  fn test_update_query_error(#[case] query: &str) {
+    assert!(UpdateQuery::new(&get_sql_ast(query).unwrap()).is_err());
+  }
+}
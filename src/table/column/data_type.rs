@@ -2,6 +2,7 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
+// 保存到文件时是按照 variant 的顺序编码的，新的类型只能加在最后面
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
 pub enum DataType {
   Integer,
@@ -10,6 +11,12 @@ pub enum DataType {
   Real,
   None,
   Invalid,
+  Blob,
+  Date,
+  Time,
+  Timestamp,
+  Decimal,
+  Json,
 }
 
 impl DataType {
@@ -19,10 +26,16 @@ impl DataType {
       "text" => DataType::Text,
       "bool" => DataType::Bool,
       "real" => DataType::Real,
+      "blob" => DataType::Blob,
+      "date" => DataType::Date,
+      "time" => DataType::Time,
+      "timestamp" => DataType::Timestamp,
+      "decimal" => DataType::Decimal,
+      "json" => DataType::Json,
       "none" => DataType::None,
       _ => {
         eprintln!("Invalid datatype: {}", command);
-        return DataType::Invalid;
+        DataType::Invalid
       },
     }
   }
@@ -35,6 +48,12 @@ impl fmt::Display for DataType {
       DataType::Text => f.write_str("Text"),
       DataType::Bool => f.write_str("Boolean"),
       DataType::Real => f.write_str("Real"),
+      DataType::Blob => f.write_str("Blob"),
+      DataType::Date => f.write_str("Date"),
+      DataType::Time => f.write_str("Time"),
+      DataType::Timestamp => f.write_str("Timestamp"),
+      DataType::Decimal => f.write_str("Decimal"),
+      DataType::Json => f.write_str("Json"),
       DataType::None => f.write_str("None"),
       DataType::Invalid => f.write_str("Invalid"),
     }
@@ -1,12 +1,500 @@
-use std::collections::{BTreeMap};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::{Bound, RangeBounds};
 
 use serde::{Deserialize, Serialize};
 
+use crate::table::row::value::Value;
+use crate::table::row::date_time::{Date, Time, Timestamp};
+use crate::table::row::decimal::Decimal;
+use crate::table::row::json::Json;
+use crate::table::column::data_type::DataType;
+
+// f64 没有实现 Ord，不能直接作为 BTreeMap 的 key
+// 这里用 total_cmp 给它定义一个全序，并且把 -0.0 统一成 0.0，这样 -0.0 和 0.0 是同一个 key
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+pub struct OrderedReal(f64);
+
+impl OrderedReal {
+  pub fn new(r: f64) -> Self {
+    OrderedReal(if r == 0.0 { 0.0 } else { r })
+  }
+}
+
+impl PartialEq for OrderedReal {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for OrderedReal {}
+
+impl PartialOrd for OrderedReal {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for OrderedReal {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.0.total_cmp(&other.0)
+  }
+}
+
+// 组合 key 中一列的值，NULL 没有对应的 key
+// 保存到文件时是按照 variant 的顺序编码的，新的类型只能加在最后面
+#[derive(Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone)]
+pub enum IndexKey {
+  Integer(i64),
+  Text(String),
+  Real(OrderedReal),
+  Bool(bool),
+  Blob(Vec<u8>),
+  Date(Date),
+  Time(Time),
+  Timestamp(Timestamp),
+  Decimal(Decimal),
+  Json(Json),
+}
+
+impl IndexKey {
+  pub fn new(value: &Value) -> Option<IndexKey> {
+    match value {
+      Value::Integer(i) => Some(IndexKey::Integer(*i)),
+      Value::Text(s) => Some(IndexKey::Text(s.to_string())),
+      Value::Real(r) => Some(IndexKey::Real(OrderedReal::new(*r))),
+      Value::Bool(b) => Some(IndexKey::Bool(*b)),
+      Value::Blob(bytes) => Some(IndexKey::Blob(bytes.clone())),
+      Value::Date(d) => Some(IndexKey::Date(*d)),
+      Value::Time(t) => Some(IndexKey::Time(*t)),
+      Value::Timestamp(ts) => Some(IndexKey::Timestamp(*ts)),
+      Value::Decimal(d) => Some(IndexKey::Decimal(*d)),
+      Value::Json(json) => Some(IndexKey::Json(json.clone())),
+      Value::Null => None,
+    }
+  }
+}
+
+// 唯一索引一个值只对应一个 row id，非唯一索引一个值对应一组 row id
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+pub enum IndexTree<K: Ord> {
+  Unique(BTreeMap<K, i64>),
+  NonUnique(BTreeMap<K, BTreeSet<i64>>),
+}
+
+impl<K: Ord> IndexTree<K> {
+  pub fn new(is_unique: bool) -> Self {
+    if is_unique {
+      IndexTree::Unique(BTreeMap::new())
+    } else {
+      IndexTree::NonUnique(BTreeMap::new())
+    }
+  }
+
+  // 唯一索引直接返回 key 对应的 row id，不需要再分配一个 Vec
+  pub fn get_row_id(&self, key: &K) -> Option<i64> {
+    match self {
+      IndexTree::Unique(tree) => tree.get(key).cloned(),
+      IndexTree::NonUnique(tree) => tree.get(key).and_then(|row_ids| row_ids.iter().next().cloned()),
+    }
+  }
+
+  pub fn get_row_ids(&self, key: &K) -> Vec<i64> {
+    match self {
+      IndexTree::Unique(tree) => tree.get(key).into_iter().cloned().collect(),
+      IndexTree::NonUnique(tree) => tree.get(key).map_or(vec![], |row_ids| row_ids.iter().cloned().collect()),
+    }
+  }
+
+  pub fn get_ordered_row_ids(&self) -> Vec<i64> {
+    match self {
+      IndexTree::Unique(tree) => tree.values().cloned().collect(),
+      IndexTree::NonUnique(tree) => tree.values().flatten().cloned().collect(),
+    }
+  }
+
+  // 按照 key 的顺序拿到 key 在 [low, high] 范围内的所有 row id
+  pub fn get_range_row_ids(&self, low: Bound<&K>, high: Bound<&K>) -> Vec<i64> {
+    // BTreeMap::range 在 low > high 或者 low == high 且有一边是开区间的时候会 panic
+    if let (
+      Bound::Included(l) | Bound::Excluded(l),
+      Bound::Included(h) | Bound::Excluded(h),
+    ) = (low, high) {
+      let is_empty = match l.cmp(h) {
+        Ordering::Greater => true,
+        Ordering::Equal => matches!(low, Bound::Excluded(_)) || matches!(high, Bound::Excluded(_)),
+        Ordering::Less => false,
+      };
+      if is_empty { return vec![]; }
+    }
+
+    match self {
+      IndexTree::Unique(tree) => tree.range((low, high)).map(|(_, row_id)| *row_id).collect(),
+      IndexTree::NonUnique(tree) => tree.range((low, high)).flat_map(|(_, row_ids)| row_ids.iter().cloned()).collect(),
+    }
+  }
+
+  pub fn insert(&mut self, key: K, row_id: i64) {
+    match self {
+      IndexTree::Unique(tree) => { tree.insert(key, row_id); },
+      IndexTree::NonUnique(tree) => { tree.entry(key).or_default().insert(row_id); },
+    }
+  }
+
+  // 唯一索引只有 key 指向的是这个 row id 时才删除，避免把其他行的索引给删掉
+  pub fn remove(&mut self, key: &K, row_id: i64) {
+    match self {
+      IndexTree::Unique(tree) => {
+        if tree.get(key) == Some(&row_id) { tree.remove(key); }
+      },
+      IndexTree::NonUnique(tree) => {
+        if let Some(row_ids) = tree.get_mut(key) {
+          row_ids.remove(&row_id);
+          if row_ids.is_empty() { tree.remove(key); }
+        }
+      },
+    }
+  }
+}
+
+// Bool 只有 true 和 false 两个值，不需要 BTreeMap，每个值直接对应一组 row id（posting set）
+// 唯一索引每个值最多只有一个 row id，和 IndexTree::Unique 一样后写入的会替换掉原来的
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+pub struct BoolIndex {
+  is_unique: bool,
+  false_row_ids: BTreeSet<i64>,
+  true_row_ids: BTreeSet<i64>,
+}
+
+impl BoolIndex {
+  pub fn new(is_unique: bool) -> Self {
+    BoolIndex {
+      is_unique,
+      false_row_ids: BTreeSet::new(),
+      true_row_ids: BTreeSet::new(),
+    }
+  }
+
+  fn get_posting_set(&self, key: bool) -> &BTreeSet<i64> {
+    if key { &self.true_row_ids } else { &self.false_row_ids }
+  }
+
+  fn get_posting_set_mut(&mut self, key: bool) -> &mut BTreeSet<i64> {
+    if key { &mut self.true_row_ids } else { &mut self.false_row_ids }
+  }
+
+  pub fn get_row_id(&self, key: bool) -> Option<i64> {
+    self.get_posting_set(key).iter().next().cloned()
+  }
+
+  pub fn get_row_ids(&self, key: bool) -> Vec<i64> {
+    self.get_posting_set(key).iter().cloned().collect()
+  }
+
+  // false 排在 true 前面
+  pub fn get_ordered_row_ids(&self) -> Vec<i64> {
+    self.false_row_ids.iter().chain(self.true_row_ids.iter()).cloned().collect()
+  }
+
+  pub fn get_range_row_ids(&self, low: Bound<&bool>, high: Bound<&bool>) -> Vec<i64> {
+    [false, true]
+      .into_iter()
+      .filter(|key| (low, high).contains(key))
+      .flat_map(|key| self.get_posting_set(key).iter().cloned())
+      .collect()
+  }
+
+  pub fn insert(&mut self, key: bool, row_id: i64) {
+    let is_unique = self.is_unique;
+    let row_ids = self.get_posting_set_mut(key);
+    if is_unique { row_ids.clear(); }
+    row_ids.insert(row_id);
+  }
+
+  // 只删除这个 row id，唯一索引中 key 指向其他行的时候保持不变
+  pub fn remove(&mut self, key: bool, row_id: i64) {
+    self.get_posting_set_mut(key).remove(&row_id);
+  }
+}
+
+// 保存到文件时是按照 variant 的顺序编码的，新的类型只能加在最后面
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
 pub enum Index {
-  Integer(BTreeMap<i32, i64>),
-  Text(BTreeMap<String, i64>),
-  // Bool(BTreeMap<bool, i64>),
-  // Real(BTreeMap<f32, i64>),
+  Integer(IndexTree<i64>),
+  Text(IndexTree<String>),
+  Real(IndexTree<OrderedReal>),
+  Bool(BoolIndex),
   None,
+  Blob(IndexTree<Vec<u8>>),
+  Date(IndexTree<Date>),
+  Time(IndexTree<Time>),
+  Timestamp(IndexTree<Timestamp>),
+  Decimal(IndexTree<Decimal>),
+  // JSON 按照紧凑格式的文本建索引，只用来检查唯一性约束
+  Json(IndexTree<Json>),
+}
+
+// Index 里面存的是 值 -> row id
+impl Index {
+  // 创建一个和列类型对应的空的 Index，不支持索引的类型为 Index::None
+  pub fn new(column_datatype: &DataType, is_unique: bool) -> Self {
+    match column_datatype {
+      DataType::Integer => Index::Integer(IndexTree::new(is_unique)),
+      DataType::Text => Index::Text(IndexTree::new(is_unique)),
+      DataType::Real => Index::Real(IndexTree::new(is_unique)),
+      DataType::Blob => Index::Blob(IndexTree::new(is_unique)),
+      DataType::Date => Index::Date(IndexTree::new(is_unique)),
+      DataType::Time => Index::Time(IndexTree::new(is_unique)),
+      DataType::Timestamp => Index::Timestamp(IndexTree::new(is_unique)),
+      DataType::Decimal => Index::Decimal(IndexTree::new(is_unique)),
+      DataType::Json => Index::Json(IndexTree::new(is_unique)),
+      DataType::Bool => Index::Bool(BoolIndex::new(is_unique)),
+      _ => Index::None,
+    }
+  }
+
+  // 值对应的第一个 row id，唯一索引用来检查唯一性约束以及点查
+  pub fn get_row_id(&self, value: &Value) -> Option<i64> {
+    match (self, value) {
+      (Index::Integer(tree), Value::Integer(i)) => tree.get_row_id(i),
+      (Index::Text(tree), Value::Text(s)) => tree.get_row_id(s),
+      (Index::Real(tree), Value::Real(r)) => tree.get_row_id(&OrderedReal::new(*r)),
+      (Index::Bool(bool_index), Value::Bool(b)) => bool_index.get_row_id(*b),
+      (Index::Blob(tree), Value::Blob(bytes)) => tree.get_row_id(bytes),
+      (Index::Date(tree), Value::Date(d)) => tree.get_row_id(d),
+      (Index::Time(tree), Value::Time(t)) => tree.get_row_id(t),
+      (Index::Timestamp(tree), Value::Timestamp(ts)) => tree.get_row_id(ts),
+      (Index::Decimal(tree), Value::Decimal(d)) => tree.get_row_id(d),
+      (Index::Json(tree), Value::Json(json)) => tree.get_row_id(json),
+      _ => None,
+    }
+  }
+
+  // 值对应的所有 row id，按照 row id 的顺序排列
+  pub fn get_row_ids(&self, value: &Value) -> Vec<i64> {
+    match (self, value) {
+      (Index::Integer(tree), Value::Integer(i)) => tree.get_row_ids(i),
+      (Index::Text(tree), Value::Text(s)) => tree.get_row_ids(s),
+      (Index::Real(tree), Value::Real(r)) => tree.get_row_ids(&OrderedReal::new(*r)),
+      (Index::Bool(bool_index), Value::Bool(b)) => bool_index.get_row_ids(*b),
+      (Index::Blob(tree), Value::Blob(bytes)) => tree.get_row_ids(bytes),
+      (Index::Date(tree), Value::Date(d)) => tree.get_row_ids(d),
+      (Index::Time(tree), Value::Time(t)) => tree.get_row_ids(t),
+      (Index::Timestamp(tree), Value::Timestamp(ts)) => tree.get_row_ids(ts),
+      (Index::Decimal(tree), Value::Decimal(d)) => tree.get_row_ids(d),
+      (Index::Json(tree), Value::Json(json)) => tree.get_row_ids(json),
+      _ => vec![],
+    }
+  }
+
+  // 按照索引中 key 的顺序拿到所有的 row id
+  pub fn get_ordered_row_ids(&self) -> Vec<i64> {
+    match self {
+      Index::Integer(tree) => tree.get_ordered_row_ids(),
+      Index::Text(tree) => tree.get_ordered_row_ids(),
+      Index::Real(tree) => tree.get_ordered_row_ids(),
+      Index::Bool(bool_index) => bool_index.get_ordered_row_ids(),
+      Index::Blob(tree) => tree.get_ordered_row_ids(),
+      Index::Date(tree) => tree.get_ordered_row_ids(),
+      Index::Time(tree) => tree.get_ordered_row_ids(),
+      Index::Timestamp(tree) => tree.get_ordered_row_ids(),
+      Index::Decimal(tree) => tree.get_ordered_row_ids(),
+      Index::Json(tree) => tree.get_ordered_row_ids(),
+      Index::None => vec![],
+    }
+  }
+
+  // 范围查询，边界的类型需要和索引的类型一致，NULL 的边界什么都查不到
+  pub fn get_range_row_ids(&self, low: Bound<&Value>, high: Bound<&Value>) -> Vec<i64> {
+    match self {
+      Index::Integer(tree) => get_range_row_ids(tree, low, high, |value| match value { Value::Integer(i) => Some(*i), _ => None }),
+      Index::Text(tree) => get_range_row_ids(tree, low, high, |value| match value { Value::Text(s) => Some(s.to_string()), _ => None }),
+      Index::Real(tree) => get_range_row_ids(tree, low, high, |value| match value { Value::Real(r) => Some(OrderedReal::new(*r)), _ => None }),
+      Index::Bool(bool_index) => match (get_key_bound(low, get_bool), get_key_bound(high, get_bool)) {
+        (Some(low), Some(high)) => bool_index.get_range_row_ids(low.as_ref(), high.as_ref()),
+        _ => vec![],
+      },
+      Index::Blob(tree) => get_range_row_ids(tree, low, high, |value| match value { Value::Blob(bytes) => Some(bytes.clone()), _ => None }),
+      Index::Date(tree) => get_range_row_ids(tree, low, high, |value| match value { Value::Date(d) => Some(*d), _ => None }),
+      Index::Time(tree) => get_range_row_ids(tree, low, high, |value| match value { Value::Time(t) => Some(*t), _ => None }),
+      Index::Timestamp(tree) => get_range_row_ids(tree, low, high, |value| match value { Value::Timestamp(ts) => Some(*ts), _ => None }),
+      Index::Decimal(tree) => get_range_row_ids(tree, low, high, |value| match value { Value::Decimal(d) => Some(*d), _ => None }),
+      Index::Json(tree) => get_range_row_ids(tree, low, high, |value| match value { Value::Json(json) => Some(json.clone()), _ => None }),
+      Index::None => vec![],
+    }
+  }
+
+  // NULL 不会写入索引
+  pub fn insert(&mut self, value: &Value, row_id: i64) {
+    match (self, value) {
+      (Index::Integer(tree), Value::Integer(i)) => tree.insert(*i, row_id),
+      (Index::Text(tree), Value::Text(s)) => tree.insert(s.to_string(), row_id),
+      (Index::Real(tree), Value::Real(r)) => tree.insert(OrderedReal::new(*r), row_id),
+      (Index::Bool(bool_index), Value::Bool(b)) => bool_index.insert(*b, row_id),
+      (Index::Blob(tree), Value::Blob(bytes)) => tree.insert(bytes.clone(), row_id),
+      (Index::Date(tree), Value::Date(d)) => tree.insert(*d, row_id),
+      (Index::Time(tree), Value::Time(t)) => tree.insert(*t, row_id),
+      (Index::Timestamp(tree), Value::Timestamp(ts)) => tree.insert(*ts, row_id),
+      (Index::Decimal(tree), Value::Decimal(d)) => tree.insert(*d, row_id),
+      (Index::Json(tree), Value::Json(json)) => tree.insert(json.clone(), row_id),
+      _ => (),
+    }
+  }
+
+  pub fn remove(&mut self, value: &Value, row_id: i64) {
+    match (self, value) {
+      (Index::Integer(tree), Value::Integer(i)) => tree.remove(i, row_id),
+      (Index::Text(tree), Value::Text(s)) => tree.remove(s, row_id),
+      (Index::Real(tree), Value::Real(r)) => tree.remove(&OrderedReal::new(*r), row_id),
+      (Index::Bool(bool_index), Value::Bool(b)) => bool_index.remove(*b, row_id),
+      (Index::Blob(tree), Value::Blob(bytes)) => tree.remove(bytes, row_id),
+      (Index::Date(tree), Value::Date(d)) => tree.remove(d, row_id),
+      (Index::Time(tree), Value::Time(t)) => tree.remove(t, row_id),
+      (Index::Timestamp(tree), Value::Timestamp(ts)) => tree.remove(ts, row_id),
+      (Index::Decimal(tree), Value::Decimal(d)) => tree.remove(d, row_id),
+      (Index::Json(tree), Value::Json(json)) => tree.remove(json, row_id),
+      _ => (),
+    }
+  }
+}
+
+// 把 Value 的边界转换成索引 key 的边界，类型不对的话返回 None
+fn get_key_bound<K>(bound: Bound<&Value>, get_key: impl Fn(&Value) -> Option<K>) -> Option<Bound<K>> {
+  match bound {
+    Bound::Included(value) => get_key(value).map(Bound::Included),
+    Bound::Excluded(value) => get_key(value).map(Bound::Excluded),
+    Bound::Unbounded => Some(Bound::Unbounded),
+  }
+}
+
+fn get_bool(value: &Value) -> Option<bool> {
+  match value {
+    Value::Bool(b) => Some(*b),
+    _ => None,
+  }
+}
+
+fn get_range_row_ids<K: Ord>(
+  tree: &IndexTree<K>,
+  low: Bound<&Value>,
+  high: Bound<&Value>,
+  get_key: impl Fn(&Value) -> Option<K>,
+) -> Vec<i64> {
+  match (get_key_bound(low, &get_key), get_key_bound(high, &get_key)) {
+    (Some(low), Some(high)) => tree.get_range_row_ids(low.as_ref(), high.as_ref()),
+    _ => vec![],
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+
+  #[rstest]
+  #[case(DataType::Real, vec![Value::Real(2.5), Value::Real(-1.0), Value::Real(-0.0)], vec![2, 3, 1])]
+  #[case(DataType::Bool, vec![Value::Bool(true), Value::Bool(false)], vec![2, 1])]
+  fn test_real_and_bool_index(
+    #[case] column_datatype: DataType,
+    #[case] values: Vec<Value>,
+    #[case] expected_ordered_row_ids: Vec<i64>,
+  ) {
+    let mut index = Index::new(&column_datatype, true);
+    for (row_id, value) in values.iter().enumerate() {
+      index.insert(value, row_id as i64 + 1);
+    }
+    assert_eq!(index.get_ordered_row_ids(), expected_ordered_row_ids);
+
+    for (row_id, value) in values.iter().enumerate() {
+      index.remove(value, row_id as i64 + 1);
+    }
+    assert_eq!(index, Index::new(&column_datatype, true));
+  }
+
+  #[rstest]
+  #[case(true, vec![3], vec![2, 3], vec![3])]
+  #[case(false, vec![1, 3], vec![2, 1, 3], vec![1, 3])]
+  fn test_bool_index(
+    #[case] is_unique: bool,
+    #[case] expected_row_ids: Vec<i64>,
+    #[case] expected_ordered_row_ids: Vec<i64>,
+    #[case] expected_range_row_ids: Vec<i64>,
+  ) {
+    let mut index = Index::new(&DataType::Bool, is_unique);
+    index.insert(&Value::Bool(true), 1);
+    index.insert(&Value::Bool(false), 2);
+    index.insert(&Value::Bool(true), 3);
+    index.insert(&Value::Null, 4);
+    assert_eq!(index.get_row_ids(&Value::Bool(true)), expected_row_ids);
+    assert_eq!(index.get_ordered_row_ids(), expected_ordered_row_ids);
+    assert_eq!(
+      index.get_range_row_ids(Bound::Excluded(&Value::Bool(false)), Bound::Unbounded),
+      expected_range_row_ids,
+    );
+    assert!(index.get_range_row_ids(Bound::Included(&Value::Bool(true)), Bound::Excluded(&Value::Bool(true))).is_empty());
+    assert!(index.get_range_row_ids(Bound::Included(&Value::Integer(1)), Bound::Unbounded).is_empty());
+
+    // 删掉的不是 key 指向的 row id 的话，唯一索引保持不变
+    index.remove(&Value::Bool(true), 1);
+    assert_eq!(index.get_row_id(&Value::Bool(true)), Some(3));
+    index.remove(&Value::Bool(true), 3);
+    assert_eq!(index.get_row_id(&Value::Bool(true)), None);
+    assert_eq!(index.get_row_id(&Value::Bool(false)), Some(2));
+  }
+
+  #[test]
+  fn test_real_index_zero() {
+    let mut index = Index::new(&DataType::Real, true);
+    index.insert(&Value::Real(-0.0), 1);
+
+    assert_eq!(index.get_row_id(&Value::Real(0.0)), Some(1));
+    assert_eq!(index.get_row_id(&Value::Real(1.0)), None);
+  }
+
+  #[rstest]
+  #[case(Bound::Included(2), Bound::Included(4), vec![2, 3, 4])]
+  #[case(Bound::Excluded(2), Bound::Unbounded, vec![3, 4, 5])]
+  #[case(Bound::Unbounded, Bound::Excluded(2), vec![1])]
+  #[case(Bound::Included(3), Bound::Excluded(3), vec![])]
+  #[case(Bound::Included(4), Bound::Included(2), vec![])]
+  fn test_get_range_row_ids(
+    #[case] low: Bound<i64>,
+    #[case] high: Bound<i64>,
+    #[case] expected: Vec<i64>,
+    #[values(true, false)] is_unique: bool,
+  ) {
+    let mut index = Index::new(&DataType::Integer, is_unique);
+    for i in 1..=5 {
+      index.insert(&Value::Integer(i), i);
+    }
+    let low = low.map(Value::Integer);
+    let high = high.map(Value::Integer);
+
+    assert_eq!(index.get_range_row_ids(low.as_ref(), high.as_ref()), expected);
+    assert!(index.get_range_row_ids(Bound::Included(&Value::Text("a".to_string())), Bound::Unbounded).is_empty());
+  }
+
+  #[rstest]
+  #[case(true, vec![3, 2], vec![2])]
+  #[case(false, vec![3, 1, 2], vec![1, 2])]
+  fn test_unique_and_non_unique_index(
+    #[case] is_unique: bool,
+    #[case] expected_ordered_row_ids: Vec<i64>,
+    #[case] expected_row_ids: Vec<i64>,
+  ) {
+    let mut index = Index::new(&DataType::Text, is_unique);
+    index.insert(&Value::Text("b".to_string()), 1);
+    index.insert(&Value::Text("b".to_string()), 2);
+    index.insert(&Value::Text("a".to_string()), 3);
+    index.insert(&Value::Null, 4);
+    assert_eq!(index.get_ordered_row_ids(), expected_ordered_row_ids);
+    assert_eq!(index.get_row_ids(&Value::Text("b".to_string())), expected_row_ids);
+
+    // 删掉的不是 key 指向的 row id 的话，唯一索引保持不变
+    index.remove(&Value::Text("b".to_string()), 1);
+    assert_eq!(index.get_row_ids(&Value::Text("b".to_string())), vec![2]);
+    index.remove(&Value::Text("b".to_string()), 2);
+    assert_eq!(index.get_row_id(&Value::Text("b".to_string())), None);
+  }
 }
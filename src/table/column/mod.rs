@@ -1,9 +1,12 @@
 pub mod index;
 pub mod data_type;
 
-use std::collections::{BTreeMap};
-
 use serde::{Deserialize, Serialize};
+use sqlparser::ast::Expr;
+
+use crate::error::{Result, NollaDBError};
+use crate::sql_query::expression::sql_text;
+use crate::table::row::value::Value;
 
 use index::Index;
 use data_type::DataType;
@@ -17,6 +20,14 @@ pub struct Column {
   pub is_not_null_constraint: bool,
   pub is_indexed: bool,
   pub index: Index,
+  // INSERT 时没有写到这一列的话用 DEFAULT 的值，没有 DEFAULT 的话是 NULL
+  #[serde(with = "sql_text::option")]
+  pub default_expr: Option<Expr>,
+  // 写在这一列上的 CHECK 约束，也可以引用同一行的其他列
+  #[serde(with = "sql_text::vec")]
+  pub check_exprs: Vec<Expr>,
+  // DECIMAL(p, s) 的 p 和 s，写入的值保留 s 位小数，最多 p 位有效数字
+  pub decimal_precision: Option<(u32, u32)>,
 }
 
 impl Column {
@@ -28,14 +39,10 @@ impl Column {
     is_not_null_constraint: bool,
   ) -> Self {
     let cd = DataType::new(column_datatype);
-    let index = match cd {
-      DataType::Integer => Index::Integer(BTreeMap::new()),
-      DataType::Text => Index::Text(BTreeMap::new()),
-      DataType::Bool => Index::None,
-      DataType::Real => Index::None,
-      DataType::None => Index::None,
-      DataType::Invalid => Index::None,
-    };
+    // PRIMARY KEY 和 UNIQUE 的列默认有索引，用来检查唯一性约束
+    // 其他的列需要通过 CREATE INDEX 创建
+    let is_indexed = is_primary_key || is_unique_constraint;
+    let index = if is_indexed { Index::new(&cd, true) } else { Index::None };
 
     Column {
       column_name,
@@ -43,12 +50,31 @@ impl Column {
       is_primary_key,
       is_unique_constraint,
       is_not_null_constraint,
-      is_indexed: is_primary_key,
+      is_indexed,
       index,
+      default_expr: None,
+      check_exprs: vec![],
+      decimal_precision: None,
     }
   }
 
   pub fn get_index_mut(&mut self) -> &mut Index {
     &mut self.index
   }
+
+  // 按照 DECIMAL(p, s) 四舍五入，整数部分超出范围的话报错，value 需要先转换成这一列的类型
+  pub fn round_decimal(&self, value: Value) -> Result<Value> {
+    match (self.decimal_precision, value) {
+      (Some((precision, scale)), Value::Decimal(d)) => d
+        .round_to_precision(precision, scale)
+        .map(Value::Decimal)
+        .ok_or_else(|| NollaDBError::TypeMismatch(
+          format!(
+            "Value '{}' is out of range for DECIMAL({}, {}) column {}",
+            d, precision, scale, self.column_name
+          )
+        )),
+      (_, value) => Ok(value),
+    }
+  }
 }
@@ -0,0 +1,59 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+// 父表中被引用的行删除或者更新时，子表中引用了它的行怎么处理
+// 没有事务，所以 NO ACTION 和 RESTRICT 一样，都是直接报错
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone, Copy)]
+pub enum ForeignKeyAction {
+  Restrict,
+  Cascade,
+  SetNull,
+}
+
+impl fmt::Display for ForeignKeyAction {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ForeignKeyAction::Restrict => f.write_str("RESTRICT"),
+      ForeignKeyAction::Cascade => f.write_str("CASCADE"),
+      ForeignKeyAction::SetNull => f.write_str("SET NULL"),
+    }
+  }
+}
+
+// 子表的 column_names 引用父表 foreign_table_name 中的 referred_column_names
+// 被引用的列必须是父表的 PRIMARY KEY 或者 UNIQUE 约束
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+pub struct ForeignKey {
+  pub column_names: Vec<String>,
+  pub foreign_table_name: String,
+  pub referred_column_names: Vec<String>,
+  pub on_delete: ForeignKeyAction,
+  pub on_update: ForeignKeyAction,
+}
+
+impl ForeignKey {
+  // 和 SQLite 一样，违反约束时列出子表中外键的每一列，比如 orders.user_id
+  pub fn get_qualified_column_names(&self, table_name: &str) -> String {
+    self
+      .column_names
+      .iter()
+      .map(|column_name| format!("{}.{}", table_name, column_name))
+      .collect::<Vec<String>>()
+      .join(", ")
+  }
+}
+
+impl fmt::Display for ForeignKey {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "FOREIGN KEY ({}) REFERENCES {} ({}) ON DELETE {} ON UPDATE {}",
+      self.column_names.join(", "),
+      self.foreign_table_name,
+      self.referred_column_names.join(", "),
+      self.on_delete,
+      self.on_update
+    )
+  }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ConstraintKind;
+
+use super::row::Row;
+use super::row::value::Value;
+use super::column::index::{IndexTree, IndexKey};
+
+// 由多列组成的 PRIMARY KEY 或者 UNIQUE 约束
+// 只有一列的约束直接用列上的索引来检查，不会用到这个
+#[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
+pub struct TableKey {
+  pub column_names: Vec<String>,
+  pub is_primary: bool,
+  // 每一列的值组成的数组 -> row id
+  pub index: IndexTree<Vec<IndexKey>>,
+}
+
+impl TableKey {
+  pub fn new(column_names: Vec<String>, is_primary: bool) -> Self {
+    TableKey {
+      column_names,
+      is_primary,
+      index: IndexTree::new(true),
+    }
+  }
+
+  // 只要有一列是 NULL 就没有 key，NULL 和任何值都不相等，所以不会违反唯一性约束
+  fn get_key(values: &[Value]) -> Option<Vec<IndexKey>> {
+    values.iter().map(IndexKey::new).collect()
+  }
+
+  // values 按照 column_names 的顺序排列，并且已经转换成了每一列对应的类型
+  pub fn get_row_id(&self, values: &[Value]) -> Option<i64> {
+    TableKey::get_key(values).and_then(|key| self.index.get_row_id(&key))
+  }
+
+  pub fn insert(&mut self, values: &[Value], row_id: i64) {
+    if let Some(key) = TableKey::get_key(values) {
+      self.index.insert(key, row_id);
+    }
+  }
+
+  pub fn remove(&mut self, values: &[Value], row_id: i64) {
+    if let Some(key) = TableKey::get_key(values) {
+      self.index.remove(&key, row_id);
+    }
+  }
+
+  // 按照 column_names 的顺序拿到一行在这些列上的值
+  pub fn get_values(&self, table_rows_data: &HashMap<String, Row>, row_id: &i64) -> Vec<Value> {
+    self
+      .column_names
+      .iter()
+      .map(|column_name| table_rows_data
+        .get(column_name)
+        .map_or(Value::Null, |row| row.get_value(row_id))
+      )
+      .collect()
+  }
+
+  pub fn get_constraint_kind(&self) -> ConstraintKind {
+    if self.is_primary { ConstraintKind::PrimaryKey } else { ConstraintKind::Unique }
+  }
+
+  // 和 SQLite 一样，违反约束时列出 key 中的每一列，比如 test.a, test.b
+  pub fn get_qualified_column_names(&self, table_name: &str) -> String {
+    self
+      .column_names
+      .iter()
+      .map(|column_name| format!("{}.{}", table_name, column_name))
+      .collect::<Vec<String>>()
+      .join(", ")
+  }
+}
+
+impl fmt::Display for TableKey {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} ({})", self.get_constraint_kind(), self.column_names.join(", "))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::{assert_eq};
+
+  #[test]
+  fn test_table_key() {
+    let mut table_key = TableKey::new(vec!["a".to_string(), "b".to_string()], true);
+    table_key.insert(&[Value::Integer(1), Value::Text("x".to_string())], 1);
+    table_key.insert(&[Value::Integer(1), Value::Null], 2);
+
+    assert_eq!(table_key.get_row_id(&[Value::Integer(1), Value::Text("x".to_string())]), Some(1));
+    assert_eq!(table_key.get_row_id(&[Value::Integer(1), Value::Text("y".to_string())]), None);
+    assert_eq!(table_key.get_row_id(&[Value::Integer(1), Value::Null]), None);
+    assert_eq!(table_key.to_string(), "PRIMARY KEY (a, b)");
+    assert_eq!(table_key.get_qualified_column_names("test"), "test.a, test.b");
+
+    table_key.remove(&[Value::Integer(1), Value::Text("x".to_string())], 2);
+    assert_eq!(table_key.get_row_id(&[Value::Integer(1), Value::Text("x".to_string())]), Some(1));
+    table_key.remove(&[Value::Integer(1), Value::Text("x".to_string())], 1);
+    assert_eq!(table_key, TableKey::new(vec!["a".to_string(), "b".to_string()], true));
+  }
+}
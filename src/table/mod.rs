@@ -1,11 +1,14 @@
-mod row;
-mod column;
+pub mod row;
+pub mod column;
+pub mod key;
+pub mod foreign_key;
 
-use std::collections::{HashMap, BTreeMap};
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::cell::RefCell;
 
 use serde::{Deserialize, Serialize};
+use sqlparser::ast::Expr;
 use prettytable::{
   Table as PrintTable,
   Row as PrintRow,
@@ -15,20 +18,43 @@ use prettytable::{
 use crate::sql_query::query::create::{
   CreateQuery,
   SchemaOfSQLColumn,
+  SchemaOfSQLKey,
+};
+use crate::error::{Result, NollaDBError, ConstraintKind};
+use crate::sql_query::expression::{
+  RowContext,
+  evaluate_expression,
+  evaluate_check,
+  visit_column_idents,
+  is_column_referenced,
+  sql_text,
 };
-use crate::error::{Result, NollaDBError};
 
 use row::Row;
+use row::value::Value;
 use column::Column;
 use column::data_type::DataType;
 use column::index::Index;
+use key::TableKey;
+use foreign_key::ForeignKey;
 
 #[derive(Deserialize, Serialize, PartialEq, Debug, Clone)]
 pub struct Table {
-  pub primary_key: String,
+  // PRIMARY KEY 由哪些列组成，没有 PRIMARY KEY 的话为空
+  pub primary_key: Vec<String>,
+  // 由多列组成的 PRIMARY KEY 以及 UNIQUE 约束
+  pub table_keys: Vec<TableKey>,
+  // 这张表上的外键，引用的是其他表或者自己的 PRIMARY KEY 或者 UNIQUE 约束
+  pub foreign_keys: Vec<ForeignKey>,
+  // 表级别的 CHECK 约束，列上的 CHECK 约束放在对应的 Column 里面
+  #[serde(with = "sql_text::vec")]
+  pub check_exprs: Vec<Expr>,
   pub table_name: String,
   pub indexes: HashMap<String, String>,
+  // 曾经分配过的最大的 row id，AUTOINCREMENT 的表用它来保证删除了的 row id 不会再次被使用
   pub most_recent_row_id: i64,
+  // INTEGER PRIMARY KEY AUTOINCREMENT
+  pub is_autoincrement: bool,
   pub table_rows: Rc<RefCell<HashMap<String, Row>>>,
   pub table_columns: Vec<Column>,
 }
@@ -36,16 +62,21 @@ pub struct Table {
 impl Table {
   // new 表示初始化一张 table，要初始化肯定是先创建
   pub fn new(create_query: CreateQuery) -> Self {
-    // PRIMARY KEY 先默认 -1
-    let mut primary_key: String = String::from("-1");
+    let mut primary_key: Vec<String> = vec![];
 
     let CreateQuery {
       table_name,
       table_metadata_columns,
+      table_metadata_keys,
+      table_metadata_foreign_keys,
+      table_metadata_checks,
     } = create_query;
 
     let indexes = HashMap::new();
     let most_recent_row_id = 0;
+    let is_autoincrement = table_metadata_columns
+      .iter()
+      .any(|table_metadata_column| table_metadata_column.is_autoincrement);
 
     // table rows 是由 RefCell 指针管理的 HashMap
     let table_rows: Rc<RefCell<HashMap<String, Row>>>
@@ -60,75 +91,60 @@ impl Table {
         is_primary_key,
         is_unique_constraint,
         is_not_null_constraint,
+        default_expr,
+        check_exprs,
+        decimal_precision,
+        ..
       } = &table_metadata_column;
 
       // 如果是 PRIMARY KEY，说明需要列名就是 PRIMARY KEY
       if *is_primary_key {
-        primary_key = column_name.to_string();
+        primary_key = vec![column_name.to_string()];
       }
 
       // 构建 table rows
-      match DataType::new(column_datatype.to_string()) {
-        DataType::Integer => table_rows
-          .clone()
-          // 获取一个可变引用，配合 RefCell 使用
-          .borrow_mut()
-          .insert(
-            column_name.to_string(),
-            Row::Integer(BTreeMap::new())
-          ),
-        DataType::Text => table_rows
-          .clone()
-          .borrow_mut()
-          .insert(
-            column_name.to_string(),
-            Row::Text(BTreeMap::new())
-          ),
-        DataType::Bool => table_rows
-          .clone()
-          .borrow_mut()
-          .insert(
-            column_name.to_string(),
-            Row::Bool(BTreeMap::new())
-          ),
-        DataType::Real => table_rows
-          .clone()
-          .borrow_mut()
-          .insert(
-            column_name.to_string(),
-            Row::Real(BTreeMap::new())
-          ),
-        DataType::None => table_rows
-          .clone()
-          .borrow_mut()
-          .insert(
-            column_name.to_string(),
-            Row::None
-          ),
-        DataType::Invalid => table_rows
-          .clone()
-          .borrow_mut()
-          .insert(
-            column_name.to_string(),
-            Row::None
-          ),
-      };
+      table_rows
+        .clone()
+        // 获取一个可变引用，配合 RefCell 使用
+        .borrow_mut()
+        .insert(
+          column_name.to_string(),
+          Row::new(&DataType::new(column_datatype.to_string()))
+        );
 
       // 构建 table columns
-      table_columns.push(Column::new(
+      let mut table_column = Column::new(
         column_name.to_string(),
         column_datatype.to_string(),
         *is_primary_key,
         *is_unique_constraint,
         *is_not_null_constraint,
-      ));
+      );
+      table_column.default_expr = default_expr.clone();
+      table_column.check_exprs = check_exprs.clone();
+      table_column.decimal_precision = *decimal_precision;
+      table_columns.push(table_column);
     }
 
+    let table_keys = table_metadata_keys
+      .into_iter()
+      .map(|SchemaOfSQLKey { column_names, is_primary }| {
+        if is_primary {
+          primary_key = column_names.clone();
+        }
+        TableKey::new(column_names, is_primary)
+      })
+      .collect::<Vec<TableKey>>();
+
     Table {
       primary_key,
+      table_keys,
+      foreign_keys: table_metadata_foreign_keys,
+      check_exprs: table_metadata_checks,
       table_name,
       indexes,
       most_recent_row_id,
+      is_autoincrement,
       table_rows,
       table_columns,
     }
@@ -142,7 +158,7 @@ impl Table {
   }
 
   #[allow(dead_code)]
-  pub fn get_column(&mut self, column_name: String) -> Result<&Column> {
+  pub fn get_column(&self, column_name: String) -> Result<&Column> {
     for table_column in self.table_columns.iter() {
       if table_column.column_name == column_name {
         return Ok(table_column);
@@ -163,50 +179,181 @@ impl Table {
     Err(NollaDBError::General(String::from("Column not found")))
   }
 
+  // 拿到表中所有记录的 row id
+  // 每个 column 的 BTreeMap 都存着所有的 row id，所以取第一个 column 就好
+  pub fn get_row_ids(&self) -> Vec<i64> {
+    let table_rows_data = self.table_rows.as_ref().borrow();
+    match self.table_columns.first() {
+      Some(table_column) => table_rows_data
+        .get(&table_column.column_name)
+        .map_or(vec![], |row| row.get_row_ids()),
+      None => vec![],
+    }
+  }
+
+  // 拿到某一列上 row id 对应的值
+  pub fn get_value(&self, column_name: &str, row_id: &i64) -> Result<Value> {
+    let table_rows_data = self.table_rows.as_ref().borrow();
+    match table_rows_data.get(column_name) {
+      Some(row) => Ok(row.get_value(row_id)),
+      None => Err(NollaDBError::General(
+        format!("Column '{}' not found", column_name)
+      )),
+    }
+  }
+
+  // 拿到某一列上的多个值，只需要借用一次 table_rows
+  // row_ids 为 None 的时候直接扫描整列，不在这一列上的（即 NULL）不会出现在结果里面
+  pub fn get_column_values(&self, column_name: &str, row_ids: Option<&[i64]>) -> Result<Vec<Value>> {
+    let table_rows_data = self.table_rows.as_ref().borrow();
+    match table_rows_data.get(column_name) {
+      Some(row) => Ok(match row_ids {
+        Some(row_ids) => row_ids.iter().map(|row_id| row.get_value(row_id)).collect(),
+        None => row.get_values(),
+      }),
+      None => Err(NollaDBError::General(
+        format!("Column '{}' not found", column_name)
+      )),
+    }
+  }
+
+  // Integer 类型的单列 PRIMARY KEY 就是 row id，返回它的列名
+  pub fn get_integer_primary_key(&self) -> Option<&str> {
+    match self.primary_key.as_slice() {
+      [column_name] => match self.get_column(column_name.to_string()) {
+        Ok(table_column) if table_column.column_datatype == DataType::Integer => Some(column_name),
+        _ => None,
+      },
+      _ => None,
+    }
+  }
+
+  // 这些列是不是正好组成了一个 PRIMARY KEY 或者 UNIQUE 约束，外键只能引用这样的列
+  pub fn is_unique_key(&self, column_names: &[String]) -> bool {
+    match column_names {
+      [column_name] => self
+        .get_column(column_name.to_string())
+        .is_ok_and(|table_column| table_column.is_unique_constraint),
+      _ => self
+        .table_keys
+        .iter()
+        .any(|table_key| table_key.column_names == column_names),
+    }
+  }
+
+  // 通过 PRIMARY KEY 或者 UNIQUE 约束的索引找到 key 对应的行
+  // values 需要先经过 cast_value 转换
+  pub fn get_row_id_by_key(&self, column_names: &[String], values: &[Value]) -> Option<i64> {
+    match (column_names, values) {
+      ([column_name], [value]) => self
+        .get_column(column_name.to_string())
+        .ok()
+        .and_then(|table_column| table_column.index.get_row_id(value)),
+      _ => self
+        .table_keys
+        .iter()
+        .find(|table_key| table_key.column_names == column_names)
+        .and_then(|table_key| table_key.get_row_id(values)),
+    }
+  }
+
+  // 找到在这些列上的值和 values 都相等的所有行，单列有索引的话用索引，否则扫描整张表
+  // values 需要先经过 cast_value 转换，NULL 和任何值都不相等
+  pub fn get_row_ids_by_values(&self, column_names: &[String], values: &[Value]) -> Result<Vec<i64>> {
+    if values.iter().any(|value| value.is_null()) {
+      return Ok(vec![]);
+    }
+    if let ([column_name], [value]) = (column_names, values) {
+      let table_column = self.get_column(column_name.to_string())?;
+      if table_column.is_indexed {
+        return Ok(table_column.index.get_row_ids(value));
+      }
+    }
+
+    let mut row_ids: Vec<i64> = vec![];
+    for row_id in self.get_row_ids() {
+      let mut is_matched = true;
+      for (column_name, value) in column_names.iter().zip(values.iter()) {
+        if self.get_value(column_name, &row_id)? != *value {
+          is_matched = false;
+          break;
+        }
+      }
+      if is_matched {
+        row_ids.push(row_id);
+      }
+    }
+
+    Ok(row_ids)
+  }
+
+  // 拿到一行在每一列上的值，用来检查 CHECK 约束
+  fn get_column_values_of_row(&self, row_id: &i64) -> Result<HashMap<String, Value>> {
+    let mut column_values: HashMap<String, Value> = HashMap::new();
+    for table_column in &self.table_columns {
+      column_values.insert(
+        table_column.column_name.to_string(),
+        self.get_value(&table_column.column_name, row_id)?,
+      );
+    }
+
+    Ok(column_values)
+  }
+
+  // 表上所有的 CHECK 约束，包括列上的以及表级别的
+  fn get_check_exprs(&self) -> Vec<&Expr> {
+    self
+      .table_columns
+      .iter()
+      .flat_map(|table_column| table_column.check_exprs.iter())
+      .chain(self.check_exprs.iter())
+      .collect()
+  }
+
+  // 检查一行是否满足 check_exprs 中的每一个 CHECK 约束，结果为 false 的话报错，NULL 不算违反约束
+  fn check_check_constraint(&self, check_exprs: &[&Expr], column_values: HashMap<String, Value>) -> Result<()> {
+    let row_context = RowContext::from_column_values(column_values);
+    for expr in check_exprs {
+      if !evaluate_check(expr, &row_context)? {
+        return Err(NollaDBError::CheckViolation(expr.to_string()));
+      }
+    }
+
+    Ok(())
+  }
+
+  // 违反 PRIMARY KEY 或者 UNIQUE 约束时返回的错误
+  fn get_constraint_violation_error(&self, table_column: &Column) -> NollaDBError {
+    NollaDBError::ConstraintViolation {
+      kind: if table_column.is_primary_key { ConstraintKind::PrimaryKey } else { ConstraintKind::Unique },
+      column: format!("{}.{}", self.table_name, table_column.column_name),
+    }
+  }
+
+  fn get_table_key_violation_error(&self, table_key: &TableKey) -> NollaDBError {
+    NollaDBError::ConstraintViolation {
+      kind: table_key.get_constraint_kind(),
+      column: table_key.get_qualified_column_names(&self.table_name),
+    }
+  }
+
   // 检查 InsertQuery 中的唯一性约束
+  // NULL 和任何值都不相等，所以不会违反唯一性约束
   pub fn check_unique_constraint(
-    &mut self,
-    table_column_names: &Vec<String>,
-    table_column_value: &Vec<String>,
+    &self,
+    table_column_names: &[String],
+    table_column_value: &[Value],
   ) -> Result<()> {
-    for (i, table_column_name) in table_column_names.iter().enumerate() {
-      let table_column = self.get_column_mut(table_column_name.to_string()).unwrap();
+    for (table_column_name, value) in table_column_names.iter().zip(table_column_value) {
+      let table_column = self.get_column(table_column_name.to_string())?;
       let Column { index, column_name, .. } = &table_column;
 
       // 找到下一个具备唯一性约束的 column 为止
-      // 或者
-      // 如果当前插入的 column name 和表中的 column name 不一致也继续找
-      if !table_column.is_unique_constraint ||
-         *table_column_name != *column_name { continue; }
+      if !table_column.is_unique_constraint { continue; }
 
-      let column_value = &table_column_value[i];
+      let column_value = self.cast_value(column_name, value)?;
+      if column_value.is_null() { continue; }
       match index {
-        Index::Integer(tree) => {
-          if tree.contains_key(&column_value.parse::<i32>().unwrap()) {
-            return Err(
-              NollaDBError::General(
-                format!(
-                  "Error: column {} has a unique constraint violation,
-                  value {} already exists for column {}",
-                  *column_name, column_value, *column_name
-                )
-              )
-            );
-          }
-        },
-        Index::Text(tree) => {
-          if tree.contains_key(column_value) {
-            return Err(
-              NollaDBError::General(
-                format!(
-                  "Error: column {} has a unique constraint violation,
-                  value {} already exists for column {}",
-                  *column_name, column_value, *column_name
-                )
-              )
-            );
-          }
-        },
         Index::None => {
           return Err(
             NollaDBError::General(
@@ -217,149 +364,596 @@ impl Table {
             )
           );
         },
+        index => {
+          if index.get_row_id(&column_value).is_some() {
+            return Err(self.get_constraint_violation_error(table_column));
+          }
+        },
       };
     }
 
+    // 由多列组成的约束要把这几列的值合在一起检查，没有写到的列都是 NULL
+    for table_key in &self.table_keys {
+      let mut values: Vec<Value> = vec![];
+      for column_name in &table_key.column_names {
+        let value = match table_column_names
+          .iter()
+          .position(|table_column_name| table_column_name == column_name)
+          .and_then(|i| table_column_value.get(i)) {
+            Some(value) => self.cast_value(column_name, value)?,
+            None => Value::Null,
+        };
+        values.push(value);
+      }
+      if table_key.get_row_id(&values).is_some() {
+        return Err(self.get_table_key_violation_error(table_key));
+      }
+    }
+
     Ok(())
   }
 
+  // 和 SQLite 一样分配新的 row id
+  // 一般是当前最大的 row id 加 1，所以删除了最大的那一行之后，它的 row id 还会被再次使用
+  // AUTOINCREMENT 的话是曾经分配过的最大的 row id 加 1，删除了的 row id 不会被再次使用
+  fn get_new_row_id(&self) -> Result<i64> {
+    let max_row_id = if self.is_autoincrement {
+      self.most_recent_row_id
+    } else {
+      let table_rows_data = self.table_rows.as_ref().borrow();
+      self
+        .table_columns
+        .first()
+        .and_then(|table_column| table_rows_data.get(&table_column.column_name))
+        .and_then(|row| row.get_max_row_id())
+        .unwrap_or(0)
+    };
+
+    if max_row_id == i64::MAX {
+      return Err(NollaDBError::Internal(
+        format!(
+          "Can not allocate a new row id for table {}, because the maximum row id {} has been used",
+          self.table_name,
+          i64::MAX
+        )
+      ));
+    }
+
+    Ok(max_row_id + 1)
+  }
+
+  // 插入一行，返回这一行的 row id
+  // 值会先转换成每一列对应的类型，没有写到的列用 DEFAULT 的值，没有 DEFAULT 的话是 NULL
+  // Integer 类型的 PRIMARY KEY 就是 row id，没有写或者写的是 NULL 的话就自动分配一个
   pub fn insert_row(
     &mut self,
-    table_column_names: &Vec<String>,
-    table_column_value: &Vec<String>,
-  ) {
-    let mut new_row_id = self.most_recent_row_id + i64::from(1);
+    table_column_names: &[String],
+    table_column_value: &[Value],
+  ) -> Result<i64> {
+    let mut new_row_id: Option<i64> = None;
+    let integer_primary_key = self.get_integer_primary_key().map(|column_name| column_name.to_string());
+
+    // 1. 先把每一列要写入的值都准备好并检查 NOT NULL 约束，避免一行只写入了一半
+    let mut values: Vec<Value> = vec![];
+    for table_column in &self.table_columns {
+      let Column { column_name, column_datatype, .. } = table_column;
+      if let DataType::None | DataType::Invalid = column_datatype {
+        return Err(NollaDBError::Internal(
+          format!("Can not insert into column {} with {} data type", column_name, column_datatype)
+        ));
+      }
+
+      let mut value = match table_column_names
+        .iter()
+        .position(|table_column_name| table_column_name == column_name) {
+          Some(i) => self.cast_value(column_name, &table_column_value[i])?,
+          None => match &table_column.default_expr {
+            Some(expr) => self.cast_value(column_name, &evaluate_expression(expr, &RowContext::default())?)?,
+            None => Value::Null,
+          },
+      };
+
+      if integer_primary_key.as_ref() == Some(column_name) {
+        let row_id = match value {
+          Value::Integer(i) => i,
+          _ => self.get_new_row_id()?,
+        };
+        value = Value::Integer(row_id);
+        new_row_id = Some(row_id);
+      }
+
+      if value.is_null() && (table_column.is_not_null_constraint || table_column.is_primary_key) {
+        return Err(NollaDBError::NotNullViolation(format!("{}.{}", self.table_name, column_name)));
+      }
+      values.push(value);
+    }
 
+    // 2. 检查 CHECK 约束
+    let check_exprs = self.get_check_exprs();
+    if !check_exprs.is_empty() {
+      self.check_check_constraint(
+        &check_exprs,
+        self
+          .table_columns
+          .iter()
+          .map(|table_column| table_column.column_name.to_string())
+          .zip(values.iter().cloned())
+          .collect(),
+      )?;
+    }
+
+    // 3. 写入每一列的数据以及索引
+    let new_row_id = match new_row_id {
+      Some(row_id) => row_id,
+      None => self.get_new_row_id()?,
+    };
     let table_rows_clone = Rc::clone(&self.table_rows);
     let mut table_rows_data =
       table_rows_clone
         .as_ref()
         .borrow_mut();
+    for (table_column, value) in self.table_columns.iter_mut().zip(values.iter()) {
+      match table_rows_data.get_mut(&table_column.column_name) {
+        Some(row) => row.set_value(new_row_id, value)?,
+        None => return Err(NollaDBError::General(
+          format!("Column '{}' not found", table_column.column_name)
+        )),
+      }
+      table_column.get_index_mut().insert(value, new_row_id);
+    }
+    for table_key in self.table_keys.iter_mut() {
+      let values = table_key.get_values(&table_rows_data, &new_row_id);
+      table_key.insert(&values, new_row_id);
+    }
 
-    if self.primary_key != "-1" {
+    if new_row_id > self.most_recent_row_id {
+      self.most_recent_row_id = new_row_id;
+    }
 
-      // 定义了某一列的名字为 PRIMARY KEY
-      let mut table_certain_column_data =
-        table_rows_data
-          .get_mut(&self.primary_key)
-          .unwrap();
+    Ok(new_row_id)
+  }
 
-      match
-       table_column_names
-        .iter()
-        .any(|table_column_name| table_column_name == &self.primary_key) {
-          // 如果要 INSERT 的列的列名包含 PRIMARY KEY
-          // 就只更新 row id
-          true => {
-
-            if let Row::Integer(_) = &mut table_certain_column_data {
-              for (i, table_column_name) in table_column_names.iter().enumerate() {
-                // 循环并找到这一列
-                if table_column_name != &self.primary_key { continue; }
-                let value = &table_column_value[i];
-                // 更新 row id
-                new_row_id = value.parse::<i64>().unwrap();
-              }
-            }
+  // 把值转换成某一列对应的类型，DECIMAL(p, s) 的列还会四舍五入并检查范围，和写入这一列的值一样
+  pub fn cast_value(&self, column_name: &str, value: &Value) -> Result<Value> {
+    let value = self.cast_value_to_column_type(column_name, value)?;
+    self.get_column(column_name.to_string())?.round_decimal(value)
+  }
 
-          },
-          // 如果要 INSERT 的列的列名不包含 PRIMARY KEY
-          // 直接插入这一列的数据，不用更新 row id
-          false => {
-
-            if let Row::Integer(row_tree) = &mut table_certain_column_data {
-              let key = new_row_id.clone();
-              let value = new_row_id as i32;
-
-              row_tree.insert(key, value);
-
-              let table_certain_column_index =
-                self
-                  .get_column_mut(self.primary_key.to_string())
-                  .unwrap()
-                  .get_index_mut();
-              if let Index::Integer(column_tree) = table_certain_column_index {
-                column_tree.insert(value, key);
-              }
-            }
+  // 只转换类型，不四舍五入
+  // 用索引查找的时候用这个，不然 price > 1.005 会变成 price > 1.01，漏掉 1.01 这一行
+  pub fn cast_value_to_column_type(&self, column_name: &str, value: &Value) -> Result<Value> {
+    let table_rows_data = self.table_rows.as_ref().borrow();
+    match table_rows_data.get(column_name) {
+      Some(row) => row.cast_value(value),
+      None => Err(NollaDBError::General(
+        format!("Column '{}' not found", column_name)
+      )),
+    }
+  }
 
-          },
+  // 检查 UpdateQuery 中的 NOT NULL 约束、唯一性约束以及 CHECK 约束
+  // updated_rows 里面是每一个要更新的 row id 以及对应的 (列名, 新的值)
+  pub fn check_update_constraint(
+    &self,
+    updated_rows: &[(i64, Vec<(String, Value)>)],
+  ) -> Result<()> {
+    for table_column in &self.table_columns {
+      let Column {
+        column_name,
+        is_unique_constraint,
+        is_not_null_constraint,
+        index,
+        ..
+      } = table_column;
+
+      // 同一条 UPDATE 语句里面也不能把多行更新成同一个值
+      let mut updated_values: HashSet<String> = HashSet::new();
+      for (row_id, updated_values_of_row) in updated_rows {
+        let value = match updated_values_of_row
+          .iter()
+          .find(|(updated_column_name, _)| updated_column_name == column_name) {
+            Some((_, value)) => value,
+            None => continue,
+        };
+
+        if (*is_not_null_constraint || table_column.is_primary_key) && value.is_null() {
+          return Err(NollaDBError::NotNullViolation(format!("{}.{}", self.table_name, column_name)));
+        }
+
+        if !*is_unique_constraint || value.is_null() { continue; }
+
+        let is_existed = match index.get_row_id(value) {
+          Some(existed_row_id) => existed_row_id != *row_id,
+          None => false,
+        };
+        if is_existed || !updated_values.insert(value.to_string()) {
+          return Err(self.get_constraint_violation_error(table_column));
+        }
       }
     }
 
-    // 检查 INSERT statement 中是否有表中没有的 column
-    // 如果是，就对该 column 补 "Null"
-    // 使得 row 的长度能对齐
-    let mut j: usize = 0;
-    let column_names_vec = self
+    // 由多列组成的约束，没有更新的列用这一行原来的值
+    let table_rows_data = self.table_rows.as_ref().borrow();
+    for table_key in &self.table_keys {
+      let mut updated_keys: HashSet<String> = HashSet::new();
+      for (row_id, updated_values_of_row) in updated_rows {
+        if !updated_values_of_row
+          .iter()
+          .any(|(updated_column_name, _)| table_key.column_names.contains(updated_column_name)) {
+          continue;
+        }
+
+        let mut values = table_key.get_values(&table_rows_data, row_id);
+        for (value, column_name) in values.iter_mut().zip(table_key.column_names.iter()) {
+          if let Some((_, updated_value)) = updated_values_of_row
+            .iter()
+            .find(|(updated_column_name, _)| updated_column_name == column_name) {
+            *value = updated_value.clone();
+          }
+        }
+        if values.iter().any(|value| value.is_null()) { continue; }
+
+        let is_existed = match table_key.get_row_id(&values) {
+          Some(existed_row_id) => existed_row_id != *row_id,
+          None => false,
+        };
+        if is_existed || !updated_keys.insert(format!("{:?}", values)) {
+          return Err(self.get_table_key_violation_error(table_key));
+        }
+      }
+    }
+
+    // CHECK 约束，没有更新的列用这一行原来的值
+    let check_exprs = self.get_check_exprs();
+    if !check_exprs.is_empty() {
+      for (row_id, updated_values_of_row) in updated_rows {
+        let mut column_values = self.get_column_values_of_row(row_id)?;
+        for (column_name, value) in updated_values_of_row {
+          column_values.insert(column_name.to_string(), value.clone());
+        }
+        self.check_check_constraint(&check_exprs, column_values)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  // 更新 row id 对应的这一行，updated_values 需要先经过 cast_value 转换
+  // 返回更新之后这一行的 row id
+  pub fn update_row(
+    &mut self,
+    row_id: i64,
+    updated_values: &[(String, Value)],
+  ) -> Result<i64> {
+    let table_rows_clone = Rc::clone(&self.table_rows);
+    let mut table_rows_data =
+      table_rows_clone
+        .as_ref()
+        .borrow_mut();
+
+    let integer_primary_key = self.get_integer_primary_key().map(|column_name| column_name.to_string());
+    let old_key_values = self
+      .table_keys
+      .iter()
+      .map(|table_key| table_key.get_values(&table_rows_data, &row_id))
+      .collect::<Vec<Vec<Value>>>();
+
+    let mut new_row_id = row_id;
+    for (column_name, value) in updated_values {
+      let row = match table_rows_data.get_mut(column_name) {
+        Some(row) => row,
+        None => return Err(NollaDBError::General(
+          format!("Column '{}' not found", column_name)
+        )),
+      };
+      let old_value = row.get_value(&row_id);
+      row.set_value(row_id, value)?;
+
+      // 删掉旧的索引，插入新的索引
+      let index = self.get_column_mut(column_name.to_string())?.get_index_mut();
+      index.remove(&old_value, row_id);
+      index.insert(value, row_id);
+
+      // Integer 类型的 PRIMARY KEY 就是 row id，更新了它也就要更新 row id
+      if integer_primary_key.as_ref() == Some(column_name) {
+        if let Value::Integer(i) = value {
+          new_row_id = *i;
+        }
+      }
+    }
+
+    if new_row_id != row_id {
+      for table_column in self.table_columns.iter_mut() {
+        let row = match table_rows_data.get_mut(&table_column.column_name) {
+          Some(row) => row,
+          None => continue,
+        };
+        row.move_value(row_id, new_row_id);
+
+        let value = row.get_value(&new_row_id);
+        let index = table_column.get_index_mut();
+        index.remove(&value, row_id);
+        index.insert(&value, new_row_id);
+      }
+
+      if new_row_id > self.most_recent_row_id {
+        self.most_recent_row_id = new_row_id;
+      }
+    }
+
+    // 组合 key 的索引也要删掉旧的，插入新的
+    for (table_key, old_values) in self.table_keys.iter_mut().zip(old_key_values.iter()) {
+      table_key.remove(old_values, row_id);
+      let values = table_key.get_values(&table_rows_data, &new_row_id);
+      table_key.insert(&values, new_row_id);
+    }
+
+    Ok(new_row_id)
+  }
+
+  // 删除 row id 对应的这一行，同时删除每一列索引中对应的 row id
+  pub fn delete_row(&mut self, row_id: i64) {
+    let table_rows_clone = Rc::clone(&self.table_rows);
+    let mut table_rows_data =
+      table_rows_clone
+        .as_ref()
+        .borrow_mut();
+
+    for table_key in self.table_keys.iter_mut() {
+      let values = table_key.get_values(&table_rows_data, &row_id);
+      table_key.remove(&values, row_id);
+    }
+    for table_column in self.table_columns.iter_mut() {
+      if let Some(row) = table_rows_data.get_mut(&table_column.column_name) {
+        let value = row.remove_value(&row_id);
+        table_column.get_index_mut().remove(&value, row_id);
+      }
+    }
+  }
+
+  // CREATE [UNIQUE] INDEX
+  // 只扫描一遍这一列就建好索引，UNIQUE 的话同时检查已有的值有没有重复
+  pub fn create_index(&mut self, index_name: &str, column_name: &str, is_unique: bool) -> Result<()> {
+    let table_name = self.table_name.to_string();
+    let table_column = self.get_column(column_name.to_string())?;
+    if table_column.is_indexed {
+      return Err(NollaDBError::Internal(
+        format!("Can not create index {}, because column {} already has an index", index_name, column_name)
+      ));
+    }
+
+    let mut index = Index::new(&table_column.column_datatype, is_unique);
+    if index == Index::None {
+      return Err(NollaDBError::Internal(
+        format!("Can not create index on column {} with {} data type", column_name, table_column.column_datatype)
+      ));
+    }
+
+    {
+      let table_rows_data = self.table_rows.as_ref().borrow();
+      let row = match table_rows_data.get(column_name) {
+        Some(row) => row,
+        None => return Err(NollaDBError::General(
+          format!("Column '{}' not found", column_name)
+        )),
+      };
+      for (row_id, value) in row.get_row_ids().into_iter().zip(row.get_values()) {
+        if is_unique && !value.is_null() && index.get_row_id(&value).is_some() {
+          return Err(NollaDBError::ConstraintViolation {
+            kind: ConstraintKind::Unique,
+            column: format!("{}.{}", table_name, column_name),
+          });
+        }
+        index.insert(&value, row_id);
+      }
+    }
+
+    let table_column = self.get_column_mut(column_name.to_string())?;
+    table_column.index = index;
+    table_column.is_indexed = true;
+    table_column.is_unique_constraint = is_unique;
+    self.indexes.insert(index_name.to_string(), column_name.to_string());
+
+    Ok(())
+  }
+
+  // DROP INDEX
+  pub fn drop_index(&mut self, index_name: &str) -> Result<()> {
+    let column_name = match self.indexes.remove(index_name) {
+      Some(column_name) => column_name,
+      None => return Err(NollaDBError::Internal(
+        format!("Index {} not found", index_name)
+      )),
+    };
+
+    let table_column = self.get_column_mut(column_name)?;
+    table_column.index = Index::None;
+    table_column.is_indexed = false;
+    table_column.is_unique_constraint = false;
+
+    Ok(())
+  }
+
+  // ALTER TABLE ADD COLUMN
+  // 已经存在的行在新的列上都写入 default_value，并且要满足新的列上的 CHECK 约束
+  pub fn add_column(
+    &mut self,
+    table_metadata_column: &SchemaOfSQLColumn,
+    default_value: &Value,
+  ) -> Result<()> {
+    let SchemaOfSQLColumn {
+      column_name,
+      column_datatype,
+      is_primary_key,
+      is_unique_constraint,
+      is_not_null_constraint,
+      default_expr,
+      check_exprs,
+      decimal_precision,
+      ..
+    } = table_metadata_column;
+
+    if self.has_column(column_name.to_string()) {
+      return Err(NollaDBError::Internal(
+        format!("Duplicate column name: {}", column_name)
+      ));
+    }
+    // 和 SQLite 一样，新加的列不能是 PRIMARY KEY 或者 UNIQUE
+    if *is_primary_key || *is_unique_constraint {
+      return Err(NollaDBError::Internal(
+        format!("Can not add a PRIMARY KEY or UNIQUE column {}", column_name)
+      ));
+    }
+
+    let mut table_column = Column::new(
+      column_name.to_string(),
+      column_datatype.to_string(),
+      *is_primary_key,
+      *is_unique_constraint,
+      *is_not_null_constraint,
+    );
+    if let DataType::None | DataType::Invalid = table_column.column_datatype {
+      return Err(NollaDBError::Internal(
+        format!("Can not add column {} with {} data type", column_name, table_column.column_datatype)
+      ));
+    }
+    table_column.decimal_precision = *decimal_precision;
+
+    let mut row = Row::new(&table_column.column_datatype);
+    let value = table_column.round_decimal(row.cast_value(default_value)?)?;
+    let row_ids = self.get_row_ids();
+    if value.is_null() && *is_not_null_constraint && !row_ids.is_empty() {
+      return Err(NollaDBError::NotNullViolation(format!("{}.{}", self.table_name, column_name)));
+    }
+    if !check_exprs.is_empty() {
+      let check_exprs = check_exprs.iter().collect::<Vec<&Expr>>();
+      // 没有任何行的时候也对每一列都是 NULL 的一行求值一次，检查引用的列都存在
+      let mut column_values = self
         .table_columns
         .iter()
-        .map(|table_column| table_column.column_name.to_string())
-        .collect::<Vec<String>>();
+        .map(|table_column| (table_column.column_name.to_string(), Value::Null))
+        .collect::<HashMap<String, Value>>();
+      column_values.insert(column_name.to_string(), Value::Null);
+      self.check_check_constraint(&check_exprs, column_values)?;
 
-    for i in 0..column_names_vec.len() {
-      let key = &column_names_vec[i];
-      let mut value = String::from("Null");
-
-      match &table_column_names.get(j) {
-        Some(table_column_name) => {
-          if key == &table_column_name.to_string() {
-            value = table_column_value[j].to_string();
-            j += 1;
-          } else {
-            // 如果在原来的表中对应 column 名字不匹配
-            // 如果是 PRIMARY KEY 则跳过
-            if key == &self.primary_key { continue; }
-          }
-        },
-        _ => {
-          // 找到末尾
-          // 如果是 PRIMARY KEY 则跳过
-          if key == &self.primary_key { continue; }
-        },
+      for row_id in &row_ids {
+        let mut column_values = self.get_column_values_of_row(row_id)?;
+        column_values.insert(column_name.to_string(), value.clone());
+        self.check_check_constraint(&check_exprs, column_values)?;
       }
+    }
+    table_column.default_expr = default_expr.clone();
+    table_column.check_exprs = check_exprs.clone();
+    for row_id in row_ids {
+      row.set_value(row_id, &value)?;
+      table_column.get_index_mut().insert(&value, row_id);
+    }
 
-      // 拿到这个 key 对应的 column data
-      let mut table_key_corresponding_column_data =
-        table_rows_data
-          .get_mut(key)
-          .unwrap();
+    self.table_rows.as_ref().borrow_mut().insert(column_name.to_string(), row);
+    self.table_columns.push(table_column);
 
-      // 拿到这个 key 对应的 column index
-      let table_key_corresponding_column_index =
-        self
-          .get_column_mut(key.to_string())
-          .unwrap()
-          .get_index_mut();
-
-      // 更新
-      let key = new_row_id.clone();
-      match &mut table_key_corresponding_column_data {
-        Row::Integer(row_tree) => {
-          let value = value.parse::<i32>().unwrap();
-          row_tree.insert(key, value);
-          if let Index::Integer(column_tree) = table_key_corresponding_column_index {
-            column_tree.insert(value, key);
-          }
-        },
-        Row::Bool(row_tree) => {
-          let value = value.parse::<bool>().unwrap();
-          row_tree.insert(key, value);
-        },
-        Row::Text(row_tree) => {
-          row_tree.insert(key, value.to_string());
-          if let Index::Text(column_tree) = table_key_corresponding_column_index {
-            column_tree.insert(value.to_string(), key);
-          }
-        },
-        Row::Real(row_tree) => {
-          let value = value.parse::<f32>().unwrap();
-          row_tree.insert(key, value);
-        },
-        Row::None => panic!("None column data found"),
+    Ok(())
+  }
+
+  // ALTER TABLE DROP COLUMN
+  pub fn drop_column(&mut self, column_name: &str) -> Result<()> {
+    let position = match self
+      .table_columns
+      .iter()
+      .position(|table_column| table_column.column_name == column_name) {
+        Some(position) => position,
+        None => return Err(NollaDBError::General(
+          format!("Column '{}' not found", column_name)
+        )),
+    };
+
+    if self.primary_key.iter().any(|primary_key_column_name| primary_key_column_name == column_name) {
+      return Err(NollaDBError::Internal(
+        format!("Can not drop PRIMARY KEY column {}", column_name)
+      ));
+    }
+    if let Some(table_key) = self
+      .table_keys
+      .iter()
+      .find(|table_key| table_key.column_names.iter().any(|key_column_name| key_column_name == column_name)) {
+      return Err(NollaDBError::Internal(
+        format!("Can not drop column {}, because it is used in {}", column_name, table_key)
+      ));
+    }
+    if let Some(foreign_key) = self
+      .foreign_keys
+      .iter()
+      .find(|foreign_key| foreign_key.column_names.iter().any(|key_column_name| key_column_name == column_name)) {
+      return Err(NollaDBError::Internal(
+        format!("Can not drop column {}, because it is used in {}", column_name, foreign_key)
+      ));
+    }
+    // 这一列自己的 CHECK 约束会和这一列一起删除，但是不能被其他的 CHECK 约束引用
+    if let Some(expr) = self
+      .table_columns
+      .iter()
+      .filter(|table_column| table_column.column_name != column_name)
+      .flat_map(|table_column| table_column.check_exprs.iter())
+      .chain(self.check_exprs.iter())
+      .find(|expr| is_column_referenced(expr, column_name)) {
+      return Err(NollaDBError::Internal(
+        format!("Can not drop column {}, because it is used in CHECK ({})", column_name, expr)
+      ));
+    }
+    if self.table_columns.len() == 1 {
+      return Err(NollaDBError::Internal(
+        format!("Can not drop column {}, because it is the only column in table {}", column_name, self.table_name)
+      ));
+    }
+
+    self.table_columns.remove(position);
+    self.table_rows.as_ref().borrow_mut().remove(column_name);
+    self.indexes.retain(|_, indexed_column_name| indexed_column_name != column_name);
+
+    Ok(())
+  }
+
+  // ALTER TABLE RENAME COLUMN
+  pub fn rename_column(&mut self, old_column_name: &str, new_column_name: &str) -> Result<()> {
+    if self.has_column(new_column_name.to_string()) {
+      return Err(NollaDBError::Internal(
+        format!("Duplicate column name: {}", new_column_name)
+      ));
+    }
+
+    self.get_column_mut(old_column_name.to_string())?.column_name = new_column_name.to_string();
+
+    let mut table_rows_data = self.table_rows.as_ref().borrow_mut();
+    if let Some(row) = table_rows_data.remove(old_column_name) {
+      table_rows_data.insert(new_column_name.to_string(), row);
+    }
+    for key_column_name in self
+      .primary_key
+      .iter_mut()
+      .chain(self.table_keys.iter_mut().flat_map(|table_key| table_key.column_names.iter_mut()))
+      .chain(self.foreign_keys.iter_mut().flat_map(|foreign_key| foreign_key.column_names.iter_mut())) {
+      if key_column_name == old_column_name {
+        *key_column_name = new_column_name.to_string();
+      }
+    }
+    for indexed_column_name in self.indexes.values_mut() {
+      if indexed_column_name == old_column_name {
+        *indexed_column_name = new_column_name.to_string();
       }
     }
-    self.most_recent_row_id = new_row_id;
+    for expr in self
+      .table_columns
+      .iter_mut()
+      .flat_map(|table_column| table_column.check_exprs.iter_mut())
+      .chain(self.check_exprs.iter_mut()) {
+      visit_column_idents(expr, &mut |ident| {
+        if ident.value == old_column_name {
+          ident.value = new_column_name.to_string();
+        }
+      });
+    }
+
+    Ok(())
   }
 
   pub fn print_column_of_schema(&self) -> Result<usize> {
@@ -371,6 +965,8 @@ impl Table {
       "IS UNIQUE",
       "IS NOT NULL",
       "IS INDEXED",
+      "DEFAULT",
+      "TABLE CONSTRAINTS",
     ]);
 
     for table_column in &self.table_columns {
@@ -381,9 +977,37 @@ impl Table {
         is_unique_constraint,
         is_not_null_constraint,
         is_indexed,
+        default_expr,
+        check_exprs,
         ..
       } = &table_column;
 
+      // 由多列组成的 PRIMARY KEY 的每一列也都算是 PRIMARY KEY
+      let is_primary_key = *is_primary_key || self.primary_key.contains(column_name);
+      // 这一列上的 CHECK，这一列所在的组合 key、外键以及表级别的 CHECK
+      let table_constraints = check_exprs
+        .iter()
+        .map(|expr| format!("CHECK ({})", expr))
+        .chain(self
+          .table_keys
+          .iter()
+          .filter(|table_key| table_key.column_names.contains(column_name))
+          .map(|table_key| table_key.to_string())
+        )
+        .chain(self
+          .foreign_keys
+          .iter()
+          .filter(|foreign_key| foreign_key.column_names.contains(column_name))
+          .map(|foreign_key| foreign_key.to_string())
+        )
+        .chain(self
+          .check_exprs
+          .iter()
+          .filter(|expr| is_column_referenced(expr, column_name))
+          .map(|expr| format!("CHECK ({})", expr))
+        )
+        .collect::<Vec<String>>();
+
       print_table.add_row(row![
         column_name,
         column_datatype,
@@ -391,10 +1015,14 @@ impl Table {
         is_unique_constraint,
         is_not_null_constraint,
         is_indexed,
+        default_expr.as_ref().map_or(String::new(), |expr| expr.to_string()),
+        table_constraints.join("\n"),
       ]);
     }
 
-    Ok(print_table.printstd())
+    print_table
+      .print_tty(false)
+      .map_err(|error| NollaDBError::Internal(error.to_string()))
   }
 
   pub fn print_table_data(&self) -> Result<usize> {
@@ -411,7 +1039,7 @@ impl Table {
     let print_table_rows_header = PrintRow::new(
       column_names_vec
         .iter()
-        .map(|column_name| PrintCell::new(&column_name))
+        .map(|column_name| PrintCell::new(column_name))
         .collect::<Vec<PrintCell>>(),
     );
 
@@ -420,13 +1048,11 @@ impl Table {
       table_rows_clone
         .as_ref()
         .borrow();
-    let table_first_column_data =
-      table_rows_data
-        .get(&self.table_columns.first().unwrap().column_name)
-        .unwrap();
-
-    let number_of_element_in_column
-      = table_first_column_data.get_number_of_element_in_column();
+    // 每一行在每一列上都有对应的 key，所以任意一列的长度就是行数
+    let number_of_element_in_column = table_rows_data
+      .values()
+      .next()
+      .map_or(0, |row| row.get_number_of_element_in_column());
 
     let mut print_table_rows: Vec<PrintRow>
       = vec![
@@ -436,20 +1062,16 @@ impl Table {
 
     // 拿到每个 column_name 对应下的数据，并进行输出
     for column_name in &column_names_vec {
-      let table_certain_column_data =
-        table_rows_data
-          .get(column_name)
-          .expect("Can not find any rows with the given column name");
-      let values_of_table_certain_column_data =
-        table_certain_column_data.get_serialized_column_data();
-
-      for i in 0..number_of_element_in_column {
-        let mut cell_instance = "";
-        if let Some(cell) =
-          &values_of_table_certain_column_data.get(i) {
-          cell_instance = cell;
+      let values_of_table_certain_column_data = match table_rows_data.get(column_name) {
+        Some(table_certain_column_data) => table_certain_column_data.get_values(),
+        None => vec![],
+      };
+
+      for (i, print_table_row) in print_table_rows.iter_mut().enumerate() {
+        match values_of_table_certain_column_data.get(i) {
+          Some(value) => print_table_row.add_cell(value.get_print_cell()),
+          None => print_table_row.add_cell(PrintCell::new("")),
         }
-        print_table_rows[i].add_cell(PrintCell::new(cell_instance));
       }
     }
 
@@ -458,7 +1080,9 @@ impl Table {
       print_table.add_row(row);
     }
 
-    Ok(print_table.printstd())
+    print_table
+      .print_tty(false)
+      .map_err(|error| NollaDBError::Internal(error.to_string()))
   }
 }
 
@@ -512,7 +1136,7 @@ mod tests {
     if let Some(table_column) =
       table.table_columns
         .iter()
-        .filter(|tc| tc.column_name == "id".to_string())
+        .filter(|tc| tc.column_name == "id")
         .collect::<Vec<&Column>>()
         .first() {
       assert_eq!(table.table_columns.len(), expected_table_columns_len);
@@ -541,9 +1165,159 @@ mod tests {
     assert_eq!(table.print_column_of_schema(), Ok(print_lines_number));
   }
 
+  #[test]
+  fn test_insert_row_with_null() {
+    let mut table = create_new_table(
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER, rate REAL);"
+    ).unwrap();
+    let row_id = table.insert_row(
+      &["name".to_string()],
+      &[Value::Text("Null".to_string())],
+    ).unwrap();
+
+    assert_eq!(row_id, 1);
+    assert_eq!(table.get_row_ids(), vec![1]);
+    assert_eq!(table.get_value("id", &1), Ok(Value::Integer(1)));
+    assert_eq!(table.get_value("name", &1), Ok(Value::Text("Null".to_string())));
+    assert_eq!(table.get_value("score", &1), Ok(Value::Null));
+    assert_eq!(table.get_value("rate", &1), Ok(Value::Null));
+    assert_eq!(table.print_table_data(), Ok(5));
+  }
+
+  #[rstest]
+  #[case(vec!["id", "name"], vec![Value::Integer(1), Value::Null])]
+  #[case(vec!["id"], vec![Value::Integer(1)])]
+  #[case(vec!["id", "name"], vec![Value::Text("x".to_string()), Value::Text("x".to_string())])]
+  fn test_insert_row_error(
+    #[case] table_column_names: Vec<&str>,
+    #[case] table_column_value: Vec<Value>,
+  ) {
+    let mut table = create_new_table(
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT NOT NULL);"
+    ).unwrap();
+    let table_column_names = table_column_names
+      .iter()
+      .map(|table_column_name| table_column_name.to_string())
+      .collect::<Vec<String>>();
+
+    assert!(table.insert_row(&table_column_names, &table_column_value).is_err());
+    assert!(table.get_row_ids().is_empty());
+  }
+
+  #[test]
+  fn test_create_and_drop_index() {
+    let mut table = create_new_table(
+      "CREATE TABLE test (id INTEGER PRIMARY KEY, name TEXT, score INTEGER);"
+    ).unwrap();
+    let table_column_names = vec!["name".to_string(), "score".to_string()];
+    for (name, score) in [("b", 2), ("a", 1)] {
+      table.insert_row(&table_column_names, &[Value::Text(name.to_string()), Value::Integer(score)]).unwrap();
+    }
+    table.insert_row(&table_column_names, &[Value::Null, Value::Integer(3)]).unwrap();
+
+    table.create_index("idx_name", "name", true).unwrap();
+    let table_column = table.get_column("name".to_string()).unwrap();
+    assert!(table_column.is_indexed && table_column.is_unique_constraint);
+    assert_eq!(table_column.index.get_ordered_row_ids(), vec![2, 1]);
+    assert_eq!(table.indexes.get("idx_name"), Some(&"name".to_string()));
+
+    // 新插入的行也会写入索引
+    table.insert_row(&table_column_names, &[Value::Text("c".to_string()), Value::Integer(3)]).unwrap();
+    assert_eq!(table.get_column("name".to_string()).unwrap().index.get_row_id(&Value::Text("c".to_string())), Some(4));
+
+    // 已经有索引或者有重复值的列不能再建 UNIQUE 索引
+    assert!(table.create_index("idx_name_2", "name", false).is_err());
+    assert!(table.create_index("idx_score", "score", true).is_err());
+    assert!(!table.get_column("score".to_string()).unwrap().is_indexed);
+
+    table.drop_index("idx_name").unwrap();
+    let table_column = table.get_column("name".to_string()).unwrap();
+    assert!(!table_column.is_indexed && !table_column.is_unique_constraint);
+    assert_eq!(table_column.index, Index::None);
+    assert!(table.indexes.is_empty());
+    assert!(table.drop_index("idx_name").is_err());
+  }
+
+  #[test]
+  fn test_composite_keys() {
+    let mut table = create_new_table(
+      "CREATE TABLE test (id INTEGER, name TEXT, score INTEGER, PRIMARY KEY (id, name), UNIQUE (name, score));"
+    ).unwrap();
+    assert_eq!(table.primary_key, vec!["id".to_string(), "name".to_string()]);
+    assert_eq!(table.get_integer_primary_key(), None);
+    // 组合 key 的列上不会单独建索引
+    assert!(!table.get_column("id".to_string()).unwrap().is_indexed);
+
+    let table_column_names = vec!["id".to_string(), "name".to_string(), "score".to_string()];
+    for (id, name, score) in [(1, "a", Value::Integer(1)), (1, "b", Value::Integer(1)), (2, "a", Value::Null)] {
+      let values = [Value::Integer(id), Value::Text(name.to_string()), score];
+      table.check_unique_constraint(&table_column_names, &values).unwrap();
+      table.insert_row(&table_column_names, &values).unwrap();
+    }
+    // row id 不再是 PRIMARY KEY 的值
+    assert_eq!(table.get_row_ids(), vec![1, 2, 3]);
+
+    let primary_key_error = Err(NollaDBError::ConstraintViolation {
+      kind: ConstraintKind::PrimaryKey,
+      column: "test.id, test.name".to_string(),
+    });
+    let unique_error = Err(NollaDBError::ConstraintViolation {
+      kind: ConstraintKind::Unique,
+      column: "test.name, test.score".to_string(),
+    });
+    assert_eq!(
+      table.check_unique_constraint(&table_column_names, &[Value::Integer(1), Value::Text("a".to_string()), Value::Integer(5)]),
+      primary_key_error
+    );
+    assert_eq!(
+      table.check_unique_constraint(&table_column_names, &[Value::Integer(3), Value::Text("a".to_string()), Value::Integer(1)]),
+      unique_error
+    );
+    // 有一列是 NULL 的话不会违反 UNIQUE 约束，但是 PRIMARY KEY 的列不能是 NULL
+    assert!(table.check_unique_constraint(&table_column_names, &[Value::Integer(3), Value::Text("a".to_string()), Value::Null]).is_ok());
+    assert_eq!(
+      table.insert_row(&table_column_names, &[Value::Null, Value::Text("c".to_string()), Value::Null]),
+      Err(NollaDBError::NotNullViolation("test.id".to_string()))
+    );
+
+    // UPDATE 时没有更新的列用原来的值
+    assert_eq!(
+      table.check_update_constraint(&[(2, vec![("name".to_string(), Value::Text("a".to_string()))])]),
+      primary_key_error
+    );
+    assert_eq!(
+      table.check_update_constraint(&[(3, vec![("score".to_string(), Value::Integer(1))])]),
+      unique_error
+    );
+    assert_eq!(
+      table.check_update_constraint(&[
+        (1, vec![("id".to_string(), Value::Integer(5))]),
+        (2, vec![("id".to_string(), Value::Integer(5)), ("name".to_string(), Value::Text("a".to_string()))]),
+      ]),
+      primary_key_error
+    );
+    table.update_row(1, &[("id".to_string(), Value::Integer(5))]).unwrap();
+    table.delete_row(2);
+    let values = [Value::Integer(1), Value::Text("b".to_string()), Value::Integer(1)];
+    assert!(table.check_unique_constraint(&table_column_names, &values).is_ok());
+    assert_eq!(
+      table.check_unique_constraint(&table_column_names, &[Value::Integer(5), Value::Text("a".to_string()), Value::Null]),
+      primary_key_error
+    );
+
+    // 组合 key 中的列不能删除，重命名的时候 key 中的列名也要一起改
+    assert!(table.drop_column("id").is_err());
+    assert!(table.drop_column("score").is_err());
+    table.rename_column("name", "nickname").unwrap();
+    assert_eq!(table.primary_key, vec!["id".to_string(), "nickname".to_string()]);
+    assert_eq!(table.table_keys[1].to_string(), "UNIQUE (nickname, score)");
+    // nickname 同时在两个组合 key 里面，这一格会占两行
+    assert_eq!(table.print_column_of_schema(), Ok(10));
+  }
+
   fn create_new_table(query: &str) -> Result<Table, ()> {
     let dialect = SQLiteDialect {};
-    let mut ast = Parser::parse_sql(&dialect, &query).unwrap();
+    let mut ast = Parser::parse_sql(&dialect, query).unwrap();
     let create_query = CreateQuery::new(&ast.pop().unwrap()).unwrap();
     let table = Table::new(create_query);
 
@@ -0,0 +1,275 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::NollaDBError;
+
+const MICROSECONDS_PER_SECOND: i64 = 1_000_000;
+const MICROSECONDS_PER_DAY: i64 = 86_400 * MICROSECONDS_PER_SECOND;
+
+// 日期，存的是距离 1970-01-01 的天数，这样比较大小以及作为索引的 key 都很直接
+#[derive(Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub struct Date(i64);
+
+// 一天中的时间，存的是距离 00:00:00 的微秒数
+#[derive(Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub struct Time(i64);
+
+// 日期加上时间，存的是距离 1970-01-01 00:00:00 的微秒数，不带时区
+#[derive(Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
+pub struct Timestamp(i64);
+
+impl Date {
+  pub fn new(year: i64, month: u32, day: u32) -> Option<Date> {
+    if !(0..=9999).contains(&year) || !(1..=12).contains(&month) || day < 1 || day > get_days_in_month(year, month) {
+      return None;
+    }
+    Some(Date(get_days_from_civil(year, month, day)))
+  }
+}
+
+impl Time {
+  pub fn new(hour: u32, minute: u32, second: u32, microsecond: u32) -> Option<Time> {
+    if hour > 23 || minute > 59 || second > 59 || microsecond >= 1_000_000 {
+      return None;
+    }
+    Some(Time(
+      (i64::from(hour) * 3600 + i64::from(minute) * 60 + i64::from(second)) * MICROSECONDS_PER_SECOND
+        + i64::from(microsecond)
+    ))
+  }
+}
+
+impl Timestamp {
+  pub fn new(date: Date, time: Time) -> Timestamp {
+    Timestamp(date.0 * MICROSECONDS_PER_DAY + time.0)
+  }
+
+  pub fn get_date(&self) -> Date {
+    Date(self.0.div_euclid(MICROSECONDS_PER_DAY))
+  }
+
+  pub fn get_time(&self) -> Time {
+    Time(self.0.rem_euclid(MICROSECONDS_PER_DAY))
+  }
+}
+
+// DATE 和 TIMESTAMP 比较的时候，DATE 当作当天的 00:00:00
+impl From<Date> for Timestamp {
+  fn from(date: Date) -> Self {
+    Timestamp::new(date, Time(0))
+  }
+}
+
+// 公历的年月日和距离 1970-01-01 的天数之间的转换
+// 参考 http://howardhinnant.github.io/date_algorithms.html
+fn get_days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let year_of_era = year.rem_euclid(400);
+  let month = i64::from(month);
+  let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + i64::from(day) - 1;
+  let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  era * 146097 + day_of_era - 719468
+}
+
+fn get_civil_from_days(days: i64) -> (i64, u32, u32) {
+  let days = days + 719468;
+  let era = days.div_euclid(146097);
+  let day_of_era = days.rem_euclid(146097);
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_from_march = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+  let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+  (year, month as u32, day as u32)
+}
+
+fn get_days_in_month(year: i64, month: u32) -> u32 {
+  match month {
+    2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+    2 => 28,
+    4 | 6 | 9 | 11 => 30,
+    _ => 31,
+  }
+}
+
+// 解析固定位数的数字，不允许有正负号
+fn parse_digits(s: &str, length: usize) -> Option<u32> {
+  if s.len() != length || !s.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+  s.parse::<u32>().ok()
+}
+
+// YYYY-MM-DD
+fn parse_date(s: &str) -> Option<Date> {
+  match s.split('-').collect::<Vec<&str>>().as_slice() {
+    [year, month, day] => Date::new(
+      i64::from(parse_digits(year, 4)?),
+      parse_digits(month, 2)?,
+      parse_digits(day, 2)?,
+    ),
+    _ => None,
+  }
+}
+
+// HH:MM、HH:MM:SS 或者 HH:MM:SS.ffffff，小数部分最多到微秒
+fn parse_time(s: &str) -> Option<Time> {
+  let (hour_minute_second, fraction) = match s.split_once('.') {
+    Some((hour_minute_second, fraction)) => (hour_minute_second, Some(fraction)),
+    None => (s, None),
+  };
+  let microsecond = match fraction {
+    Some(fraction) if (1..=6).contains(&fraction.len()) => {
+      parse_digits(fraction, fraction.len())? * 10u32.pow(6 - fraction.len() as u32)
+    },
+    Some(_) => return None,
+    None => 0,
+  };
+
+  match hour_minute_second.split(':').collect::<Vec<&str>>().as_slice() {
+    [hour, minute] if fraction.is_none() => Time::new(parse_digits(hour, 2)?, parse_digits(minute, 2)?, 0, 0),
+    [hour, minute, second] => Time::new(
+      parse_digits(hour, 2)?,
+      parse_digits(minute, 2)?,
+      parse_digits(second, 2)?,
+      microsecond,
+    ),
+    _ => None,
+  }
+}
+
+impl FromStr for Date {
+  type Err = NollaDBError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    parse_date(s.trim()).ok_or_else(|| NollaDBError::Internal(format!("Invalid date '{}'", s)))
+  }
+}
+
+impl FromStr for Time {
+  type Err = NollaDBError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    parse_time(s.trim()).ok_or_else(|| NollaDBError::Internal(format!("Invalid time '{}'", s)))
+  }
+}
+
+// YYYY-MM-DD HH:MM:SS，日期和时间之间也可以用 T 分隔，只有日期的话就是当天的 00:00:00
+impl FromStr for Timestamp {
+  type Err = NollaDBError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let s = s.trim();
+    match s.split_once([' ', 'T']) {
+      Some((date, time)) => parse_date(date).zip(parse_time(time.trim_start())),
+      None => parse_date(s).map(|date| (date, Time(0))),
+    }
+    .map(|(date, time)| Timestamp::new(date, time))
+    .ok_or_else(|| NollaDBError::Internal(format!("Invalid timestamp '{}'", s)))
+  }
+}
+
+impl fmt::Display for Date {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let (year, month, day) = get_civil_from_days(self.0);
+    write!(f, "{:04}-{:02}-{:02}", year, month, day)
+  }
+}
+
+// 没有小数部分的话不打印，有的话去掉末尾的 0
+impl fmt::Display for Time {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let seconds = self.0 / MICROSECONDS_PER_SECOND;
+    write!(f, "{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)?;
+    let microsecond = self.0 % MICROSECONDS_PER_SECOND;
+    if microsecond != 0 {
+      write!(f, ".{}", format!("{:06}", microsecond).trim_end_matches('0'))?;
+    }
+    Ok(())
+  }
+}
+
+impl fmt::Display for Timestamp {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{} {}", self.get_date(), self.get_time())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+
+  #[rstest]
+  #[case("1970-01-01", "1970-01-01")]
+  #[case("2000-02-29", "2000-02-29")]
+  #[case(" 0001-12-31 ", "0001-12-31")]
+  #[case("9999-12-31", "9999-12-31")]
+  fn test_parse_and_display_date(
+    #[case] input: &str,
+    #[case] expected: &str,
+  ) {
+    assert_eq!(input.parse::<Date>().unwrap().to_string(), expected);
+  }
+
+  #[rstest]
+  #[case("12:30", "12:30:00")]
+  #[case("23:59:59", "23:59:59")]
+  #[case("00:00:01.5", "00:00:01.5")]
+  #[case("08:00:00.000123", "08:00:00.000123")]
+  fn test_parse_and_display_time(
+    #[case] input: &str,
+    #[case] expected: &str,
+  ) {
+    assert_eq!(input.parse::<Time>().unwrap().to_string(), expected);
+  }
+
+  #[rstest]
+  #[case("2024-03-01 12:30:00", "2024-03-01 12:30:00")]
+  #[case("2024-03-01T08:15", "2024-03-01 08:15:00")]
+  #[case("1969-12-31 23:59:59.25", "1969-12-31 23:59:59.25")]
+  #[case("2024-03-01", "2024-03-01 00:00:00")]
+  fn test_parse_and_display_timestamp(
+    #[case] input: &str,
+    #[case] expected: &str,
+  ) {
+    assert_eq!(input.parse::<Timestamp>().unwrap().to_string(), expected);
+  }
+
+  #[rstest]
+  #[case("2023-02-29")]
+  #[case("2024-13-01")]
+  #[case("2024-1-01")]
+  #[case("+024-01-01")]
+  #[case("24:00")]
+  #[case("12:60:00")]
+  #[case("12:00:00.1234567")]
+  #[case("12:00.5")]
+  #[case("2024-03-01 25:00")]
+  #[case("")]
+  fn test_parse_invalid_date_time(
+    #[case] input: &str,
+  ) {
+    assert!(input.parse::<Date>().is_err());
+    assert!(input.parse::<Time>().is_err());
+    assert!(input.parse::<Timestamp>().is_err());
+  }
+
+  #[test]
+  fn test_date_time_ordering() {
+    let dates = ["1969-12-31", "1970-01-01", "2000-02-29", "2000-03-01"]
+      .iter()
+      .map(|s| s.parse::<Date>().unwrap())
+      .collect::<Vec<Date>>();
+    assert!(dates.windows(2).all(|pair| pair[0] < pair[1]));
+
+    assert!("09:59:59.9".parse::<Time>().unwrap() < "10:00".parse::<Time>().unwrap());
+    assert_eq!(Timestamp::from(dates[1]), "1970-01-01 00:00:00".parse::<Timestamp>().unwrap());
+    assert!(Timestamp::from(dates[0]) < "1969-12-31 00:00:00.1".parse::<Timestamp>().unwrap());
+  }
+}
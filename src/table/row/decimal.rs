@@ -0,0 +1,382 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Neg;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::NollaDBError;
+
+// 最多 38 位有效数字，小数点后面最多 38 位
+const MAX_PRECISION: u32 = 38;
+
+// 精确的十进制数，值为 mantissa / 10^scale
+// 构造的时候会去掉小数部分末尾的 0，所以 1.50 和 1.5 是同一个值，可以直接作为索引的 key
+#[derive(Deserialize, Serialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Decimal {
+  mantissa: i128,
+  scale: u32,
+}
+
+fn get_power_of_ten(exponent: u32) -> Option<i128> {
+  10i128.checked_pow(exponent)
+}
+
+// 除法的结果四舍五入（远离 0 的方向）
+fn get_rounded_quotient(dividend: i128, divisor: i128) -> Option<i128> {
+  let quotient = dividend.checked_div(divisor)?;
+  let remainder = dividend % divisor;
+  if remainder.unsigned_abs() >= divisor.unsigned_abs() - remainder.unsigned_abs() {
+    quotient.checked_add(if (dividend < 0) == (divisor < 0) { 1 } else { -1 })
+  } else {
+    Some(quotient)
+  }
+}
+
+// 两个 u128 相乘，结果用 4 个 u64 表示，低位在前
+fn get_wide_product(a: u128, b: u128) -> [u64; 4] {
+  let a = [a as u64, (a >> 64) as u64];
+  let b = [b as u64, (b >> 64) as u64];
+  let mut product = [0u64; 4];
+  for i in 0..2 {
+    let mut carry: u128 = 0;
+    for j in 0..2 {
+      let t = u128::from(a[i]) * u128::from(b[j]) + u128::from(product[i + j]) + carry;
+      product[i + j] = t as u64;
+      carry = t >> 64;
+    }
+    product[i + 2] = carry as u64;
+  }
+  product
+}
+
+// 除以 10，返回余数
+fn divide_wide_by_ten(n: &mut [u64; 4]) -> u64 {
+  let mut remainder: u128 = 0;
+  for limb in n.iter_mut().rev() {
+    let current = (remainder << 64) | u128::from(*limb);
+    *limb = (current / 10) as u64;
+    remainder = current % 10;
+  }
+  remainder as u64
+}
+
+// 小于 10^38 的话返回对应的 u128
+fn get_narrow_product(n: &[u64; 4]) -> Option<u128> {
+  let narrow = (u128::from(n[1]) << 64) | u128::from(n[0]);
+  if n[2] != 0 || n[3] != 0 || narrow >= get_power_of_ten(MAX_PRECISION)? as u128 {
+    return None;
+  }
+  Some(narrow)
+}
+
+impl Decimal {
+  // 超出精度的话返回 None，小数点后面超过 38 位的部分会四舍五入
+  pub fn new(mantissa: i128, scale: u32) -> Option<Decimal> {
+    let (mut mantissa, mut scale) = if scale > MAX_PRECISION {
+      (get_rounded_quotient(mantissa, get_power_of_ten(scale - MAX_PRECISION)?)?, MAX_PRECISION)
+    } else {
+      (mantissa, scale)
+    };
+    while scale > 0 && mantissa % 10 == 0 {
+      mantissa /= 10;
+      scale -= 1;
+    }
+
+    if mantissa.unsigned_abs() >= get_power_of_ten(MAX_PRECISION)?.unsigned_abs() {
+      return None;
+    }
+    Some(Decimal { mantissa, scale })
+  }
+
+  // f64 先转成最短的十进制表示，所以 0.1 转换之后就是 0.1
+  pub fn from_real(r: f64) -> Option<Decimal> {
+    if !r.is_finite() {
+      return None;
+    }
+    r.to_string().parse::<Decimal>().ok()
+  }
+
+  pub fn to_real(self) -> f64 {
+    self.to_string().parse::<f64>().unwrap_or(f64::NAN)
+  }
+
+  // 没有小数部分并且在 i64 范围内的话可以转换成 Integer
+  pub fn to_integer(self) -> Option<i64> {
+    if self.scale != 0 {
+      return None;
+    }
+    i64::try_from(self.mantissa).ok()
+  }
+
+  // 保留 digits 位小数，四舍五入（远离 0 的方向）
+  pub fn round(self, digits: u32) -> Decimal {
+    if self.scale <= digits {
+      return self;
+    }
+    get_power_of_ten(self.scale - digits)
+      .and_then(|divisor| get_rounded_quotient(self.mantissa, divisor))
+      .and_then(|mantissa| Decimal::new(mantissa, digits))
+      .unwrap_or(self)
+  }
+
+  // 把两个数转换成相同的 scale
+  fn get_rescaled_mantissas(self, other: Decimal) -> Option<(i128, i128, u32)> {
+    let scale = self.scale.max(other.scale);
+    Some((
+      self.mantissa.checked_mul(get_power_of_ten(scale - self.scale)?)?,
+      other.mantissa.checked_mul(get_power_of_ten(scale - other.scale)?)?,
+      scale,
+    ))
+  }
+
+  pub fn checked_add(self, other: Decimal) -> Option<Decimal> {
+    let (a, b, scale) = self.get_rescaled_mantissas(other)?;
+    Decimal::new(a.checked_add(b)?, scale)
+  }
+
+  pub fn checked_sub(self, other: Decimal) -> Option<Decimal> {
+    let (a, b, scale) = self.get_rescaled_mantissas(other)?;
+    Decimal::new(a.checked_sub(b)?, scale)
+  }
+
+  // 两个 mantissa 的乘积最多有 76 位，直接相乘会溢出
+  // 所以先算出完整的乘积，再去掉小数部分末尾超出精度的位数，最后四舍五入
+  pub fn checked_mul(self, other: Decimal) -> Option<Decimal> {
+    let mut product = get_wide_product(self.mantissa.unsigned_abs(), other.mantissa.unsigned_abs());
+    let mut scale = self.scale + other.scale;
+    let mut removed_digit = 0;
+    while scale > 0 && (scale > MAX_PRECISION || get_narrow_product(&product).is_none()) {
+      removed_digit = divide_wide_by_ten(&mut product);
+      scale -= 1;
+    }
+
+    let mut mantissa = get_narrow_product(&product)? as i128;
+    if removed_digit >= 5 {
+      mantissa += 1;
+    }
+    Decimal::new(if (self.mantissa < 0) != (other.mantissa < 0) { -mantissa } else { mantissa }, scale)
+  }
+
+  // DECIMAL(p, s) 的列上保留 s 位小数，四舍五入之后整数部分超过 p - s 位的话返回 None
+  pub fn round_to_precision(self, precision: u32, scale: u32) -> Option<Decimal> {
+    let rounded = self.round(scale);
+    let integer_part = rounded.mantissa.unsigned_abs() / get_power_of_ten(rounded.scale)? as u128;
+    if integer_part >= get_power_of_ten(precision.checked_sub(scale)?)? as u128 {
+      return None;
+    }
+    Some(rounded)
+  }
+
+  // 除不尽的话结果比两边最大的 scale 多保留 6 位小数
+  pub fn checked_div(self, other: Decimal) -> Option<Decimal> {
+    if other.mantissa == 0 {
+      return None;
+    }
+    let scale = (self.scale.max(other.scale) + 6).min(MAX_PRECISION);
+    // self / other = self.mantissa * 10^(other.scale + scale - self.scale) / other.mantissa / 10^scale
+    let (dividend, divisor) = if other.scale + scale >= self.scale {
+      (self.mantissa.checked_mul(get_power_of_ten(other.scale + scale - self.scale)?)?, other.mantissa)
+    } else {
+      (self.mantissa, other.mantissa.checked_mul(get_power_of_ten(self.scale - other.scale - scale)?)?)
+    };
+    Decimal::new(get_rounded_quotient(dividend, divisor)?, scale)
+  }
+
+  // 余数的符号和被除数相同
+  pub fn checked_rem(self, other: Decimal) -> Option<Decimal> {
+    let (a, b, scale) = self.get_rescaled_mantissas(other)?;
+    Decimal::new(a.checked_rem(b)?, scale)
+  }
+}
+
+impl Neg for Decimal {
+  type Output = Decimal;
+
+  fn neg(self) -> Decimal {
+    Decimal { mantissa: -self.mantissa, scale: self.scale }
+  }
+}
+
+impl From<i64> for Decimal {
+  fn from(i: i64) -> Self {
+    Decimal { mantissa: i128::from(i), scale: 0 }
+  }
+}
+
+// 先比较整数部分，相同的话再比较小数部分，这样不会因为对齐 scale 而溢出
+impl Ord for Decimal {
+  fn cmp(&self, other: &Self) -> Ordering {
+    let self_unit = 10i128.pow(self.scale);
+    let other_unit = 10i128.pow(other.scale);
+    let scale = self.scale.max(other.scale);
+    (self.mantissa / self_unit)
+      .cmp(&(other.mantissa / other_unit))
+      .then_with(|| {
+        (self.mantissa % self_unit * 10i128.pow(scale - self.scale))
+          .cmp(&(other.mantissa % other_unit * 10i128.pow(scale - other.scale)))
+      })
+  }
+}
+
+impl PartialOrd for Decimal {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+// [+-]digits[.digits]，不支持科学计数法
+impl FromStr for Decimal {
+  type Err = NollaDBError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid_decimal_error = || NollaDBError::Internal(format!("Invalid decimal '{}'", s));
+    let trimmed = s.trim();
+    let (is_negative, unsigned) = match trimmed.strip_prefix('-') {
+      Some(unsigned) => (true, unsigned),
+      None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+    };
+    let (integer_part, fraction_part) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if integer_part.is_empty() && fraction_part.is_empty()
+      || !integer_part.bytes().chain(fraction_part.bytes()).all(|b| b.is_ascii_digit()) {
+      return Err(invalid_decimal_error());
+    }
+
+    // 去掉整数部分前面以及小数部分后面多余的 0，剩下的有效数字超过 i128 的范围的话就是超出了精度
+    let fraction_part = fraction_part.trim_end_matches('0');
+    let digits = format!("{}{}", integer_part, fraction_part);
+    let digits = digits.trim_start_matches('0');
+    let mantissa = if digits.is_empty() { 0 } else { digits.parse::<i128>().map_err(|_| invalid_decimal_error())? };
+    Decimal::new(if is_negative { -mantissa } else { mantissa }, fraction_part.len() as u32)
+      .ok_or_else(invalid_decimal_error)
+  }
+}
+
+impl fmt::Display for Decimal {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let digits = format!("{:0>width$}", self.mantissa.unsigned_abs(), width = self.scale as usize + 1);
+    let (integer_part, fraction_part) = digits.split_at(digits.len() - self.scale as usize);
+    if self.mantissa < 0 {
+      f.write_str("-")?;
+    }
+    if fraction_part.is_empty() {
+      f.write_str(integer_part)
+    } else {
+      write!(f, "{}.{}", integer_part, fraction_part)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+
+  fn decimal(s: &str) -> Decimal {
+    s.parse::<Decimal>().unwrap()
+  }
+
+  #[rstest]
+  #[case("1.50", "1.5")]
+  #[case("-0.001", "-0.001")]
+  #[case("+007", "7")]
+  #[case(".5", "0.5")]
+  #[case("10.", "10")]
+  #[case("-0.0", "0")]
+  #[case("12345678901234567890.123456789012345678", "12345678901234567890.123456789012345678")]
+  fn test_parse_and_display_decimal(
+    #[case] input: &str,
+    #[case] expected: &str,
+  ) {
+    assert_eq!(decimal(input).to_string(), expected);
+  }
+
+  #[rstest]
+  #[case("")]
+  #[case("-")]
+  #[case(".")]
+  #[case("1e5")]
+  #[case("1.2.3")]
+  #[case("123456789012345678901234567890123456789")]
+  fn test_parse_invalid_decimal(
+    #[case] input: &str,
+  ) {
+    assert!(input.parse::<Decimal>().is_err());
+  }
+
+  #[rstest]
+  #[case("0.1", "0.2", Ordering::Less)]
+  #[case("1.5", "1.50", Ordering::Equal)]
+  #[case("-1.5", "-1.2", Ordering::Less)]
+  #[case("100", "99.999", Ordering::Greater)]
+  #[case("-0.5", "0.1", Ordering::Less)]
+  #[case("12345678901234567890123456789012345678", "0.00000000000000000000000000000000000001", Ordering::Greater)]
+  fn test_compare_decimal(
+    #[case] left: &str,
+    #[case] right: &str,
+    #[case] expected: Ordering,
+  ) {
+    assert_eq!(decimal(left).cmp(&decimal(right)), expected);
+  }
+
+  #[test]
+  fn test_decimal_arithmetic() {
+    assert_eq!(decimal("0.1").checked_add(decimal("0.2")), Some(decimal("0.3")));
+    assert_eq!(decimal("1.25").checked_sub(decimal("2")), Some(decimal("-0.75")));
+    assert_eq!(decimal("1.1").checked_mul(decimal("1.1")), Some(decimal("1.21")));
+    assert_eq!(decimal("1").checked_div(decimal("3")), Some(decimal("0.333333")));
+    assert_eq!(decimal("2").checked_div(decimal("3")), Some(decimal("0.666667")));
+    assert_eq!(decimal("-7.5").checked_rem(decimal("2")), Some(decimal("-1.5")));
+    assert_eq!(decimal("1").checked_div(decimal("0")), None);
+    assert_eq!(decimal("99999999999999999999999999999999999999").checked_add(decimal("1")), None);
+  }
+
+  #[rstest]
+  #[case("1.0000000000000000000000000000001", "1.0000000000000000000000000000001", Some("1.0000000000000000000000000000002"))]
+  #[case("-0.33333333333333333333333333333333333333", "3", Some("-0.99999999999999999999999999999999999999"))]
+  #[case("0.66666666666666666666666666666666666667", "0.5", Some("0.33333333333333333333333333333333333334"))]
+  #[case("9999999999999999999.9999999999999999999", "9999999999999999999.9999999999999999999", Some("99999999999999999999999999999999999998"))]
+  #[case("10000000000000000000", "10000000000000000000", None)]
+  #[case("12345678901234567890", "12345678901234567890", None)]
+  #[case("1234567890123456789", "12345678901234567890", Some("15241578753238836750190519987501905210"))]
+  #[case("0.00000000000000000001", "0.00000000000000000001", Some("0"))]
+  fn test_decimal_mul(
+    #[case] left: &str,
+    #[case] right: &str,
+    #[case] expected: Option<&str>,
+  ) {
+    assert_eq!(decimal(left).checked_mul(decimal(right)), expected.map(decimal));
+    assert_eq!(decimal(right).checked_mul(decimal(left)), expected.map(decimal));
+  }
+
+  #[rstest]
+  #[case("1.005", 5, 2, Some("1.01"))]
+  #[case("-999.994", 5, 2, Some("-999.99"))]
+  #[case("999.995", 5, 2, None)]
+  #[case("1000", 5, 2, None)]
+  #[case("0.5", 1, 1, Some("0.5"))]
+  #[case("1", 1, 1, None)]
+  #[case("123.4", 3, 0, Some("123"))]
+  fn test_round_to_precision(
+    #[case] input: &str,
+    #[case] precision: u32,
+    #[case] scale: u32,
+    #[case] expected: Option<&str>,
+  ) {
+    assert_eq!(decimal(input).round_to_precision(precision, scale), expected.map(decimal));
+  }
+
+  #[test]
+  fn test_convert_decimal() {
+    assert_eq!(Decimal::from_real(0.1), Some(decimal("0.1")));
+    assert_eq!(Decimal::from_real(f64::NAN), None);
+    assert_eq!(decimal("2.5").to_real(), 2.5);
+    assert_eq!(decimal("42").to_integer(), Some(42));
+    assert_eq!(decimal("4.2").to_integer(), None);
+    assert_eq!(Decimal::from(-3), decimal("-3"));
+    assert_eq!(decimal("2.345").round(2), decimal("2.35"));
+    assert_eq!(decimal("-2.5").round(0), decimal("-3"));
+    assert_eq!(decimal("1.2").round(3), decimal("1.2"));
+  }
+}
@@ -0,0 +1,330 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value as JsonValue, Number as JsonNumber};
+
+use crate::error::NollaDBError;
+use super::value::Value;
+
+// JSON 值，存的是 serde_json 输出的紧凑格式，对象的 key 保留插入的顺序（serde_json 的 preserve_order）
+// 和 SQLite 的 json() 一样只去掉多余的空白，两个 JSON 值相等当且仅当它们的文本相等，可以直接作为索引的 key
+#[derive(Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Debug, Clone)]
+pub struct Json(String);
+
+// JSON path 中的一步
+#[derive(PartialEq, Debug, Clone)]
+pub enum JsonPathStep {
+  // .key 或者 ."key"
+  Key(String),
+  // [N]
+  Index(usize),
+  // [#-N]，从数组末尾往前数
+  IndexFromEnd(usize),
+}
+
+// SQLite 风格的 JSON path，比如 $.a.b[0]、$."a b"[#-1]
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct JsonPath(Vec<JsonPathStep>);
+
+impl Json {
+  fn get_json_value(&self) -> JsonValue {
+    serde_json::from_str(&self.0).unwrap_or(JsonValue::Null)
+  }
+
+  // Integer、Real 以及 Boolean 可以直接转换成 JSON 的标量，NaN 和无穷大不是合法的 JSON 数字
+  pub fn from_scalar(value: &Value) -> Option<Json> {
+    match value {
+      Value::Integer(i) => Some(Json::from(&JsonValue::from(*i))),
+      Value::Real(r) => JsonNumber::from_f64(*r).map(|n| Json::from(&JsonValue::Number(n))),
+      Value::Bool(b) => Some(Json::from(&JsonValue::Bool(*b))),
+      _ => None,
+    }
+  }
+
+  // Text 转换成 JSON 的字符串，NULL 以及 Blob 之类没有对应 JSON 类型的值返回 None
+  pub fn from_sql_value(value: &Value) -> Option<Json> {
+    match value {
+      Value::Json(json) => Some(json.clone()),
+      Value::Text(s) => Some(Json::from(&JsonValue::String(s.to_string()))),
+      value => Json::from_scalar(value),
+    }
+  }
+
+  // 数组或者对象
+  pub fn is_container(&self) -> bool {
+    self.0.starts_with('[') || self.0.starts_with('{')
+  }
+
+  // 和 SQLite 的 json_type() 一样
+  pub fn get_type_name(&self) -> &'static str {
+    match self.get_json_value() {
+      JsonValue::Null => "null",
+      JsonValue::Bool(true) => "true",
+      JsonValue::Bool(false) => "false",
+      JsonValue::Number(n) if n.is_f64() => "real",
+      JsonValue::Number(_) => "integer",
+      JsonValue::String(_) => "text",
+      JsonValue::Array(_) => "array",
+      JsonValue::Object(_) => "object",
+    }
+  }
+
+  // 标量转换成对应的 SQL 值，超出 i64 范围的整数转换成 Real；数组和对象仍然是 JSON
+  pub fn get_sql_value(&self) -> Value {
+    match self.get_json_value() {
+      JsonValue::Null => Value::Null,
+      JsonValue::Bool(b) => Value::Bool(b),
+      JsonValue::Number(n) => match n.as_i64() {
+        Some(i) => Value::Integer(i),
+        None => Value::Real(n.as_f64().unwrap_or(f64::NAN)),
+      },
+      JsonValue::String(s) => Value::Text(s),
+      JsonValue::Array(_) | JsonValue::Object(_) => Value::Json(self.clone()),
+    }
+  }
+
+  // 按照 path 找到对应的子元素，找不到的话返回 None
+  pub fn extract(&self, path: &JsonPath) -> Option<Json> {
+    let mut json_value = self.get_json_value();
+    for step in &path.0 {
+      json_value = match (step, json_value) {
+        (JsonPathStep::Key(key), JsonValue::Object(mut object)) => object.remove(key)?,
+        (JsonPathStep::Index(i), JsonValue::Array(mut array)) if *i < array.len() => array.swap_remove(*i),
+        (JsonPathStep::IndexFromEnd(i), JsonValue::Array(mut array)) if *i >= 1 && *i <= array.len() => {
+          let len = array.len();
+          array.swap_remove(len - i)
+        },
+        _ => return None,
+      };
+    }
+
+    Some(Json::from(&json_value))
+  }
+
+  // 数组的每一个元素以及对象的每一个 key 对应的值，标量返回 None
+  pub fn get_children(&self) -> Option<Vec<(JsonPathStep, Json)>> {
+    match self.get_json_value() {
+      JsonValue::Array(array) => Some(
+        array
+          .iter()
+          .enumerate()
+          .map(|(i, json_value)| (JsonPathStep::Index(i), Json::from(json_value)))
+          .collect()
+      ),
+      JsonValue::Object(object) => Some(
+        object
+          .iter()
+          .map(|(key, json_value)| (JsonPathStep::Key(key.to_string()), Json::from(json_value)))
+          .collect()
+      ),
+      _ => None,
+    }
+  }
+
+  // 不是数组的话长度为 0
+  pub fn get_array_length(&self) -> usize {
+    match self.get_json_value() {
+      JsonValue::Array(array) => array.len(),
+      _ => 0,
+    }
+  }
+
+  // 把多个 JSON 值组成一个数组，None 对应数组中的 null
+  pub fn from_array(jsons: &[Option<Json>]) -> Json {
+    Json::from(&JsonValue::Array(
+      jsons
+        .iter()
+        .map(|json| json.as_ref().map_or(JsonValue::Null, |json| json.get_json_value()))
+        .collect()
+    ))
+  }
+}
+
+impl From<&JsonValue> for Json {
+  fn from(json_value: &JsonValue) -> Self {
+    Json(json_value.to_string())
+  }
+}
+
+impl FromStr for Json {
+  type Err = NollaDBError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    serde_json::from_str::<JsonValue>(s)
+      .map(|json_value| Json::from(&json_value))
+      .map_err(|_| NollaDBError::Internal(format!("Malformed JSON '{}'", s)))
+  }
+}
+
+impl fmt::Display for Json {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+impl JsonPath {
+  pub fn push(&mut self, step: JsonPathStep) {
+    self.0.push(step);
+  }
+}
+
+impl From<JsonPathStep> for JsonPath {
+  fn from(step: JsonPathStep) -> Self {
+    JsonPath(vec![step])
+  }
+}
+
+// $ 开头，后面是 .key、."key"、[N] 或者 [#-N]
+impl FromStr for JsonPath {
+  type Err = NollaDBError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid_path_error = || NollaDBError::Internal(format!("Invalid JSON path '{}'", s));
+    let mut rest = s.strip_prefix('$').ok_or_else(invalid_path_error)?;
+    let mut steps: Vec<JsonPathStep> = vec![];
+    while !rest.is_empty() {
+      if let Some(quoted) = rest.strip_prefix(".\"") {
+        let end = quoted.find('"').ok_or_else(invalid_path_error)?;
+        steps.push(JsonPathStep::Key(quoted[..end].to_string()));
+        rest = &quoted[end + 1..];
+      } else if let Some(key) = rest.strip_prefix('.') {
+        let end = key.find(['.', '[']).unwrap_or(key.len());
+        if end == 0 {
+          return Err(invalid_path_error());
+        }
+        steps.push(JsonPathStep::Key(key[..end].to_string()));
+        rest = &key[end..];
+      } else if let Some(index) = rest.strip_prefix('[') {
+        let end = index.find(']').ok_or_else(invalid_path_error)?;
+        let (digits, step): (&str, fn(usize) -> JsonPathStep) = match index[..end].strip_prefix("#-") {
+          Some(digits) => (digits, JsonPathStep::IndexFromEnd),
+          None => (&index[..end], JsonPathStep::Index),
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+          return Err(invalid_path_error());
+        }
+        steps.push(step(digits.parse::<usize>().map_err(|_| invalid_path_error())?));
+        rest = &index[end + 1..];
+      } else {
+        return Err(invalid_path_error());
+      }
+    }
+
+    Ok(JsonPath(steps))
+  }
+}
+
+// 只包含字母、数字和下划线的 key 不需要加引号
+impl fmt::Display for JsonPath {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("$")?;
+    for step in &self.0 {
+      match step {
+        JsonPathStep::Key(key) if !key.is_empty()
+          && key.chars().all(|c| c.is_alphanumeric() || c == '_') => write!(f, ".{}", key)?,
+        JsonPathStep::Key(key) => write!(f, ".\"{}\"", key)?,
+        JsonPathStep::Index(i) => write!(f, "[{}]", i)?,
+        JsonPathStep::IndexFromEnd(i) => write!(f, "[#-{}]", i)?,
+      }
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+
+  fn json(s: &str) -> Json {
+    s.parse::<Json>().unwrap()
+  }
+
+  #[rstest]
+  #[case(r#" {"b": 1, "a": [true, null, 1.5]} "#, r#"{"b":1,"a":[true,null,1.5]}"#)]
+  #[case(r#""x""#, r#""x""#)]
+  #[case("-12", "-12")]
+  fn test_parse_and_display_json(
+    #[case] input: &str,
+    #[case] expected: &str,
+  ) {
+    assert_eq!(json(input).to_string(), expected);
+  }
+
+  #[rstest]
+  #[case("")]
+  #[case("{a: 1}")]
+  #[case("[1, 2")]
+  #[case("'x'")]
+  fn test_parse_malformed_json(
+    #[case] input: &str,
+  ) {
+    assert!(input.parse::<Json>().is_err());
+  }
+
+  #[rstest]
+  #[case("$", r#"{"a":{"b":[1,2,3]},"c d":"x"}"#)]
+  #[case("$.a.b", "[1,2,3]")]
+  #[case("$.a.b[1]", "2")]
+  #[case("$.a.b[#-1]", "3")]
+  #[case(r#"$."c d""#, r#""x""#)]
+  fn test_extract_json(
+    #[case] path: &str,
+    #[case] expected: &str,
+  ) {
+    let path = path.parse::<JsonPath>().unwrap();
+    assert_eq!(json(r#"{"a": {"b": [1, 2, 3]}, "c d": "x"}"#).extract(&path), Some(json(expected)));
+  }
+
+  #[rstest]
+  #[case("$.x")]
+  #[case("$.a.b[3]")]
+  #[case("$.a.b[#-0]")]
+  #[case("$.a[0]")]
+  fn test_extract_missing_json(
+    #[case] path: &str,
+  ) {
+    let path = path.parse::<JsonPath>().unwrap();
+    assert_eq!(json(r#"{"a": {"b": [1, 2, 3]}}"#).extract(&path), None);
+  }
+
+  #[rstest]
+  #[case("a.b")]
+  #[case("$.")]
+  #[case("$[x]")]
+  #[case("$[1")]
+  #[case(r#"$."a"#)]
+  fn test_parse_invalid_json_path(
+    #[case] input: &str,
+  ) {
+    assert!(input.parse::<JsonPath>().is_err());
+  }
+
+  #[test]
+  fn test_json_path_display() {
+    let mut path = "$.a".parse::<JsonPath>().unwrap();
+    path.push(JsonPathStep::Key("b c".to_string()));
+    path.push(JsonPathStep::Index(0));
+    path.push(JsonPathStep::IndexFromEnd(2));
+    assert_eq!(path.to_string(), r#"$.a."b c"[0][#-2]"#);
+  }
+
+  #[rstest]
+  #[case("null", "null", Value::Null)]
+  #[case("true", "true", Value::Bool(true))]
+  #[case("42", "integer", Value::Integer(42))]
+  #[case("1.5", "real", Value::Real(1.5))]
+  #[case(r#""x""#, "text", Value::Text("x".to_string()))]
+  #[case("[1]", "array", Value::Json(json("[1]")))]
+  #[case(r#"{"a":1}"#, "object", Value::Json(json(r#"{"a":1}"#)))]
+  fn test_json_type_and_sql_value(
+    #[case] input: &str,
+    #[case] expected_type_name: &str,
+    #[case] expected_value: Value,
+  ) {
+    assert_eq!(json(input).get_type_name(), expected_type_name);
+    assert_eq!(json(input).get_sql_value(), expected_value);
+  }
+}
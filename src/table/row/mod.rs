@@ -1,14 +1,35 @@
+pub mod value;
+pub mod date_time;
+pub mod decimal;
+pub mod json;
+
 use std::collections::{BTreeMap};
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Result, NollaDBError};
+use crate::table::column::data_type::DataType;
+use value::Value;
+use date_time::{Date, Time, Timestamp};
+use decimal::Decimal;
+use json::Json;
+
+// 每一列都用 row id 作为 key，这样每一行在每一列上都有对应的 key
+// value 为 None 表示这一行在这一列上的值是 NULL
+// 保存到文件时是按照 variant 的顺序编码的，新的类型只能加在最后面
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
 pub enum Row {
-  Integer(BTreeMap<i64, i32>),
-  Bool(BTreeMap<i64, bool>),
-  Text(BTreeMap<i64, String>),
-  Real(BTreeMap<i64, f32>),
+  Integer(BTreeMap<i64, Option<i64>>),
+  Bool(BTreeMap<i64, Option<bool>>),
+  Text(BTreeMap<i64, Option<String>>),
+  Real(BTreeMap<i64, Option<f64>>),
   None,
+  Blob(BTreeMap<i64, Option<Vec<u8>>>),
+  Date(BTreeMap<i64, Option<Date>>),
+  Time(BTreeMap<i64, Option<Time>>),
+  Timestamp(BTreeMap<i64, Option<Timestamp>>),
+  Decimal(BTreeMap<i64, Option<Decimal>>),
+  Json(BTreeMap<i64, Option<Json>>),
 }
 
 // 这里是通过 Row 构建 Column
@@ -16,23 +37,331 @@ pub enum Row {
 // Column 有一个 Index，这个 Index 也是由 BTreeMap 管理
 // 这个 Index 里的 BTreeMap 存的 key/value 跟 Row 里面的 key/value 刚好相反
 impl Row {
+  // 创建一个和列类型对应的空的 Row
+  pub fn new(column_datatype: &DataType) -> Self {
+    match column_datatype {
+      DataType::Integer => Row::Integer(BTreeMap::new()),
+      DataType::Text => Row::Text(BTreeMap::new()),
+      DataType::Bool => Row::Bool(BTreeMap::new()),
+      DataType::Real => Row::Real(BTreeMap::new()),
+      DataType::Blob => Row::Blob(BTreeMap::new()),
+      DataType::Date => Row::Date(BTreeMap::new()),
+      DataType::Time => Row::Time(BTreeMap::new()),
+      DataType::Timestamp => Row::Timestamp(BTreeMap::new()),
+      DataType::Decimal => Row::Decimal(BTreeMap::new()),
+      DataType::Json => Row::Json(BTreeMap::new()),
+      DataType::None | DataType::Invalid => Row::None,
+    }
+  }
+
   pub fn get_number_of_element_in_column(&self) -> usize {
     match self {
       Row::Integer(tree) => tree.len(),
       Row::Bool(tree) => tree.len(),
       Row::Text(tree) => tree.len(),
       Row::Real(tree) => tree.len(),
-      Row::None => panic!("Found None Type in columns"),
+      Row::Blob(tree) => tree.len(),
+      Row::Date(tree) => tree.len(),
+      Row::Time(tree) => tree.len(),
+      Row::Timestamp(tree) => tree.len(),
+      Row::Decimal(tree) => tree.len(),
+      Row::Json(tree) => tree.len(),
+      Row::None => 0,
+    }
+  }
+
+  // 根据 row id 拿到这一列上对应的值
+  pub fn get_value(&self, row_id: &i64) -> Value {
+    match self {
+      Row::Integer(tree) => tree.get(row_id).cloned().flatten().map_or(Value::Null, Value::Integer),
+      Row::Bool(tree) => tree.get(row_id).cloned().flatten().map_or(Value::Null, Value::Bool),
+      Row::Text(tree) => tree.get(row_id).cloned().flatten().map_or(Value::Null, Value::Text),
+      Row::Real(tree) => tree.get(row_id).cloned().flatten().map_or(Value::Null, Value::Real),
+      Row::Blob(tree) => tree.get(row_id).cloned().flatten().map_or(Value::Null, Value::Blob),
+      Row::Date(tree) => tree.get(row_id).cloned().flatten().map_or(Value::Null, Value::Date),
+      Row::Time(tree) => tree.get(row_id).cloned().flatten().map_or(Value::Null, Value::Time),
+      Row::Timestamp(tree) => tree.get(row_id).cloned().flatten().map_or(Value::Null, Value::Timestamp),
+      Row::Decimal(tree) => tree.get(row_id).cloned().flatten().map_or(Value::Null, Value::Decimal),
+      Row::Json(tree) => tree.get(row_id).cloned().flatten().map_or(Value::Null, Value::Json),
+      Row::None => Value::Null,
+    }
+  }
+
+  // 按照 row id 的顺序拿到这一列上所有的值
+  pub fn get_values(&self) -> Vec<Value> {
+    match self {
+      Row::Integer(tree) => tree.values().map(|value| value.map_or(Value::Null, Value::Integer)).collect(),
+      Row::Bool(tree) => tree.values().map(|value| value.map_or(Value::Null, Value::Bool)).collect(),
+      Row::Text(tree) => tree.values().map(|value| value.clone().map_or(Value::Null, Value::Text)).collect(),
+      Row::Real(tree) => tree.values().map(|value| value.map_or(Value::Null, Value::Real)).collect(),
+      Row::Blob(tree) => tree.values().map(|value| value.clone().map_or(Value::Null, Value::Blob)).collect(),
+      Row::Date(tree) => tree.values().map(|value| value.map_or(Value::Null, Value::Date)).collect(),
+      Row::Time(tree) => tree.values().map(|value| value.map_or(Value::Null, Value::Time)).collect(),
+      Row::Timestamp(tree) => tree.values().map(|value| value.map_or(Value::Null, Value::Timestamp)).collect(),
+      Row::Decimal(tree) => tree.values().map(|value| value.map_or(Value::Null, Value::Decimal)).collect(),
+      Row::Json(tree) => tree.values().map(|value| value.clone().map_or(Value::Null, Value::Json)).collect(),
+      Row::None => vec![],
+    }
+  }
+
+  pub fn get_row_ids(&self) -> Vec<i64> {
+    match self {
+      Row::Integer(tree) => tree.keys().cloned().collect(),
+      Row::Bool(tree) => tree.keys().cloned().collect(),
+      Row::Text(tree) => tree.keys().cloned().collect(),
+      Row::Real(tree) => tree.keys().cloned().collect(),
+      Row::Blob(tree) => tree.keys().cloned().collect(),
+      Row::Date(tree) => tree.keys().cloned().collect(),
+      Row::Time(tree) => tree.keys().cloned().collect(),
+      Row::Timestamp(tree) => tree.keys().cloned().collect(),
+      Row::Decimal(tree) => tree.keys().cloned().collect(),
+      Row::Json(tree) => tree.keys().cloned().collect(),
+      Row::None => vec![],
+    }
+  }
+
+  // row id 是按照从小到大的顺序存放的，最后一个就是最大的 row id
+  pub fn get_max_row_id(&self) -> Option<i64> {
+    match self {
+      Row::Integer(tree) => tree.keys().next_back().cloned(),
+      Row::Bool(tree) => tree.keys().next_back().cloned(),
+      Row::Text(tree) => tree.keys().next_back().cloned(),
+      Row::Real(tree) => tree.keys().next_back().cloned(),
+      Row::Blob(tree) => tree.keys().next_back().cloned(),
+      Row::Date(tree) => tree.keys().next_back().cloned(),
+      Row::Time(tree) => tree.keys().next_back().cloned(),
+      Row::Timestamp(tree) => tree.keys().next_back().cloned(),
+      Row::Decimal(tree) => tree.keys().next_back().cloned(),
+      Row::Json(tree) => tree.keys().next_back().cloned(),
+      Row::None => None,
+    }
+  }
+
+  // 把值转换成这一列对应的类型
+  pub fn cast_value(&self, value: &Value) -> Result<Value> {
+    let type_mismatch_error = || NollaDBError::TypeMismatch(
+      format!(
+        "Can not store {} value '{}' into {} column",
+        value.get_type_name(), value, self.get_type_name()
+      )
+    );
+
+    match (self, value) {
+      (_, Value::Null) => Ok(Value::Null),
+      (Row::Integer(_), Value::Integer(i)) => Ok(Value::Integer(*i)),
+      (Row::Integer(_), Value::Text(s)) => s
+        .parse::<i64>()
+        .map(Value::Integer)
+        .map_err(|_| type_mismatch_error()),
+      (Row::Integer(_), Value::Decimal(d)) => d
+        .to_integer()
+        .map(Value::Integer)
+        .ok_or_else(type_mismatch_error),
+      (Row::Real(_), Value::Real(r)) => Ok(Value::Real(*r)),
+      (Row::Real(_), Value::Integer(i)) => Ok(Value::Real(*i as f64)),
+      (Row::Real(_), Value::Decimal(d)) => Ok(Value::Real(d.to_real())),
+      (Row::Real(_), Value::Text(s)) => s
+        .parse::<f64>()
+        .map(Value::Real)
+        .map_err(|_| type_mismatch_error()),
+      (Row::Bool(_), Value::Bool(b)) => Ok(Value::Bool(*b)),
+      (Row::Bool(_), Value::Text(s)) => s
+        .parse::<bool>()
+        .map(Value::Bool)
+        .map_err(|_| type_mismatch_error()),
+      (Row::Blob(_), Value::Blob(bytes)) => Ok(Value::Blob(bytes.clone())),
+      (Row::Date(_), Value::Date(d)) => Ok(Value::Date(*d)),
+      (Row::Date(_), Value::Text(s)) => s
+        .parse::<Date>()
+        .map(Value::Date)
+        .map_err(|_| type_mismatch_error()),
+      (Row::Time(_), Value::Time(t)) => Ok(Value::Time(*t)),
+      (Row::Time(_), Value::Text(s)) => s
+        .parse::<Time>()
+        .map(Value::Time)
+        .map_err(|_| type_mismatch_error()),
+      (Row::Timestamp(_), Value::Timestamp(ts)) => Ok(Value::Timestamp(*ts)),
+      (Row::Timestamp(_), Value::Date(d)) => Ok(Value::Timestamp(Timestamp::from(*d))),
+      (Row::Timestamp(_), Value::Text(s)) => s
+        .parse::<Timestamp>()
+        .map(Value::Timestamp)
+        .map_err(|_| type_mismatch_error()),
+      (Row::Decimal(_), Value::Decimal(d)) => Ok(Value::Decimal(*d)),
+      (Row::Decimal(_), Value::Integer(i)) => Ok(Value::Decimal(Decimal::from(*i))),
+      (Row::Decimal(_), Value::Real(r)) => Decimal::from_real(*r)
+        .map(Value::Decimal)
+        .ok_or_else(type_mismatch_error),
+      (Row::Decimal(_), Value::Text(s)) => s
+        .parse::<Decimal>()
+        .map(Value::Decimal)
+        .map_err(|_| type_mismatch_error()),
+      // 写入 JSON 列的 Text 必须是合法的 JSON，数字和 Boolean 直接作为 JSON 的标量
+      (Row::Json(_), Value::Json(json)) => Ok(Value::Json(json.clone())),
+      (Row::Json(_), Value::Text(s)) => s
+        .parse::<Json>()
+        .map(Value::Json)
+        .map_err(|_| type_mismatch_error()),
+      (Row::Json(_), Value::Integer(_) | Value::Real(_) | Value::Bool(_)) => Json::from_scalar(value)
+        .map(Value::Json)
+        .ok_or_else(type_mismatch_error),
+      // Blob 的内容不一定是合法的字符串，所以不能存到 Text 列
+      (Row::Text(_), Value::Blob(_)) => Err(type_mismatch_error()),
+      (Row::Text(_), value) => Ok(Value::Text(value.to_string())),
+      _ => Err(type_mismatch_error()),
     }
   }
 
-  pub fn get_serialized_column_data(&self) -> Vec<String> {
+  // 设置 row id 对应的值，value 需要先经过 cast_value 转换
+  pub fn set_value(&mut self, row_id: i64, value: &Value) -> Result<()> {
+    match (self, value) {
+      (Row::Integer(tree), Value::Integer(i)) => { tree.insert(row_id, Some(*i)); },
+      (Row::Integer(tree), Value::Null) => { tree.insert(row_id, None); },
+      (Row::Bool(tree), Value::Bool(b)) => { tree.insert(row_id, Some(*b)); },
+      (Row::Bool(tree), Value::Null) => { tree.insert(row_id, None); },
+      (Row::Text(tree), Value::Text(s)) => { tree.insert(row_id, Some(s.to_string())); },
+      (Row::Text(tree), Value::Null) => { tree.insert(row_id, None); },
+      (Row::Real(tree), Value::Real(r)) => { tree.insert(row_id, Some(*r)); },
+      (Row::Real(tree), Value::Null) => { tree.insert(row_id, None); },
+      (Row::Blob(tree), Value::Blob(bytes)) => { tree.insert(row_id, Some(bytes.clone())); },
+      (Row::Blob(tree), Value::Null) => { tree.insert(row_id, None); },
+      (Row::Date(tree), Value::Date(d)) => { tree.insert(row_id, Some(*d)); },
+      (Row::Date(tree), Value::Null) => { tree.insert(row_id, None); },
+      (Row::Time(tree), Value::Time(t)) => { tree.insert(row_id, Some(*t)); },
+      (Row::Time(tree), Value::Null) => { tree.insert(row_id, None); },
+      (Row::Timestamp(tree), Value::Timestamp(ts)) => { tree.insert(row_id, Some(*ts)); },
+      (Row::Timestamp(tree), Value::Null) => { tree.insert(row_id, None); },
+      (Row::Decimal(tree), Value::Decimal(d)) => { tree.insert(row_id, Some(*d)); },
+      (Row::Decimal(tree), Value::Null) => { tree.insert(row_id, None); },
+      (Row::Json(tree), Value::Json(json)) => { tree.insert(row_id, Some(json.clone())); },
+      (Row::Json(tree), Value::Null) => { tree.insert(row_id, None); },
+      (row, value) => return Err(NollaDBError::TypeMismatch(
+        format!(
+          "Can not store {} value '{}' into {} column",
+          value.get_type_name(), value, row.get_type_name()
+        )
+      )),
+    }
+
+    Ok(())
+  }
+
+  // 把 old_row_id 对应的值挪到 new_row_id 下面
+  pub fn move_value(&mut self, old_row_id: i64, new_row_id: i64) {
     match self {
-      Row::Integer(tree) => tree.iter().map(|(_key, value)| value.to_string()).collect(),
-      Row::Bool(tree) => tree.iter().map(|(_key, value)| value.to_string()).collect(),
-      Row::Text(tree) => tree.iter().map(|(_key, value)| value.to_string()).collect(),
-      Row::Real(tree) => tree.iter().map(|(_key, value)| value.to_string()).collect(),
-      Row::None => panic!("Found None Type in columns"),
+      Row::Integer(tree) => if let Some(value) = tree.remove(&old_row_id) { tree.insert(new_row_id, value); },
+      Row::Bool(tree) => if let Some(value) = tree.remove(&old_row_id) { tree.insert(new_row_id, value); },
+      Row::Text(tree) => if let Some(value) = tree.remove(&old_row_id) { tree.insert(new_row_id, value); },
+      Row::Real(tree) => if let Some(value) = tree.remove(&old_row_id) { tree.insert(new_row_id, value); },
+      Row::Blob(tree) => if let Some(value) = tree.remove(&old_row_id) { tree.insert(new_row_id, value); },
+      Row::Date(tree) => if let Some(value) = tree.remove(&old_row_id) { tree.insert(new_row_id, value); },
+      Row::Time(tree) => if let Some(value) = tree.remove(&old_row_id) { tree.insert(new_row_id, value); },
+      Row::Timestamp(tree) => if let Some(value) = tree.remove(&old_row_id) { tree.insert(new_row_id, value); },
+      Row::Decimal(tree) => if let Some(value) = tree.remove(&old_row_id) { tree.insert(new_row_id, value); },
+      Row::Json(tree) => if let Some(value) = tree.remove(&old_row_id) { tree.insert(new_row_id, value); },
+      Row::None => {},
     }
   }
+
+  // 删除 row id 对应的值，并返回被删除的值
+  pub fn remove_value(&mut self, row_id: &i64) -> Value {
+    match self {
+      Row::Integer(tree) => tree.remove(row_id).flatten().map_or(Value::Null, Value::Integer),
+      Row::Bool(tree) => tree.remove(row_id).flatten().map_or(Value::Null, Value::Bool),
+      Row::Text(tree) => tree.remove(row_id).flatten().map_or(Value::Null, Value::Text),
+      Row::Real(tree) => tree.remove(row_id).flatten().map_or(Value::Null, Value::Real),
+      Row::Blob(tree) => tree.remove(row_id).flatten().map_or(Value::Null, Value::Blob),
+      Row::Date(tree) => tree.remove(row_id).flatten().map_or(Value::Null, Value::Date),
+      Row::Time(tree) => tree.remove(row_id).flatten().map_or(Value::Null, Value::Time),
+      Row::Timestamp(tree) => tree.remove(row_id).flatten().map_or(Value::Null, Value::Timestamp),
+      Row::Decimal(tree) => tree.remove(row_id).flatten().map_or(Value::Null, Value::Decimal),
+      Row::Json(tree) => tree.remove(row_id).flatten().map_or(Value::Null, Value::Json),
+      Row::None => Value::Null,
+    }
+  }
+
+  pub fn get_type_name(&self) -> &'static str {
+    match self {
+      Row::Integer(_) => "Integer",
+      Row::Bool(_) => "Boolean",
+      Row::Text(_) => "Text",
+      Row::Real(_) => "Real",
+      Row::Blob(_) => "Blob",
+      Row::Date(_) => "Date",
+      Row::Time(_) => "Time",
+      Row::Timestamp(_) => "Timestamp",
+      Row::Decimal(_) => "Decimal",
+      Row::Json(_) => "Json",
+      Row::None => "None",
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::{assert_eq};
+
+  #[rstest]
+  #[case(Row::Integer(BTreeMap::new()), Value::Text("1".to_string()), Value::Integer(1))]
+  #[case(Row::Real(BTreeMap::new()), Value::Integer(1), Value::Real(1.0))]
+  #[case(Row::Bool(BTreeMap::new()), Value::Text("true".to_string()), Value::Bool(true))]
+  #[case(Row::Text(BTreeMap::new()), Value::Integer(1), Value::Text("1".to_string()))]
+  #[case(Row::Integer(BTreeMap::new()), Value::Null, Value::Null)]
+  #[case(Row::Date(BTreeMap::new()), Value::Text("2024-02-29".to_string()), Value::Date(Date::new(2024, 2, 29).unwrap()))]
+  #[case(
+    Row::Timestamp(BTreeMap::new()),
+    Value::Date(Date::new(2024, 2, 29).unwrap()),
+    Value::Timestamp(Timestamp::new(Date::new(2024, 2, 29).unwrap(), Time::new(0, 0, 0, 0).unwrap()))
+  )]
+  #[case(Row::Decimal(BTreeMap::new()), Value::Real(0.1), Value::Decimal("0.1".parse::<Decimal>().unwrap()))]
+  #[case(Row::Integer(BTreeMap::new()), Value::Decimal("42".parse::<Decimal>().unwrap()), Value::Integer(42))]
+  #[case(Row::Text(BTreeMap::new()), Value::Time(Time::new(8, 30, 0, 0).unwrap()), Value::Text("08:30:00".to_string()))]
+  fn test_cast_value(
+    #[case] row: Row,
+    #[case] value: Value,
+    #[case] expected: Value,
+  ) {
+    assert_eq!(row.cast_value(&value), Ok(expected));
+  }
+
+  #[rstest]
+  #[case(Row::Integer(BTreeMap::new()), Value::Text("abc".to_string()))]
+  #[case(Row::Integer(BTreeMap::new()), Value::Real(1.5))]
+  #[case(Row::Bool(BTreeMap::new()), Value::Integer(1))]
+  #[case(Row::Date(BTreeMap::new()), Value::Text("2023-02-29".to_string()))]
+  #[case(Row::Integer(BTreeMap::new()), Value::Decimal("1.5".parse::<Decimal>().unwrap()))]
+  #[case(Row::Text(BTreeMap::new()), Value::Blob(vec![0xff]))]
+  #[case(Row::Blob(BTreeMap::new()), Value::Text("abc".to_string()))]
+  fn test_cast_value_type_mismatch(
+    #[case] row: Row,
+    #[case] value: Value,
+  ) {
+    assert!(row.cast_value(&value).is_err());
+  }
+
+  #[test]
+  fn test_set_and_move_value() {
+    let mut row = Row::Integer(BTreeMap::new());
+    row.set_value(1, &Value::Integer(10)).unwrap();
+    row.move_value(1, 5);
+
+    assert_eq!(row.get_value(&1), Value::Null);
+    assert_eq!(row.get_value(&5), Value::Integer(10));
+
+    assert_eq!(row.remove_value(&5), Value::Integer(10));
+    assert_eq!(row.get_number_of_element_in_column(), 0);
+  }
+
+  #[test]
+  fn test_set_null_value() {
+    let mut row = Row::Text(BTreeMap::new());
+    row.set_value(1, &Value::Text("Null".to_string())).unwrap();
+    row.set_value(2, &Value::Null).unwrap();
+
+    assert_eq!(row.get_row_ids(), vec![1, 2]);
+    assert_eq!(row.get_value(&1), Value::Text("Null".to_string()));
+    assert_eq!(row.get_value(&2), Value::Null);
+    assert_eq!(row.get_values(), vec![Value::Text("Null".to_string()), Value::Null]);
+  }
 }
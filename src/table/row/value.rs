@@ -0,0 +1,228 @@
+use std::fmt;
+use std::cmp::Ordering;
+
+use prettytable::Cell as PrintCell;
+
+use crate::error::{Result, NollaDBError};
+use super::date_time::{Date, Time, Timestamp};
+use super::decimal::Decimal;
+use super::json::Json;
+
+// Value 表示某一行某一列上的单个值
+// Row 里面存的是一整列的数据，而 Value 是从 Row 里面按照 row id 取出来的那一个值
+#[derive(PartialEq, Debug, Clone)]
+pub enum Value {
+  Integer(i64),
+  Text(String),
+  Bool(bool),
+  Real(f64),
+  Blob(Vec<u8>),
+  Date(Date),
+  Time(Time),
+  Timestamp(Timestamp),
+  Decimal(Decimal),
+  Json(Json),
+  Null,
+}
+
+impl Value {
+  pub fn is_null(&self) -> bool {
+    matches!(self, Value::Null)
+  }
+
+  // 比较两个值的大小
+  // 只要有一边是 NULL，结果就是未知，返回 None
+  // Integer、Real 和 Decimal 之间可以互相比较，Date 和 Timestamp 之间可以互相比较
+  // Date、Time、Timestamp 以及 Decimal 和 Text 比较的时候，先把 Text 解析成对应的类型，这样可以直接写 d = '2024-01-01'
+  // JSON 中的标量按照对应的 SQL 值比较，所以可以直接写 payload -> '$.a' = 1
+  // JSON 的数组和对象只能和 JSON 或者能解析成 JSON 的 Text 比较，按照紧凑格式的文本比较
+  // 其他类型只能和同类型比较
+  pub fn compare(&self, other: &Value) -> Result<Option<Ordering>> {
+    match (self, other) {
+      (Value::Null, _) | (_, Value::Null) => Ok(None),
+      (Value::Json(a), _) if !a.is_container() => a.get_sql_value().compare(other),
+      (_, Value::Json(b)) if !b.is_container() => self.compare(&b.get_sql_value()),
+      (Value::Json(a), Value::Json(b)) => Ok(Some(a.cmp(b))),
+      (Value::Integer(a), Value::Integer(b)) => Ok(Some(a.cmp(b))),
+      (Value::Integer(a), Value::Real(b)) => Ok((*a as f64).partial_cmp(b)),
+      (Value::Real(a), Value::Integer(b)) => Ok(a.partial_cmp(&(*b as f64))),
+      (Value::Real(a), Value::Real(b)) => Ok(a.partial_cmp(b)),
+      (Value::Decimal(a), Value::Decimal(b)) => Ok(Some(a.cmp(b))),
+      (Value::Decimal(a), Value::Integer(b)) => Ok(Some(a.cmp(&Decimal::from(*b)))),
+      // Real 能精确转换成 Decimal 的话按照 Decimal 比较，所以 1.1 和 DECIMAL 的 1.1 相等
+      (Value::Decimal(a), Value::Real(b)) => match Decimal::from_real(*b) {
+        Some(b) => Ok(Some(a.cmp(&b))),
+        None => Ok(a.to_real().partial_cmp(b)),
+      },
+      (Value::Integer(_) | Value::Real(_), Value::Decimal(_)) => Ok(other.compare(self)?.map(Ordering::reverse)),
+      (Value::Text(a), Value::Text(b)) => Ok(Some(a.cmp(b))),
+      (Value::Bool(a), Value::Bool(b)) => Ok(Some(a.cmp(b))),
+      (Value::Blob(a), Value::Blob(b)) => Ok(Some(a.cmp(b))),
+      (Value::Date(a), Value::Date(b)) => Ok(Some(a.cmp(b))),
+      (Value::Time(a), Value::Time(b)) => Ok(Some(a.cmp(b))),
+      (Value::Timestamp(a), Value::Timestamp(b)) => Ok(Some(a.cmp(b))),
+      (Value::Date(a), Value::Timestamp(b)) => Ok(Some(Timestamp::from(*a).cmp(b))),
+      (Value::Timestamp(a), Value::Date(b)) => Ok(Some(a.cmp(&Timestamp::from(*b)))),
+      (Value::Date(_) | Value::Time(_) | Value::Timestamp(_) | Value::Decimal(_) | Value::Json(_), Value::Text(s)) => {
+        match self.parse_text_as_same_type(s) {
+          Some(value) => self.compare(&value),
+          None => Err(self.get_compare_type_mismatch_error(other)),
+        }
+      },
+      (Value::Text(_), Value::Date(_) | Value::Time(_) | Value::Timestamp(_) | Value::Decimal(_) | Value::Json(_)) => {
+        Ok(other.compare(self)?.map(Ordering::reverse))
+      },
+      _ => Err(self.get_compare_type_mismatch_error(other)),
+    }
+  }
+
+  // 把 Text 解析成和自己相同的类型，解析不了的话返回 None
+  fn parse_text_as_same_type(&self, s: &str) -> Option<Value> {
+    match self {
+      Value::Date(_) => s.parse::<Date>().ok().map(Value::Date),
+      Value::Time(_) => s.parse::<Time>().ok().map(Value::Time),
+      Value::Timestamp(_) => s.parse::<Timestamp>().ok().map(Value::Timestamp),
+      Value::Decimal(_) => s.parse::<Decimal>().ok().map(Value::Decimal),
+      Value::Json(_) => s.parse::<Json>().ok().map(Value::Json),
+      _ => None,
+    }
+  }
+
+  fn get_compare_type_mismatch_error(&self, other: &Value) -> NollaDBError {
+    NollaDBError::TypeMismatch(
+      format!(
+        "Can not compare {} value '{}' with {} value '{}'",
+        self.get_type_name(), self, other.get_type_name(), other
+      )
+    )
+  }
+
+  // ORDER BY 时用到的比较，返回的是一个全序
+  // NULL 最小，然后依次是数字（Integer、Real 和 Decimal）、Boolean、Date 和 Timestamp、Time、Text、Blob，最后是 JSON 的数组和对象
+  pub fn compare_for_order_by(&self, other: &Value) -> Ordering {
+    match self.compare(other) {
+      Ok(Some(ordering)) => ordering,
+      _ => self.get_order_by_rank().cmp(&other.get_order_by_rank()),
+    }
+  }
+
+  fn get_order_by_rank(&self) -> u8 {
+    match self {
+      Value::Null => 0,
+      Value::Integer(_) | Value::Real(_) | Value::Decimal(_) => 1,
+      Value::Bool(_) => 2,
+      Value::Date(_) | Value::Timestamp(_) => 3,
+      Value::Time(_) => 4,
+      Value::Text(_) => 5,
+      Value::Blob(_) => 6,
+      Value::Json(json) => match json.get_sql_value() {
+        Value::Json(_) => 7,
+        value => value.get_order_by_rank(),
+      },
+    }
+  }
+
+  // 打印表格时对应的单元格，NULL 用斜体显示，和字符串 'NULL' 区分开
+  pub fn get_print_cell(&self) -> PrintCell {
+    match self {
+      Value::Null => PrintCell::new("NULL").style_spec("i"),
+      value => PrintCell::new(&value.to_string()),
+    }
+  }
+
+  pub fn get_type_name(&self) -> &'static str {
+    match self {
+      Value::Integer(_) => "Integer",
+      Value::Text(_) => "Text",
+      Value::Bool(_) => "Boolean",
+      Value::Real(_) => "Real",
+      Value::Blob(_) => "Blob",
+      Value::Date(_) => "Date",
+      Value::Time(_) => "Time",
+      Value::Timestamp(_) => "Timestamp",
+      Value::Decimal(_) => "Decimal",
+      Value::Json(_) => "Json",
+      Value::Null => "Null",
+    }
+  }
+}
+
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Value::Integer(i) => write!(f, "{}", i),
+      Value::Text(s) => write!(f, "{}", s),
+      Value::Bool(b) => write!(f, "{}", b),
+      Value::Real(r) => write!(f, "{}", r),
+      // 和字面量的写法一样，X'0A1B'
+      Value::Blob(bytes) => write!(
+        f,
+        "X'{}'",
+        bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<String>()
+      ),
+      Value::Date(d) => write!(f, "{}", d),
+      Value::Time(t) => write!(f, "{}", t),
+      Value::Timestamp(ts) => write!(f, "{}", ts),
+      Value::Decimal(d) => write!(f, "{}", d),
+      Value::Json(json) => write!(f, "{}", json),
+      Value::Null => f.write_str("NULL"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use rstest::rstest;
+  use pretty_assertions::assert_eq;
+
+  #[rstest]
+  #[case(Value::Integer(1), "1")]
+  #[case(Value::Text("xxx".to_string()), "xxx")]
+  #[case(Value::Bool(true), "true")]
+  #[case(Value::Real(1.5), "1.5")]
+  #[case(Value::Null, "NULL")]
+  fn test_display_value(
+    #[case] value: Value,
+    #[case] expected: &str,
+  ) {
+    assert_eq!(format!("{}", value), expected);
+  }
+
+  #[rstest]
+  #[case(Value::Integer(1), Value::Integer(2), Some(Ordering::Less))]
+  #[case(Value::Integer(2), Value::Real(1.5), Some(Ordering::Greater))]
+  #[case(Value::Text("a".to_string()), Value::Text("a".to_string()), Some(Ordering::Equal))]
+  #[case(Value::Bool(false), Value::Bool(true), Some(Ordering::Less))]
+  #[case(Value::Null, Value::Integer(1), None)]
+  fn test_compare_value(
+    #[case] left: Value,
+    #[case] right: Value,
+    #[case] expected: Option<Ordering>,
+  ) {
+    assert_eq!(left.compare(&right), Ok(expected));
+  }
+
+  #[rstest]
+  #[case(Value::Null, Value::Integer(1), Ordering::Less)]
+  #[case(Value::Real(2.5), Value::Integer(2), Ordering::Greater)]
+  #[case(Value::Text("1".to_string()), Value::Integer(2), Ordering::Greater)]
+  #[case(Value::Null, Value::Null, Ordering::Equal)]
+  fn test_compare_value_for_order_by(
+    #[case] left: Value,
+    #[case] right: Value,
+    #[case] expected: Ordering,
+  ) {
+    assert_eq!(left.compare_for_order_by(&right), expected);
+  }
+
+  #[rstest]
+  #[case(Value::Integer(1), Value::Text("1".to_string()))]
+  #[case(Value::Bool(true), Value::Real(1.0))]
+  fn test_compare_value_type_mismatch(
+    #[case] left: Value,
+    #[case] right: Value,
+  ) {
+    assert!(left.compare(&right).is_err());
+  }
+}
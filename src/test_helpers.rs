@@ -0,0 +1,24 @@
+use sqlparser::ast::Expr;
+use sqlparser::dialect::SQLiteDialect;
+use sqlparser::parser::Parser;
+
+use crate::database::Database;
+use crate::sql_query::{handle_sql_query, tokenize_sql_query};
+
+// 测试中共用的辅助函数
+
+// 和执行 SQL 时一样先经过 tokenize_sql_query，所以也支持 -> 和 ->>
+pub fn parse_expression(input: &str) -> Expr {
+  let dialect = SQLiteDialect {};
+  let tokens = tokenize_sql_query(input).unwrap();
+  Parser::new(tokens, &dialect).parse_expr().unwrap()
+}
+
+// 依次执行 queries，返回名为 testdb 的数据库
+pub fn create_test_database(queries: &[&str]) -> Database {
+  let mut database = Database::new("testdb".to_string());
+  for query in queries {
+    handle_sql_query(query, &mut database).unwrap();
+  }
+  database
+}